@@ -0,0 +1,305 @@
+//! The command line interface of the `glacio` binary.
+//!
+//! The app is built in code, rather than loaded from YAML, so the `completions` subcommand can
+//! generate shell completions from the same definition that parses the arguments.
+
+use clap::{App, AppSettings, Arg, Shell, SubCommand};
+use glacio;
+
+/// The name of the binary, as used in shell completions.
+pub const BIN_NAME: &'static str = "glacio";
+
+/// Returns the `glacio` app, with every subcommand and argument.
+pub fn app() -> App<'static, 'static> {
+    App::new(BIN_NAME)
+        .author("Pete Gadomski <pete@gadom.ski>")
+        .version(glacio::VERSION)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .global(true)
+                .help("Write data to this file instead of standard output."),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .global(true)
+                .conflicts_with("verbose")
+                .help("Only print errors to standard error."),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .global(true)
+                .help("Print more log messages to standard error. Repeat for even more."),
+        )
+        .subcommand(api())
+        .subcommand(heartbeats())
+        .subcommand(atlas())
+        .subcommand(camera())
+        .subcommand(completions())
+}
+
+fn api() -> App<'static, 'static> {
+    SubCommand::with_name("api")
+        .about("Start the glacio api.")
+        .arg(
+            Arg::with_name("CONFIG")
+                .help("The configuration toml file.")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("ADDR")
+                .help("The address on which to start the server.")
+                .required(true)
+                .index(2),
+        )
+        .arg(Arg::with_name("watch").long("watch").help(
+            "Reload the configuration when the file changes (requires the `watch` feature).",
+        ))
+}
+
+fn heartbeats() -> App<'static, 'static> {
+    SubCommand::with_name("heartbeats")
+        .about("Print all ATLAS heartbeats.")
+        .arg(
+            Arg::with_name("CONFIG")
+                .help("The configuration toml file.")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["json", "influx"])
+                .default_value("json")
+                .help("The output format, either a JSON array or InfluxDB line protocol."),
+        )
+        .arg(
+            Arg::with_name("site")
+                .long("site")
+                .takes_value(true)
+                .default_value("atlas")
+                .help("The site tag for InfluxDB line protocol output."),
+        )
+}
+
+fn atlas() -> App<'static, 'static> {
+    SubCommand::with_name("atlas")
+        .about("Work with ATLAS heartbeats.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("watch")
+                .about("Poll an SBD root for new heartbeats and notify on each one.")
+                .arg(sbd_root())
+                .arg(
+                    Arg::with_name("imei")
+                        .long("imei")
+                        .takes_value(true)
+                        .help("Only watch messages from this IMEI."),
+                )
+                .arg(
+                    Arg::with_name("webhook")
+                        .long("webhook")
+                        .takes_value(true)
+                        .help(
+                            "POST each heartbeat, as JSON, to this url. If not provided, \
+                             heartbeats are printed.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("secret")
+                        .long("secret")
+                        .takes_value(true)
+                        .requires("webhook")
+                        .help("Shared secret to send in the webhook's X-Glacio-Secret header."),
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .takes_value(true)
+                        .default_value("60")
+                        .help("Seconds to wait between polls."),
+                )
+                .arg(Arg::with_name("all").long("all").help(
+                    "Notify on the heartbeats that are already in the root, not just new ones.",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("momsn-gaps")
+                .about(
+                    "Print gaps in the MOMSN sequences of an SBD root, which mean Iridium \
+                     dropped messages.",
+                )
+                .arg(sbd_root())
+                .arg(
+                    Arg::with_name("imei")
+                        .long("imei")
+                        .takes_value(true)
+                        .help("Only check messages from this IMEI."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about(
+                    "Print one JSON document with a site's recent heartbeats, reception, \
+                     battery and EFOY trends, and bad heartbeats.",
+                )
+                .arg(sbd_root())
+                .arg(
+                    Arg::with_name("site")
+                        .long("site")
+                        .takes_value(true)
+                        .help(
+                            "The IMEI of the site's modem. If not provided, every IMEI in the \
+                             root is exported together.",
+                        ),
+                )
+                .arg(since(
+                    "Only export messages received at or after this datetime, e.g. 2017-08-06 \
+                     or -7d.",
+                ))
+                .arg(
+                    Arg::with_name("last")
+                        .long("last")
+                        .takes_value(true)
+                        .default_value("24")
+                        .help("The number of recent heartbeats to include."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("annotate")
+                .about(
+                    "Print every field of a raw heartbeat with its byte offset, hex, and parsed \
+                     value, to find where a bad heartbeat stops matching the format.",
+                )
+                .arg(
+                    Arg::with_name("FILE")
+                        .help("A file with one reassembled heartbeat, starting with ATHB.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(Arg::with_name("json").long("json").help(
+                    "Print the annotations as a JSON array instead of aligned text.",
+                )),
+        )
+}
+
+fn camera() -> App<'static, 'static> {
+    SubCommand::with_name("camera")
+        .about("Work with camera images.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("doctor")
+                .about(
+                    "Report files in a camera directory that aren't named \
+                     NAME_%Y%m%d_%H%M%S.jpg or NAME-%Y%m%d-%H%M%S.jpg, and guess what they \
+                     should be named.",
+                )
+                .arg(
+                    Arg::with_name("DIR")
+                        .help("The camera directory. Its name is the camera name.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(Arg::with_name("fix").long("fix").help(
+                    "Print the renames that would fix the misnamed files.",
+                ))
+                .arg(Arg::with_name("dry-run").long("dry-run").requires("fix").help(
+                    "Only print the renames, without renaming anything. This is the default.",
+                ))
+                .arg(
+                    Arg::with_name("apply")
+                        .long("apply")
+                        .requires("fix")
+                        .conflicts_with("dry-run")
+                        .help("Actually rename the files, instead of just printing the renames."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("images")
+                .about(
+                    "Print one row for every image under a root, with its camera, subpath, \
+                     datetime, and file size.",
+                )
+                .arg(
+                    Arg::with_name("ROOT")
+                        .help(
+                            "The root directory. Every directory under it is a camera, keyed \
+                             by its path relative to the root.",
+                        )
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("camera")
+                        .long("camera")
+                        .takes_value(true)
+                        .help("Only list images from this camera, e.g. HEL_BERGCAM3/StarDot1."),
+                )
+                .arg(since(
+                    "Only list images taken at or after this datetime, e.g. 2017-08-06 or -7d.",
+                ))
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["csv", "jsonl"])
+                        .default_value("csv")
+                        .help(
+                            "The output format, either CSV with a header row or one JSON \
+                             object per line.",
+                        ),
+                ),
+        )
+}
+
+fn completions() -> App<'static, 'static> {
+    SubCommand::with_name("completions")
+        .about("Print a shell completion script for glacio to standard output.")
+        .arg(
+            Arg::with_name("SHELL")
+                .help("The shell to generate completions for.")
+                .required(true)
+                .possible_values(&Shell::variants())
+                .index(1),
+        )
+}
+
+fn sbd_root() -> Arg<'static, 'static> {
+    Arg::with_name("ROOT")
+        .help("The root of the SBD storage.")
+        .required(true)
+        .index(1)
+}
+
+fn since(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("since").long("since").takes_value(true).help(help)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bash_completions() {
+        let mut buf = Vec::new();
+        app().gen_completions_to(BIN_NAME, Shell::Bash, &mut buf);
+        let completions = String::from_utf8(buf).unwrap();
+        for subcommand in &["annotate", "export", "completions"] {
+            assert!(
+                completions.contains(subcommand),
+                "missing {} in the bash completions",
+                subcommand
+            );
+        }
+    }
+}
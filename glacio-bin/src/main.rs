@@ -1,23 +1,58 @@
 #[macro_use]
 extern crate clap;
 extern crate env_logger;
+extern crate glacio;
 extern crate glacio_http;
+#[cfg(feature = "tls")]
+extern crate hyper_native_tls;
 extern crate iron;
+#[macro_use]
+extern crate log;
+#[cfg(feature = "watch")]
+extern crate notify;
 extern crate serde_json;
 
+mod cli;
+mod output;
+
+use glacio::atlas::{Error, Heartbeat, Result, heartbeat_rate};
+use glacio::atlas::notify::Notifier;
+use glacio_http::{Api, TlsConfig};
+use output::Output;
+use std::io::Write;
+
 fn main() {
-    use glacio_http::{Api, Config};
+    use glacio_http::Config;
     use iron::Iron;
-    use clap::App;
 
-    env_logger::init().unwrap();
-    let yaml = load_yaml!("cli.yml");
-    let matches = App::from_yaml(yaml).get_matches();
+    let matches = cli::app().get_matches();
+    let mut out = {
+        let mut leaf = &matches;
+        while let (_, Some(matches)) = leaf.subcommand() {
+            leaf = matches;
+        }
+        Output::from_matches(leaf).unwrap_or_else(|err| {
+            eprintln!("Could not open the output file: {}", err);
+            std::process::exit(1);
+        })
+    };
+    out.verbosity().init_logger();
     if let Some(matches) = matches.subcommand_matches("api") {
-        let api = Api::from_path(matches.value_of("CONFIG").unwrap()).unwrap();
+        let path = matches.value_of("CONFIG").unwrap();
+        let config = Config::from_path(path).unwrap();
+        let tls = config.tls.clone();
+        let api = Api::new(config).unwrap();
+        if matches.is_present("watch") {
+            watch(api.clone(), path, &out);
+        }
         let addr = matches.value_of("ADDR").unwrap();
-        println!("Serving glacio api on http://{}", addr);
-        Iron::new(api).http(addr).unwrap();
+        match tls {
+            Some(ref tls) => serve_https(api, addr, tls, &out),
+            None => {
+                out.diagnostic(format_args!("Serving glacio api on http://{}", addr));
+                Iron::new(api).http(addr).unwrap();
+            }
+        }
     } else if let Some(matches) = matches.subcommand_matches("heartbeats") {
         let config = Config::from_path(matches.value_of("CONFIG").unwrap()).unwrap();
         let heartbeats = config
@@ -26,6 +61,336 @@ fn main() {
             .unwrap()
             .filter_map(|heartbeat| heartbeat.ok())
             .collect::<Vec<_>>();
-        println!("{}", serde_json::to_string(&heartbeats).unwrap());
+        if let Some(rate) = heartbeat_rate(&heartbeats) {
+            out.diagnostic(format!(
+                "{} heartbeats, sent about every {} minutes",
+                heartbeats.len(),
+                rate.num_minutes()
+            ));
+        }
+        if matches.value_of("format") == Some("influx") {
+            use glacio::atlas::influx;
+            let site = matches.value_of("site").unwrap();
+            for heartbeat in &heartbeats {
+                write!(out, "{}", influx::to_line_protocol(heartbeat, site)).unwrap();
+            }
+        } else {
+            writeln!(out, "{}", serde_json::to_string(&heartbeats).unwrap()).unwrap();
+        }
+    } else if let Some(matches) = matches.subcommand_matches("atlas") {
+        if let Some(matches) = matches.subcommand_matches("watch") {
+            atlas_watch(matches, &mut out);
+        } else if let Some(matches) = matches.subcommand_matches("momsn-gaps") {
+            atlas_momsn_gaps(matches, &mut out);
+        } else if let Some(matches) = matches.subcommand_matches("export") {
+            atlas_export(matches, &mut out);
+        } else if let Some(matches) = matches.subcommand_matches("annotate") {
+            atlas_annotate(matches, &mut out);
+        }
+    } else if let Some(matches) = matches.subcommand_matches("camera") {
+        if let Some(matches) = matches.subcommand_matches("doctor") {
+            camera_doctor(matches, &mut out);
+        } else if let Some(matches) = matches.subcommand_matches("images") {
+            camera_images(matches, &mut out);
+        }
+    } else if let Some(matches) = matches.subcommand_matches("completions") {
+        let shell = value_t!(matches, "SHELL", clap::Shell).unwrap_or_else(|e| e.exit());
+        cli::app().gen_completions_to(cli::BIN_NAME, shell, &mut out);
+    }
+}
+
+/// Serves the api over HTTPS.
+#[cfg(feature = "tls")]
+fn serve_https(api: Api, addr: &str, tls: &TlsConfig, out: &Output) {
+    use hyper_native_tls::NativeTlsServer;
+    use iron::Iron;
+
+    let ssl = NativeTlsServer::new(&tls.identity_file, &tls.password).unwrap();
+    out.diagnostic(format_args!("Serving glacio api on https://{}", addr));
+    Iron::new(api).https(addr, ssl).unwrap();
+}
+
+/// Refuses to serve, rather than falling back to plain HTTP when HTTPS was asked for.
+#[cfg(not(feature = "tls"))]
+fn serve_https(_: Api, _: &str, _: &TlsConfig, _: &Output) {
+    eprintln!("glacio was built without the `tls` feature, so it can't serve the [tls] config");
+    std::process::exit(1);
+}
+
+/// Polls an SBD root forever, notifying on each new heartbeat.
+fn atlas_watch(matches: &clap::ArgMatches, out: &mut Output) {
+    use glacio::atlas::SbdSource;
+    use glacio::atlas::notify::{Poller, WebhookNotifier};
+    use std::thread;
+    use std::time::Duration;
+
+    let mut source = SbdSource::new(matches.value_of("ROOT").unwrap());
+    if let Some(imei) = matches.value_of("imei") {
+        source = source.imeis(&[imei]);
+    }
+    let interval = value_t!(matches, "interval", u64).unwrap_or_else(|e| e.exit());
+    let mut poller = Poller::new(source);
+    if !matches.is_present("all") {
+        poller.poll(&mut Quiet).unwrap();
+    }
+    let mut webhook = matches.value_of("webhook").map(|url| {
+        let mut webhook = WebhookNotifier::new(url);
+        if let Some(secret) = matches.value_of("secret") {
+            webhook = webhook.secret(secret);
+        }
+        webhook
+    });
+    let mut print = Print(out);
+    let notifier: &mut Notifier = match webhook {
+        Some(ref mut webhook) => webhook,
+        None => &mut print,
+    };
+    loop {
+        if let Err(err) = poller.poll(notifier) {
+            error!("Error while polling for heartbeats: {}", err);
+        }
+        thread::sleep(Duration::from_secs(interval));
+    }
+}
+
+/// Prints each MOMSN gap in an SBD root.
+fn atlas_momsn_gaps(matches: &clap::ArgMatches, out: &mut Output) {
+    use glacio::atlas::SbdSource;
+
+    let mut source = SbdSource::new(matches.value_of("ROOT").unwrap());
+    if let Some(imei) = matches.value_of("imei") {
+        source = source.imeis(&[imei]);
+    }
+    for gap in source.momsn_gaps().unwrap() {
+        writeln!(
+            out,
+            "{} {}: {} -> {} ({} missing)",
+            gap.datetime,
+            gap.imei,
+            gap.before,
+            gap.after,
+            gap.missing()
+        ).unwrap();
+    }
+}
+
+/// Prints, or writes to a file, a combined status document for one site.
+fn atlas_export(matches: &clap::ArgMatches, out: &mut Output) {
+    use glacio::atlas::export::Export;
+    use glacio_http::param;
+    use std::process;
+
+    let mut export = Export::new(matches.value_of("ROOT").unwrap())
+        .last(value_t!(matches, "last", usize).unwrap_or_else(|e| e.exit()));
+    if let Some(imei) = matches.value_of("site") {
+        export = export.imei(imei);
+    }
+    if let Some(since) = matches.value_of("since") {
+        match param::parse_datetime(since) {
+            Some(since) => export = export.since(since),
+            None => {
+                eprintln!("invalid --since, expected {}", param::DATETIME_EXPECTED);
+                process::exit(1);
+            }
+        }
+    }
+    let export = export.build().unwrap();
+    writeln!(out, "{}", serde_json::to_string_pretty(&export).unwrap()).unwrap();
+}
+
+/// Prints every field of a raw heartbeat file with its byte range and parsed value.
+///
+/// Long fields only show their first few bytes of hex.
+fn atlas_annotate(matches: &clap::ArgMatches, out: &mut Output) {
+    use glacio::atlas::annotate;
+    use std::fs::File;
+    use std::io::Read;
+
+    const HEX_BYTES: usize = 8;
+
+    let mut bytes = Vec::new();
+    File::open(matches.value_of("FILE").unwrap())
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .unwrap();
+    let annotations = annotate::annotate(&bytes);
+    if matches.is_present("json") {
+        writeln!(out, "{}", serde_json::to_string_pretty(&annotations).unwrap()).unwrap();
+        return;
     }
+    for annotation in annotations {
+        let field = &bytes[annotation.offset..annotation.offset + annotation.len];
+        let mut hex = field
+            .iter()
+            .take(HEX_BYTES)
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if field.len() > HEX_BYTES {
+            hex.push_str(" ..");
+        }
+        let value = match (annotation.parsed_value, annotation.error) {
+            (_, Some(error)) => format!("error: {}", error),
+            (Some(value), None) => value,
+            (None, None) => String::new(),
+        };
+        writeln!(
+            out,
+            "{:>5}  {:<26}  {:<16}  {}",
+            annotation.offset,
+            hex,
+            annotation.label,
+            value
+        ).unwrap();
+    }
+}
+
+/// Reports, and optionally renames, misnamed files in a camera directory.
+///
+/// Files are never renamed on top of existing files.
+fn camera_doctor(matches: &clap::ArgMatches, out: &mut Output) {
+    use glacio::camera::repair;
+    use std::fs;
+
+    let fix = matches.is_present("fix");
+    let apply = matches.is_present("apply");
+    for diagnosis in repair::diagnose(matches.value_of("DIR").unwrap()).unwrap() {
+        let path = diagnosis.path.display();
+        match diagnosis.suggestion {
+            Some(ref suggestion) if fix => {
+                if suggestion.exists() {
+                    out.diagnostic(format_args!(
+                        "{}: {} already exists, skipping",
+                        path,
+                        suggestion.display()
+                    ));
+                } else if apply {
+                    match fs::rename(&diagnosis.path, suggestion) {
+                        Ok(()) => {
+                            writeln!(out, "renamed {} -> {}", path, suggestion.display()).unwrap()
+                        }
+                        Err(err) => error!("{}: could not rename: {}", path, err),
+                    }
+                } else {
+                    writeln!(out, "would rename {} -> {}", path, suggestion.display()).unwrap();
+                }
+            }
+            Some(ref suggestion) => {
+                writeln!(out, "{}: should be {}", path, suggestion.display()).unwrap()
+            }
+            None => writeln!(out, "{}: no datetime found", path).unwrap(),
+        }
+    }
+}
+
+/// Prints a row for every image under a root, one at a time.
+///
+/// Images that can't be read are reported on standard error and skipped.
+fn camera_images(matches: &clap::ArgMatches, out: &mut Output) {
+    use glacio::camera::listing::{self, Listing};
+    use glacio_http::param;
+    use std::process;
+
+    let mut listing = Listing::new(matches.value_of("ROOT").unwrap());
+    if let Some(camera) = matches.value_of("camera") {
+        listing = listing.camera(camera);
+    }
+    if let Some(since) = matches.value_of("since") {
+        match param::parse_datetime(since) {
+            Some(since) => listing = listing.since(since),
+            None => {
+                eprintln!("invalid --since, expected {}", param::DATETIME_EXPECTED);
+                process::exit(1);
+            }
+        }
+    }
+    let jsonl = matches.value_of("format") == Some("jsonl");
+    if !jsonl {
+        writeln!(out, "{}", listing::CSV_HEADER).unwrap();
+    }
+    for result in listing.iter().unwrap() {
+        match result {
+            Ok(ref row) if jsonl => {
+                writeln!(out, "{}", serde_json::to_string(row).unwrap()).unwrap()
+            }
+            Ok(row) => writeln!(out, "{}", row.to_csv()).unwrap(),
+            Err(err) => out.diagnostic(format_args!("Skipping image: {}", err)),
+        }
+    }
+}
+
+/// Swallows notifications, used to skip past the heartbeats that are already on disk.
+struct Quiet;
+
+/// Writes notifications to the output, flushing after each heartbeat.
+struct Print<'a>(&'a mut Output);
+
+impl Notifier for Quiet {
+    fn on_heartbeat(&mut self, _: &Heartbeat) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_bad_heartbeat(&mut self, _: &Error) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> Notifier for Print<'a> {
+    fn on_heartbeat(&mut self, heartbeat: &Heartbeat) -> Result<()> {
+        writeln!(self.0, "{}", serde_json::to_string(heartbeat).unwrap()).unwrap();
+        self.0.flush().unwrap();
+        Ok(())
+    }
+
+    fn on_bad_heartbeat(&mut self, error: &Error) -> Result<()> {
+        self.0.diagnostic(format_args!("Bad heartbeat: {}", error));
+        Ok(())
+    }
+}
+
+/// Reloads the api's configuration, in a background thread, whenever the config file changes.
+///
+/// The config file's directory is watched, rather than the file itself, because many editors save
+/// by replacing the file. Reloads are logged, since the watching thread can't share the output.
+#[cfg(feature = "watch")]
+fn watch(api: Api, path: &str, out: &Output) {
+    use notify::{DebouncedEvent, RecursiveMode, Watcher};
+    use std::path::Path;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    let path = Path::new(path).canonicalize().unwrap();
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::watcher(tx, Duration::from_secs(1)).unwrap();
+    watcher
+        .watch(path.parent().unwrap(), RecursiveMode::NonRecursive)
+        .unwrap();
+    out.diagnostic(format_args!("Watching {} for changes", path.display()));
+    thread::spawn(move || {
+        let _watcher = watcher;
+        for event in rx {
+            match event {
+                DebouncedEvent::Create(ref changed) |
+                DebouncedEvent::Write(ref changed) |
+                DebouncedEvent::Rename(_, ref changed) if *changed == path => {
+                    match api.reload_from_path(&path) {
+                        Ok(()) => info!("Reloaded configuration from {}", path.display()),
+                        Err(err) => {
+                            error!(
+                                "Could not reload configuration from {}: {}",
+                                path.display(),
+                                err
+                            )
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+#[cfg(not(feature = "watch"))]
+fn watch(_: Api, _: &str, _: &Output) {
+    eprintln!("glacio was built without the `watch` feature, --watch is ignored");
 }
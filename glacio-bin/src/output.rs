@@ -0,0 +1,184 @@
+//! Where the binary's data and diagnostics go.
+//!
+//! Data, e.g. heartbeats as JSON, goes to standard output or to the `--output` file, and nothing
+//! else is ever written there. Diagnostics and log lines go to standard error, so piping the data
+//! somewhere is always safe. `--quiet` and `--verbose` only change how much goes to standard
+//! error.
+//!
+//! If the data's reader goes away, e.g. when it's piped into `head`, the binary exits quietly
+//! instead of panicking on the failed write.
+
+use clap::ArgMatches;
+use env_logger::LogBuilder;
+use log::LogLevelFilter;
+use std::env;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, ErrorKind, Write};
+use std::process;
+
+/// How much the binary should say on standard error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verbosity {
+    /// Only errors.
+    Quiet,
+    /// Warnings, errors, and diagnostics.
+    Normal,
+    /// More log output for each `-v`.
+    Verbose(u64),
+}
+
+/// The data sink and the verbosity for one run of the binary.
+pub struct Output {
+    data: Box<Write>,
+    verbosity: Verbosity,
+}
+
+impl Verbosity {
+    /// Reads the verbosity from the `quiet` and `verbose` flags.
+    pub fn from_matches(matches: &ArgMatches) -> Verbosity {
+        if matches.is_present("quiet") {
+            Verbosity::Quiet
+        } else {
+            match matches.occurrences_of("verbose") {
+                0 => Verbosity::Normal,
+                n => Verbosity::Verbose(n),
+            }
+        }
+    }
+
+    /// Returns the most detailed log level that should be shown.
+    pub fn log_level(&self) -> LogLevelFilter {
+        match *self {
+            Verbosity::Quiet => LogLevelFilter::Error,
+            Verbosity::Normal => LogLevelFilter::Warn,
+            Verbosity::Verbose(1) => LogLevelFilter::Info,
+            Verbosity::Verbose(2) => LogLevelFilter::Debug,
+            Verbosity::Verbose(_) => LogLevelFilter::Trace,
+        }
+    }
+
+    /// Returns true if diagnostics, e.g. skipped files, should be printed.
+    pub fn shows_diagnostics(&self) -> bool {
+        *self != Verbosity::Quiet
+    }
+
+    /// Starts the logger at this verbosity.
+    ///
+    /// `RUST_LOG`, if set, is applied on top, so a single module can still be turned up.
+    pub fn init_logger(&self) {
+        let mut builder = LogBuilder::new();
+        builder.filter(None, self.log_level());
+        if let Ok(rust_log) = env::var("RUST_LOG") {
+            builder.parse(&rust_log);
+        }
+        builder.init().unwrap();
+    }
+}
+
+impl Output {
+    /// Creates an output from the `output`, `quiet`, and `verbose` flags.
+    ///
+    /// The output file is created (or truncated) right away.
+    pub fn from_matches(matches: &ArgMatches) -> io::Result<Output> {
+        let data: Box<Write> = match matches.value_of("output") {
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+            None => Box::new(io::stdout()),
+        };
+        Ok(Output {
+            data: data,
+            verbosity: Verbosity::from_matches(matches),
+        })
+    }
+
+    /// Returns this output's verbosity.
+    pub fn verbosity(&self) -> Verbosity {
+        self.verbosity
+    }
+
+    /// Prints a diagnostic to standard error, unless we're being quiet.
+    pub fn diagnostic<D: Display>(&self, message: D) {
+        if self.verbosity.shows_diagnostics() {
+            eprintln!("{}", message);
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        exit_on_broken_pipe(self.data.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        exit_on_broken_pipe(self.data.flush())
+    }
+}
+
+/// Exits the process, successfully, if the result is a broken pipe error.
+///
+/// Any other result is passed through untouched.
+fn exit_on_broken_pipe<T>(result: io::Result<T>) -> io::Result<T> {
+    match result {
+        Err(ref err) if err.kind() == ErrorKind::BrokenPipe => process::exit(0),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cli;
+
+    fn verbosity(args: &[&str]) -> Verbosity {
+        let mut argv = vec!["glacio"];
+        argv.extend(args);
+        argv.extend(&["heartbeats", "config.toml"]);
+        Verbosity::from_matches(&cli::app().get_matches_from(argv))
+    }
+
+    #[test]
+    fn from_matches() {
+        assert_eq!(Verbosity::Normal, verbosity(&[]));
+        assert_eq!(Verbosity::Quiet, verbosity(&["-q"]));
+        assert_eq!(Verbosity::Quiet, verbosity(&["--quiet"]));
+        assert_eq!(Verbosity::Verbose(1), verbosity(&["-v"]));
+        assert_eq!(Verbosity::Verbose(3), verbosity(&["-vvv"]));
+        assert_eq!(Verbosity::Verbose(2), verbosity(&["--verbose", "--verbose"]));
+    }
+
+    #[test]
+    fn quiet_conflicts_with_verbose() {
+        assert!(
+            cli::app()
+                .get_matches_from_safe(vec!["glacio", "-q", "-v", "heartbeats", "config.toml"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn log_level() {
+        assert_eq!(LogLevelFilter::Error, Verbosity::Quiet.log_level());
+        assert_eq!(LogLevelFilter::Warn, Verbosity::Normal.log_level());
+        assert_eq!(LogLevelFilter::Info, Verbosity::Verbose(1).log_level());
+        assert_eq!(LogLevelFilter::Debug, Verbosity::Verbose(2).log_level());
+        assert_eq!(LogLevelFilter::Trace, Verbosity::Verbose(3).log_level());
+        assert_eq!(LogLevelFilter::Trace, Verbosity::Verbose(10).log_level());
+    }
+
+    #[test]
+    fn other_results_pass_through() {
+        assert_eq!(42, exit_on_broken_pipe(Ok(42)).unwrap());
+        let err = io::Error::new(ErrorKind::PermissionDenied, "denied");
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            exit_on_broken_pipe::<()>(Err(err)).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn shows_diagnostics() {
+        assert!(!Verbosity::Quiet.shows_diagnostics());
+        assert!(Verbosity::Normal.shows_diagnostics());
+        assert!(Verbosity::Verbose(1).shows_diagnostics());
+    }
+}
@@ -5,17 +5,34 @@ use config::Config;
 use iron::{AfterMiddleware, Chain, Handler, IronError, IronResult, Request, Response, Url};
 use iron::headers::AccessControlAllowOrigin;
 use logger::Logger;
+use overview::Overview;
 use router::Router;
+use source::{HeartbeatSource, ImageSource};
 use std::path::Path;
+use std::sync::{Arc, RwLock};
 
 /// The Iron JSON api handler.
+///
+/// Clones of an api share the same underlying handlers, so a configuration reloaded through one
+/// clone is visible to all of them.
 #[allow(missing_debug_implementations)]
+#[derive(Clone)]
 pub struct Api {
-    chain: Chain,
+    chain: Arc<RwLock<Arc<Chain>>>,
+    heartbeats: Option<Arc<HeartbeatSource>>,
+    images: Option<Arc<ImageSource>>,
 }
 
 struct Custom404;
 
+/// Sets the CORS header on every response, including errors.
+///
+/// With no allowed origins, any origin is allowed. Otherwise, the request's origin is echoed back
+/// only if it's in the list, and requests from any other origin get no CORS header at all.
+struct Cors {
+    allowed_origins: Vec<String>,
+}
+
 impl Api {
     /// Creates a new api from the provided path to a toml config file.
     ///
@@ -39,84 +56,224 @@ impl Api {
     /// let api = Api::new(config);
     /// ```
     pub fn new(config: Config) -> Result<Api> {
-        let mut router = Router::new();
-        router.get("/", root, "root");
-
-        let cameras = Cameras::from(config.cameras);
-        router.get(
-            "/cameras",
-            {
-                let cameras = cameras.clone();
-                move |r: &mut Request| cameras.summary(r)
-            },
-            "cameras",
-        );
-        router.get(
-            "/cameras/:name",
-            {
-                let cameras = cameras.clone();
-                move |r: &mut Request| cameras.detail(r)
-            },
-            "camera",
-        );
-        router.get(
-            "/cameras/:name/images",
-            {
-                let cameras = cameras.clone();
-                move |r: &mut Request| cameras.images(r)
-            },
-            "camera-images",
-        );
-        router.get(
-            "/cameras/:name/images/nearest/:datetime",
-            {
-                let cameras = cameras.clone();
-                move |r: &mut Request| cameras.nearest_image(r)
-            },
-            "camera-nearest-image",
-        );
-        router.get(
-            "/cameras/:name/images/latest/redirect",
-            {
-                let cameras = cameras.clone();
-                move |r: &mut Request| cameras.latest_image_redirect(r)
-            },
-            "camera-latest-image-redirect",
-        );
+        Api::build(config, None, None)
+    }
 
-        let atlas = Atlas::from(config.atlas);
-        router.get(
-            "/atlas/status",
-            move |r: &mut Request| atlas.status(r),
-            "atlas-status",
-        );
+    /// Creates a new api that reads its heartbeats and images from the provided sources.
+    ///
+    /// The configuration still provides everything else, e.g. the list of cameras and the image
+    /// servers. This is mostly useful for testing the handlers against fake data. The sources are
+    /// kept across reloads, so only the rest of the configuration changes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::{Api, Config};
+    /// # use std::sync::Arc;
+    /// let config = Config::from_path("../data/rdcrlpjg.toml").unwrap();
+    /// let heartbeats = Arc::new(config.atlas.clone());
+    /// let images = Arc::new(config.cameras.clone());
+    /// let api = Api::with_sources(config, heartbeats, images).unwrap();
+    /// ```
+    pub fn with_sources(
+        config: Config,
+        heartbeats: Arc<HeartbeatSource>,
+        images: Arc<ImageSource>,
+    ) -> Result<Api> {
+        Api::build(config, Some(heartbeats), Some(images))
+    }
 
-        let mut chain = Chain::new(router);
-        chain.link(Logger::new(None));
+    fn build(
+        config: Config,
+        heartbeats: Option<Arc<HeartbeatSource>>,
+        images: Option<Arc<ImageSource>>,
+    ) -> Result<Api> {
+        let chain = chain(config, heartbeats.clone(), images.clone())?;
+        Ok(Api {
+            chain: Arc::new(RwLock::new(Arc::new(chain))),
+            heartbeats: heartbeats,
+            images: images,
+        })
+    }
 
-        chain.link_after(Custom404);
+    /// Replaces this api's configuration.
+    ///
+    /// The new handlers are swapped in all at once, so in-flight requests finish with the old
+    /// configuration and all subsequent requests use the new one. If this api was created with
+    /// `with_sources`, it keeps reading from those sources.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::{Api, Config};
+    /// let api = Api::new(Config::new()).unwrap();
+    /// api.reload(Config::from_path("../data/rdcrlpjg.toml").unwrap()).unwrap();
+    /// ```
+    pub fn reload(&self, config: Config) -> Result<()> {
+        let chain = chain(config, self.heartbeats.clone(), self.images.clone())?;
+        // The lock only guards the swap of one `Arc` for another, so a poisoned lock can't hold a
+        // half-built chain and it's safe to keep going.
+        *self.chain.write().unwrap_or_else(|err| err.into_inner()) = Arc::new(chain);
+        Ok(())
+    }
 
-        Ok(Api { chain: chain })
+    /// Reloads this api's configuration from the provided path to a toml config file.
+    ///
+    /// If the configuration can't be read, the api is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::{Api, Config};
+    /// let api = Api::new(Config::new()).unwrap();
+    /// api.reload_from_path("../data/rdcrlpjg.toml").unwrap();
+    /// assert!(api.reload_from_path("not/a/config.toml").is_err());
+    /// ```
+    pub fn reload_from_path<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Config::from_path(path).and_then(|config| self.reload(config))
     }
 }
 
 impl Handler for Api {
     fn handle(&self, request: &mut Request) -> IronResult<Response> {
-        self.chain
-            .handle(request)
-            .map(|mut response| {
-                response.headers.set(AccessControlAllowOrigin::Any);
-                response
-            })
-            .map_err(|mut iron_error| {
-                iron_error.response.headers.set(
-                    AccessControlAllowOrigin::Any,
-                );
-                iron_error
-            })
+        // Don't hold the lock while handling, so slow requests can't hold up a reload.
+        let chain = self.chain
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone();
+        chain.handle(request)
     }
 }
 
+/// Builds the handlers, reading from the configuration's own sources unless others are provided.
+fn chain(
+    config: Config,
+    heartbeats: Option<Arc<HeartbeatSource>>,
+    images: Option<Arc<ImageSource>>,
+) -> Result<Chain> {
+    let heartbeats = heartbeats.unwrap_or_else(|| Arc::new(config.atlas.clone()));
+    let images = images.unwrap_or_else(|| Arc::new(config.cameras.clone()));
+    let mut router = Router::new();
+    router.get("/", root, "root");
+
+    let overview = Overview::new(
+        config.atlas.clone(),
+        config.cameras.clone(),
+        heartbeats.clone(),
+        images.clone(),
+    );
+    router.get(
+        "/overview",
+        move |r: &mut Request| overview.overview(r),
+        "overview",
+    );
+
+    let cameras = Cameras::new(config.cameras, images);
+    router.get(
+        "/cameras",
+        {
+            let cameras = cameras.clone();
+            move |r: &mut Request| cameras.summary(r)
+        },
+        "cameras",
+    );
+    router.get(
+        "/cameras/:name",
+        {
+            let cameras = cameras.clone();
+            move |r: &mut Request| cameras.detail(r)
+        },
+        "camera",
+    );
+    router.get(
+        "/cameras/:name/images",
+        {
+            let cameras = cameras.clone();
+            move |r: &mut Request| cameras.images(r)
+        },
+        "camera-images",
+    );
+    router.get(
+        "/cameras/:name/stats",
+        {
+            let cameras = cameras.clone();
+            move |r: &mut Request| cameras.stats(r)
+        },
+        "camera-stats",
+    );
+    router.get(
+        "/cameras/:name/images/nearest/:datetime",
+        {
+            let cameras = cameras.clone();
+            move |r: &mut Request| cameras.nearest_image(r)
+        },
+        "camera-nearest-image",
+    );
+    router.get(
+        "/cameras/:name/images/latest",
+        {
+            let cameras = cameras.clone();
+            move |r: &mut Request| cameras.latest_image(r)
+        },
+        "camera-latest-image",
+    );
+    router.get(
+        "/cameras/:name/images/latest/redirect",
+        {
+            let cameras = cameras.clone();
+            move |r: &mut Request| cameras.latest_image_redirect(r)
+        },
+        "camera-latest-image-redirect",
+    );
+    router.get(
+        "/images/latest",
+        {
+            let cameras = cameras.clone();
+            move |r: &mut Request| cameras.latest_images(r)
+        },
+        "latest-images",
+    );
+
+    let atlas = Atlas::new(config.atlas, heartbeats);
+    router.get(
+        "/atlas/status",
+        {
+            let atlas = atlas.clone();
+            move |r: &mut Request| atlas.status(r)
+        },
+        "atlas-status",
+    );
+    router.get(
+        "/atlas/heartbeats",
+        {
+            let atlas = atlas.clone();
+            move |r: &mut Request| atlas.heartbeats(r)
+        },
+        "atlas-heartbeats",
+    );
+    router.get(
+        "/atlas/heartbeats/count",
+        {
+            let atlas = atlas.clone();
+            move |r: &mut Request| atlas.heartbeat_count(r)
+        },
+        "atlas-heartbeat-count",
+    );
+    router.get(
+        "/atlas/heartbeats/latest",
+        move |r: &mut Request| atlas.latest_heartbeat(r),
+        "atlas-latest-heartbeat",
+    );
+
+    let mut chain = Chain::new(router);
+    chain.link(Logger::new(None));
+
+    chain.link_after(Custom404);
+    chain.link_after(Cors { allowed_origins: config.allowed_origins });
+
+    Ok(chain)
+}
+
 impl AfterMiddleware for Custom404 {
     fn catch(&self, _: &mut Request, err: IronError) -> IronResult<Response> {
         use router::NoRoute;
@@ -138,14 +295,64 @@ impl AfterMiddleware for Custom404 {
     }
 }
 
+impl Cors {
+    fn set_header(&self, request: &Request, response: &mut Response) {
+        use iron::headers::Vary;
+
+        if self.allowed_origins.is_empty() {
+            response.headers.set(AccessControlAllowOrigin::Any);
+            return;
+        }
+        response.headers.set(
+            Vary::Items(vec!["Origin".parse().unwrap()]),
+        );
+        let origin = request
+            .headers
+            .get_raw("Origin")
+            .and_then(|values| values.first())
+            .and_then(|value| String::from_utf8(value.clone()).ok());
+        if let Some(origin) = origin {
+            if self.allowed_origins.contains(&origin) {
+                response.headers.set(AccessControlAllowOrigin::Value(origin));
+            }
+        }
+    }
+}
+
+impl AfterMiddleware for Cors {
+    fn after(&self, request: &mut Request, mut response: Response) -> IronResult<Response> {
+        self.set_header(request, &mut response);
+        Ok(response)
+    }
+
+    fn catch(&self, request: &mut Request, mut err: IronError) -> IronResult<Response> {
+        self.set_header(request, &mut err.response);
+        Err(err)
+    }
+}
+
+/// Lists the api's resources, so clients don't have to hard-code urls.
+///
+/// This doesn't touch the filesystem, so it's always cheap.
 fn root(request: &mut Request) -> IronResult<Response> {
+    use glacio;
     use json;
     let data = json!({
+        "version": ::VERSION,
+        "glacio_version": glacio::VERSION,
+        "overview_url": url_for!(request, "overview").as_ref().to_string(),
         "cameras_url": url_for!(request, "cameras").as_ref().to_string(),
         "camera_url": decode(url_for!(request, "camera", "name" => "{name}")),
         "camera_images_url": decode(url_for!(request, "camera-images", "name" => "{name}")),
+        "camera_stats_url": decode(url_for!(request, "camera-stats", "name" => "{name}")),
+        "camera_nearest_image_url": decode(url_for!(request, "camera-nearest-image", "name" => "{name}", "datetime" => "{datetime}")),
+        "camera_latest_image_url": decode(url_for!(request, "camera-latest-image", "name" => "{name}")),
         "camera_latest_image_redirect_url": decode(url_for!(request, "camera-latest-image-redirect", "name" => "{name}")),
+        "latest_images_url": url_for!(request, "latest-images").as_ref().to_string(),
         "atlas_status_url": url_for!(request, "atlas-status").as_ref().to_string(),
+        "atlas_heartbeats_url": url_for!(request, "atlas-heartbeats").as_ref().to_string(),
+        "atlas_heartbeat_count_url": url_for!(request, "atlas-heartbeat-count").as_ref().to_string(),
+        "atlas_latest_heartbeat_url": url_for!(request, "atlas-latest-heartbeat").as_ref().to_string(),
     });
     json::response(data)
 }
@@ -161,6 +368,7 @@ fn decode(url: Url) -> String {
 mod tests {
     use super::*;
     use iron::Headers;
+    use iron::status::Status;
     use iron_test::{request, response};
     use serde_json::{self, Value};
 
@@ -170,10 +378,150 @@ mod tests {
         let response = request::get("http://localhost:3000/", Headers::new(), &api).unwrap();
         let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
             .unwrap();
+        assert_eq!("http://localhost:3000/overview", json["overview_url"]);
         assert_eq!("http://localhost:3000/cameras", json["cameras_url"]);
         assert_eq!("http://localhost:3000/cameras/{name}", json["camera_url"]);
+        assert_eq!(::VERSION, json["version"]);
         assert_eq!("http://localhost:3000/cameras/{name}/images", json["camera_images_url"]);
+        assert_eq!("http://localhost:3000/cameras/{name}/stats", json["camera_stats_url"]);
+        assert_eq!("http://localhost:3000/cameras/{name}/images/nearest/{datetime}", json["camera_nearest_image_url"]);
+        assert_eq!("http://localhost:3000/cameras/{name}/images/latest", json["camera_latest_image_url"]);
         assert_eq!("http://localhost:3000/cameras/{name}/images/latest/redirect", json["camera_latest_image_redirect_url"]);
+        assert_eq!("http://localhost:3000/images/latest", json["latest_images_url"]);
         assert_eq!("http://localhost:3000/atlas/status", json["atlas_status_url"]);
+        assert_eq!(
+            "http://localhost:3000/atlas/heartbeats/count",
+            json["atlas_heartbeat_count_url"]
+        );
+        assert_eq!(
+            "http://localhost:3000/atlas/heartbeats",
+            json["atlas_heartbeats_url"]
+        );
+        assert_eq!(
+            "http://localhost:3000/atlas/heartbeats/latest",
+            json["atlas_latest_heartbeat_url"]
+        );
+    }
+
+    #[test]
+    fn root_urls_respond() {
+        use atlas::config::EfoyCartridgeConfig;
+
+        let mut config = Config::new();
+        config.atlas.path = "../glacio/data".to_string();
+        config.atlas.efoy.cartridges = vec![
+            EfoyCartridgeConfig {
+                name: "1.1".to_string(),
+                capacity: 8.0,
+            },
+            EfoyCartridgeConfig {
+                name: "1.2".to_string(),
+                capacity: 8.0,
+            },
+        ];
+        let api = Api::new(config).unwrap();
+        let response = request::get("http://localhost:3000/", Headers::new(), &api).unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        let mut count = 0;
+        for (key, url) in json.as_object().unwrap() {
+            let url = url.as_str().unwrap();
+            if !key.ends_with("_url") || url.contains('{') {
+                continue;
+            }
+            let response = request::get(url, Headers::new(), &api).unwrap();
+            assert_eq!(Some(Status::Ok), response.status, "{}", url);
+            count += 1;
+        }
+        assert_eq!(7, count);
+    }
+
+    #[test]
+    fn cors_any_origin() {
+        let api = Api::new(Config::new()).unwrap();
+        let mut headers = Headers::new();
+        headers.set_raw("Origin", vec![b"https://anywhere.example.com".to_vec()]);
+        let response = request::get("http://localhost:3000/", headers, &api).unwrap();
+        assert_eq!(
+            Some(&AccessControlAllowOrigin::Any),
+            response.headers.get::<AccessControlAllowOrigin>()
+        );
+        let err = request::get("http://localhost:3000/atlas/status", Headers::new(), &api)
+            .unwrap_err();
+        assert_eq!(
+            Some(&AccessControlAllowOrigin::Any),
+            err.response.headers.get::<AccessControlAllowOrigin>()
+        );
+    }
+
+    #[test]
+    fn cors_allowed_origins() {
+        let mut config = Config::new();
+        config.allowed_origins = vec!["https://dashboard.example.com".to_string()];
+        let api = Api::new(config).unwrap();
+        let get = |origin: &str| {
+            let mut headers = Headers::new();
+            headers.set_raw("Origin", vec![origin.as_bytes().to_vec()]);
+            request::get("http://localhost:3000/", headers, &api).unwrap()
+        };
+        let response = get("https://dashboard.example.com");
+        assert_eq!(
+            Some(&AccessControlAllowOrigin::Value(
+                "https://dashboard.example.com".to_string(),
+            )),
+            response.headers.get::<AccessControlAllowOrigin>()
+        );
+        let response = get("https://evil.example.com");
+        assert_eq!(None, response.headers.get::<AccessControlAllowOrigin>());
+        let response = request::get("http://localhost:3000/", Headers::new(), &api).unwrap();
+        assert_eq!(None, response.headers.get::<AccessControlAllowOrigin>());
+    }
+
+    #[test]
+    fn reload() {
+        use cameras::CameraConfig;
+
+        let api = Api::new(Config::new()).unwrap();
+        let handler = api.clone();
+        let cameras = |api: &Api| -> Value {
+            let response = request::get("http://localhost:3000/cameras", Headers::new(), api)
+                .unwrap();
+            serde_json::from_str(&response::extract_body_to_string(response)).unwrap()
+        };
+        assert_eq!(0, cameras(&handler).as_array().unwrap().len());
+
+        let mut config = Config::new();
+        config.cameras.cameras.push(CameraConfig {
+            name: "ATLAS_CAM".to_string(),
+            ..Default::default()
+        });
+        api.reload(config).unwrap();
+        let json = cameras(&handler);
+        assert_eq!(1, json.as_array().unwrap().len());
+        assert_eq!("ATLAS_CAM", json[0]["name"]);
+    }
+
+    #[test]
+    fn reload_keeps_sources() {
+        use Error;
+        use glacio::atlas::Heartbeat;
+
+        #[derive(Debug)]
+        struct Broken;
+
+        impl HeartbeatSource for Broken {
+            fn heartbeats(&self) -> Result<Vec<Heartbeat>> {
+                Err(Error::Config("the heartbeat source is broken".to_string()))
+            }
+        }
+
+        let mut config = Config::new();
+        config.atlas.path = "../glacio/data".to_string();
+        let images = Arc::new(config.cameras.clone());
+        let api = Api::with_sources(config.clone(), Arc::new(Broken), images).unwrap();
+        api.reload(config).unwrap();
+        let err = request::get("http://localhost:3000/atlas/status", Headers::new(), &api)
+            .unwrap_err();
+        assert_eq!(Some(Status::InternalServerError), err.response.status);
     }
 }
@@ -1,14 +1,31 @@
 //! Configuration objects for the ATLAS system.
 
 use {Error, Result};
-use glacio::atlas::{Efoy, Heartbeat, ReadSbd, SbdSource};
+use chrono::{DateTime, Utc};
+use glacio::atlas::{self, Efoy, Heartbeat, ReadSbd, SbdSource, TestMessage};
+use std::env;
+
+/// The environment variable that overrides the configured IMEI, e.g. on the CRREL test bench.
+pub const IMEI_ENV_VAR: &'static str = "GLACIO_ATLAS_IMEI";
 
 /// ATLAS configuration.
 #[derive(Clone, Debug, Default, Deserialize)]
 pub struct Config {
+    /// A human-readable description of what this ATLAS system monitors.
+    ///
+    /// Optional, and reported as `null` in the status if not provided.
+    pub description: Option<String>,
     /// The path to the SBD storage.
     pub path: String,
+    /// More paths to SBD storage, e.g. an archive of older messages.
+    ///
+    /// Messages from these paths are merged with the messages from `path`, and a message that
+    /// shows up in more than one path is only read once.
+    #[serde(default)]
+    pub paths: Vec<String>,
     /// The IMEI number of the modem that provides the SBD data.
+    ///
+    /// Use `Config::imei` to read it, since it can be overridden by the environment.
     pub imei: String,
     /// The heartbeat versions that are supported.
     pub versions: Vec<u8>,
@@ -16,6 +33,9 @@ pub struct Config {
     ///
     /// For now, we assume all EFOYs have the same setup.
     pub efoy: EfoyConfig,
+    /// The names of the cameras at this site, for the overview.
+    #[serde(default)]
+    pub cameras: Vec<String>,
 }
 
 /// EFOY configuration.
@@ -60,6 +80,176 @@ impl Config {
         }
     }
 
+    /// Returns this config's heartbeats, failing on the first heartbeat that can't be parsed.
+    ///
+    /// Unlike `heartbeats`, an empty set of heartbeats is not an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::atlas::Config;
+    /// let mut config = Config::default();
+    /// config.path = "../glacio/data".to_string();
+    /// // The test data includes a forced transmission, which isn't a heartbeat.
+    /// assert!(config.heartbeats_strict().is_err());
+    /// ```
+    pub fn heartbeats_strict(&self) -> Result<Vec<Heartbeat>> {
+        self.read_sbd()?.map(|r| r.map_err(Error::from)).collect()
+    }
+
+    /// Returns this config's heartbeats along with any errors encountered while parsing them.
+    ///
+    /// Only errors opening the SBD storage are returned as an `Err`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::atlas::Config;
+    /// let mut config = Config::default();
+    /// config.path = "../glacio/data".to_string();
+    /// let (heartbeats, errors) = config.heartbeats_with_errors().unwrap();
+    /// assert_eq!(2, heartbeats.len());
+    /// assert_eq!(1, errors.len());
+    /// ```
+    pub fn heartbeats_with_errors(&self) -> Result<(Vec<Heartbeat>, Vec<atlas::Error>)> {
+        let mut heartbeats = Vec::new();
+        let mut errors = Vec::new();
+        for result in self.read_sbd()? {
+            match result {
+                Ok(heartbeat) => heartbeats.push(heartbeat),
+                Err(err) => errors.push(err),
+            }
+        }
+        Ok((heartbeats, errors))
+    }
+
+    /// Returns the number of heartbeats that can be parsed, without keeping them around.
+    ///
+    /// Unlike `heartbeats`, no heartbeats is not an error, it's just zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::atlas::Config;
+    /// let mut config = Config::default();
+    /// config.path = "../glacio/data".to_string();
+    /// assert_eq!(2, config.heartbeat_count().unwrap());
+    /// // Messages that are in more than one path are only counted once.
+    /// config.paths.push("../glacio/data".to_string());
+    /// assert_eq!(2, config.heartbeat_count().unwrap());
+    /// ```
+    pub fn heartbeat_count(&self) -> Result<usize> {
+        Ok(self.read_sbd()?.filter(|r| r.is_ok()).count())
+    }
+
+    /// Returns the most recent heartbeat, or `None` if there are no heartbeats.
+    ///
+    /// Unlike `heartbeats`, no heartbeats is not an error, so an `Err` always means that the SBD
+    /// storage couldn't be read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::atlas::Config;
+    /// let mut config = Config::default();
+    /// config.path = "../glacio/data".to_string();
+    /// assert!(config.latest_heartbeat().unwrap().is_some());
+    /// config.path = "not/a/directory".to_string();
+    /// assert!(config.latest_heartbeat().is_err());
+    /// ```
+    pub fn latest_heartbeat(&self) -> Result<Option<Heartbeat>> {
+        let (heartbeats, _) = self.heartbeats_with_errors()?;
+        Ok(atlas::latest_heartbeat(&heartbeats).cloned())
+    }
+
+    /// Returns the latest heartbeat at or before `as_of`, or `None` if there isn't one.
+    ///
+    /// SBD messages that arrived after `as_of` aren't read at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio_http;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio_http::atlas::Config;
+    /// # fn main() {
+    /// let mut config = Config::default();
+    /// config.path = "../glacio/data".to_string();
+    /// let as_of = Utc.ymd(2017, 8, 2).and_hms(0, 0, 0);
+    /// let heartbeat = config.latest_heartbeat_as_of(as_of).unwrap().unwrap();
+    /// assert!(heartbeat.datetime <= as_of);
+    /// # }
+    /// ```
+    pub fn latest_heartbeat_as_of(&self, as_of: DateTime<Utc>) -> Result<Option<Heartbeat>> {
+        self.sbd_source().latest_heartbeat_as_of(as_of).map_err(Error::from)
+    }
+
+    /// Returns this config's heartbeats, newest first.
+    ///
+    /// Only heartbeats at or after `since` are returned, and no more than `limit` of them. Unlike
+    /// `heartbeats`, no heartbeats is not an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::atlas::Config;
+    /// let mut config = Config::default();
+    /// config.path = "../glacio/data".to_string();
+    /// let heartbeats = config.recent_heartbeats(None, 1).unwrap();
+    /// assert_eq!(1, heartbeats.len());
+    /// assert_eq!(config.latest_heartbeat().unwrap().unwrap(), heartbeats[0]);
+    /// ```
+    pub fn recent_heartbeats(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<Heartbeat>> {
+        let (heartbeats, _) = self.heartbeats_with_errors()?;
+        Ok(super::newest_first(heartbeats, since, limit))
+    }
+
+    /// Returns the test messages that were sent by hand from the site, oldest first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::atlas::Config;
+    /// let mut config = Config::default();
+    /// config.path = "../glacio/data".to_string();
+    /// let test_messages = config.test_messages().unwrap();
+    /// assert_eq!("test", test_messages[0].text);
+    /// ```
+    pub fn test_messages(&self) -> Result<Vec<TestMessage>> {
+        self.sbd_source().test_messages().map_err(Error::from)
+    }
+
+    /// Returns this config's heartbeats and test messages, reading the SBD storage only once.
+    ///
+    /// Like `heartbeats`, it's an error if there are no heartbeats.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::atlas::Config;
+    /// let mut config = Config::default();
+    /// config.path = "../glacio/data".to_string();
+    /// let (heartbeats, test_messages) = config.heartbeats_and_test_messages().unwrap();
+    /// assert_eq!(2, heartbeats.len());
+    /// assert_eq!("test", test_messages[0].text);
+    /// ```
+    pub fn heartbeats_and_test_messages(&self) -> Result<(Vec<Heartbeat>, Vec<TestMessage>)> {
+        let (read_sbd, test_messages) = self.sbd_source().iter_with_test_messages()?;
+        let heartbeats = read_sbd.flat_map(|r| r.ok()).collect::<Vec<_>>();
+        if heartbeats.is_empty() {
+            Err(Error::Config(
+                format!("No heartbeats in configured path: {}", self.path),
+            ))
+        } else {
+            Ok((heartbeats, test_messages))
+        }
+    }
+
     /// Returns an iterator over this config's `Result<Heartbeat>`s.
     ///
     /// Can be used to query this config's heartbeats while not throwing out errors.
@@ -78,11 +268,32 @@ impl Config {
     /// }
     /// ```
     pub fn read_sbd(&self) -> Result<ReadSbd> {
-        SbdSource::new(&self.path)
-            .imeis(&[&self.imei])
-            .versions(&self.versions)
-            .iter()
-            .map_err(Error::from)
+        self.sbd_source().iter().map_err(Error::from)
+    }
+
+    /// Returns the IMEI number of the modem that provides the SBD data.
+    ///
+    /// If the `GLACIO_ATLAS_IMEI` environment variable is set and isn't empty, it wins. Otherwise,
+    /// the `imei` from the configuration file is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::atlas::Config;
+    /// # use glacio_http::atlas::config::IMEI_ENV_VAR;
+    /// # use std::env;
+    /// let mut config = Config::default();
+    /// config.imei = "300234063556840".to_string();
+    /// env::set_var(IMEI_ENV_VAR, "300234010753370");
+    /// assert_eq!("300234010753370", config.imei());
+    /// env::remove_var(IMEI_ENV_VAR);
+    /// assert_eq!("300234063556840", config.imei());
+    /// ```
+    pub fn imei(&self) -> String {
+        match env::var(IMEI_ENV_VAR) {
+            Ok(ref imei) if !imei.is_empty() => imei.clone(),
+            _ => self.imei.clone(),
+        }
     }
 
     /// Returns a properly-configured `Efoy`.
@@ -123,6 +334,14 @@ impl Config {
             .map(|config| config.name.as_str())
             .collect()
     }
+
+    fn sbd_source(&self) -> SbdSource {
+        let mut source = SbdSource::new(&self.path);
+        for path in &self.paths {
+            source = source.add_path(path);
+        }
+        source.imeis(&[&self.imei()]).versions(&self.versions)
+    }
 }
 
 impl From<(String, f32)> for EfoyCartridgeConfig {
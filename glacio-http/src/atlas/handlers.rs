@@ -1,8 +1,15 @@
 //! Handle ATLAS requests.
 
+use Error;
 use atlas::{Config, Status};
+use atlas::status::Summary;
 use iron::{IronResult, Request, Response};
 use json;
+use paginate;
+use param;
+use source::HeartbeatSource;
+use std::cmp;
+use std::sync::Arc;
 
 /// Handler for ATLAS requests.
 ///
@@ -11,29 +18,409 @@ use json;
 #[derive(Clone, Debug)]
 pub struct Atlas {
     config: Config,
+    source: Arc<HeartbeatSource>,
 }
 
 impl From<Config> for Atlas {
     fn from(config: Config) -> Atlas {
-        Atlas { config: config }
+        let source = Arc::new(config.clone());
+        Atlas::new(config, source)
     }
 }
 
 impl Atlas {
+    /// Creates a new handler that reads its heartbeats from the provided source.
+    pub fn new(config: Config, source: Arc<HeartbeatSource>) -> Atlas {
+        Atlas {
+            config: config,
+            source: source,
+        }
+    }
+
     /// Returns a full status report for the ATLAS system.
-    pub fn status(&self, _: &mut Request) -> IronResult<Response> {
-        json::response(itry!(Status::new(&self.config)))
+    ///
+    /// The report includes the latest test message, so the field team can see that a test they
+    /// sent by hand made it home.
+    ///
+    /// With `summary=true`, only a small summary of the latest heartbeat is returned, for
+    /// dashboards that poll often.
+    pub fn status(&self, request: &mut Request) -> IronResult<Response> {
+        if param::parse_param(request, "summary", "true or false")?.unwrap_or(false) {
+            let heartbeat = self.source.latest_heartbeat()?;
+            return json::response(Summary::new(&self.config, heartbeat)?);
+        }
+        let (heartbeats, test_messages) = self.source.heartbeats_and_test_messages()?;
+        let mut status = Status::new(&self.config, heartbeats)?;
+        status.last_test_message = test_messages.into_iter().max_by_key(
+            |test_message| test_message.datetime,
+        );
+        json::response(status)
+    }
+
+    /// Returns the number of heartbeats available, so clients can size their requests.
+    ///
+    /// If a `since` parameter is provided, only heartbeats at or after that datetime are counted.
+    pub fn heartbeat_count(&self, request: &mut Request) -> IronResult<Response> {
+        let count = match param::parse_datetime_param(request, "since")? {
+            Some(since) => {
+                self.source
+                    .heartbeats()?
+                    .iter()
+                    .filter(|heartbeat| heartbeat.datetime >= since)
+                    .count()
+            }
+            None => self.source.heartbeat_count()?,
+        };
+        json::response(json!({ "count": count }))
+    }
+
+    /// Returns the most recent heartbeats, newest first.
+    ///
+    /// If a `since` parameter is provided, only heartbeats at or after that datetime are returned.
+    /// A `limit` parameter sets how many heartbeats to return. It defaults to `DEFAULT_PER_PAGE`
+    /// and is clamped to `MAX_PER_PAGE`, just like a page of images. A `limit` of zero is a bad
+    /// request.
+    pub fn heartbeats(&self, request: &mut Request) -> IronResult<Response> {
+        let since = param::parse_datetime_param(request, "since")?;
+        let limit = param::parse_param(request, "limit", "a positive integer")?
+            .unwrap_or(paginate::DEFAULT_PER_PAGE);
+        if limit == 0 {
+            return Err(
+                Error::InvalidParam {
+                    name: "limit".to_string(),
+                    value: limit.to_string(),
+                    expected: "a positive integer",
+                }.into(),
+            );
+        }
+        let limit = cmp::min(limit, paginate::MAX_PER_PAGE);
+        json::response(self.source.recent_heartbeats(since, limit)?)
+    }
+
+    /// Returns the latest heartbeat, or `null` if there are no heartbeats yet.
+    ///
+    /// This doesn't fail if the heartbeats can't be read, e.g. because the SBD storage isn't
+    /// mounted. Instead, the error is logged and returned as `heartbeat_error`, so a `null`
+    /// heartbeat always means that there are no heartbeats.
+    ///
+    /// If an `as_of` parameter is provided, the latest heartbeat at or before that datetime is
+    /// returned instead, to see what the dashboard showed at that time.
+    pub fn latest_heartbeat(&self, request: &mut Request) -> IronResult<Response> {
+        let latest_heartbeat = match param::parse_datetime_param(request, "as_of")? {
+            Some(as_of) => self.source.latest_heartbeat_as_of(as_of),
+            None => self.source.latest_heartbeat(),
+        };
+        let (latest_heartbeat, heartbeat_error) = match latest_heartbeat {
+            Ok(latest_heartbeat) => (latest_heartbeat, None),
+            Err(err) => {
+                error!("could not read the latest heartbeat: {}", err);
+                (None, Some(err.to_string()))
+            }
+        };
+        json::response(json!({
+            "latest_heartbeat": latest_heartbeat,
+            "heartbeat_error": heartbeat_error,
+        }))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use {Api, Config};
+    use {Api, Config, Error, Result};
     use atlas::config::EfoyCartridgeConfig;
+    use glacio::atlas::Heartbeat;
     use iron::Headers;
+    use iron::status::Status;
+    use source::HeartbeatSource;
+    use std::sync::Arc;
     use iron_test::{request, response};
     use serde_json::{self, Value};
 
+    #[derive(Debug)]
+    struct Broken;
+
+    impl HeartbeatSource for Broken {
+        fn heartbeats(&self) -> Result<Vec<Heartbeat>> {
+            Err(Error::Config("the heartbeat source is broken".to_string()))
+        }
+    }
+
+    /// Returns the fixture heartbeats newest first, the opposite of the storage order.
+    #[derive(Debug)]
+    struct Shuffled;
+
+    impl HeartbeatSource for Shuffled {
+        fn heartbeats(&self) -> Result<Vec<Heartbeat>> {
+            let mut config = Config::default();
+            config.atlas.path = "../glacio/data".to_string();
+            let mut heartbeats = config.atlas.heartbeats()?;
+            heartbeats.reverse();
+            Ok(heartbeats)
+        }
+    }
+
+    #[derive(Debug)]
+    struct Empty;
+
+    impl HeartbeatSource for Empty {
+        fn heartbeats(&self) -> Result<Vec<Heartbeat>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn get_error(api: &Api, url: &str) -> (Option<Status>, Value) {
+        let err = request::get(url, Headers::new(), api).unwrap_err();
+        let status = err.response.status;
+        let json = serde_json::from_str(&response::extract_body_to_string(err.response)).unwrap();
+        (status, json)
+    }
+
+    #[test]
+    fn status_broken_source() {
+        let config = Config::default();
+        let cameras = Arc::new(config.cameras.clone());
+        let api = Api::with_sources(config, Arc::new(Broken), cameras).unwrap();
+        let (status, json) = get_error(&api, "http://localhost:3000/atlas/status");
+        assert_eq!(Some(Status::InternalServerError), status);
+        assert_eq!(
+            "api configuration error: the heartbeat source is broken",
+            json["message"]
+        );
+        let (status, _) = get_error(&api, "http://localhost:3000/atlas/heartbeats/count");
+        assert_eq!(Some(Status::InternalServerError), status);
+    }
+
+    #[test]
+    fn status_empty_source() {
+        let config = Config::default();
+        let cameras = Arc::new(config.cameras.clone());
+        let api = Api::with_sources(config, Arc::new(Empty), cameras).unwrap();
+        let (status, json) = get_error(&api, "http://localhost:3000/atlas/status");
+        assert_eq!(Some(Status::InternalServerError), status);
+        assert!(json["message"].is_string());
+        let response = request::get(
+            "http://localhost:3000/atlas/heartbeats/count",
+            Headers::new(),
+            &api,
+        ).unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!(0, json["count"]);
+    }
+
+    #[test]
+    fn status_summary() {
+        let mut config = Config::default();
+        config.atlas.path = "../glacio/data".to_string();
+        let api = Api::new(config).unwrap();
+        let response = request::get(
+            "http://localhost:3000/atlas/status?summary=true",
+            Headers::new(),
+            &api,
+        ).unwrap();
+        let summary: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!(5, summary.as_object().unwrap().len());
+        assert_eq!(Value::Null, summary["description"]);
+        assert_eq!(
+            "2017-08-25T15:01:06+00:00",
+            summary["last_heartbeat_received"]
+        );
+        assert!((86.0325 - summary["battery_avg_soc"].as_f64().unwrap()).abs() < 1e-3);
+        assert!((2064.78 - summary["total_energy_wh"].as_f64().unwrap()).abs() < 1e-3);
+        assert_eq!(true, summary["is_riegl_switch_on"]);
+
+        let config = Config::default();
+        let cameras = Arc::new(config.cameras.clone());
+        let api = Api::with_sources(config, Arc::new(Empty), cameras).unwrap();
+        let (status, _) = get_error(&api, "http://localhost:3000/atlas/status?summary=true");
+        assert_eq!(Some(Status::InternalServerError), status);
+        let (status, _) = get_error(&api, "http://localhost:3000/atlas/status?summary=maybe");
+        assert_eq!(Some(Status::BadRequest), status);
+    }
+
+    #[test]
+    fn status_description() {
+        let mut config = Config::default();
+        config.atlas.path = "../glacio/data".to_string();
+        config.atlas.description = Some("Helheim Glacier terminus".to_string());
+        config.atlas.efoy.cartridges = vec![
+            EfoyCartridgeConfig {
+                name: "1.1".to_string(),
+                capacity: 8.0,
+            },
+            EfoyCartridgeConfig {
+                name: "1.2".to_string(),
+                capacity: 8.0,
+            },
+        ];
+        let api = Api::new(config).unwrap();
+        let response = request::get("http://localhost:3000/atlas/status", Headers::new(), &api)
+            .unwrap();
+        let status: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!("Helheim Glacier terminus", status["description"]);
+    }
+
+    #[test]
+    fn heartbeat_count() {
+        let mut config = Config::default();
+        config.atlas.path = "../glacio/data".to_string();
+        let api = Api::new(config).unwrap();
+        let response = request::get(
+            "http://localhost:3000/atlas/heartbeats/count",
+            Headers::new(),
+            &api,
+        ).unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!(2, json["count"]);
+
+        let response = request::get(
+            "http://localhost:3000/atlas/heartbeats/count?since=2017-08-02",
+            Headers::new(),
+            &api,
+        ).unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!(1, json["count"]);
+
+        let (status, json) = get_error(
+            &api,
+            "http://localhost:3000/atlas/heartbeats/count?since=tuesday",
+        );
+        assert_eq!(Some(Status::BadRequest), status);
+        assert_eq!("since", json["param"]);
+    }
+
+    #[test]
+    fn heartbeats() {
+        let get = |api: &Api, url: &str| -> Vec<String> {
+            let response = request::get(url, Headers::new(), api).unwrap();
+            let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+                .unwrap();
+            json.as_array()
+                .unwrap()
+                .iter()
+                .map(|heartbeat| heartbeat["datetime"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let mut config = Config::default();
+        config.atlas.path = "../glacio/data".to_string();
+        let cameras = Arc::new(config.cameras.clone());
+        for api in vec![
+            Api::new(config.clone()).unwrap(),
+            Api::with_sources(config, Arc::new(Shuffled), cameras).unwrap(),
+        ]
+        {
+            assert_eq!(
+                vec!["2017-08-25T15:01:06+00:00", "2017-08-01T00:00:55+00:00"],
+                get(&api, "http://localhost:3000/atlas/heartbeats")
+            );
+            assert_eq!(
+                vec!["2017-08-25T15:01:06+00:00"],
+                get(&api, "http://localhost:3000/atlas/heartbeats?limit=1")
+            );
+            assert_eq!(
+                vec!["2017-08-25T15:01:06+00:00"],
+                get(&api, "http://localhost:3000/atlas/heartbeats?since=2017-08-02")
+            );
+            let (status, json) = get_error(&api, "http://localhost:3000/atlas/heartbeats?limit=0");
+            assert_eq!(Some(Status::BadRequest), status);
+            assert_eq!("limit", json["param"]);
+        }
+    }
+
+    #[test]
+    fn latest_heartbeat() {
+        let get = |api: &Api| -> Value {
+            let response = request::get(
+                "http://localhost:3000/atlas/heartbeats/latest",
+                Headers::new(),
+                api,
+            ).unwrap();
+            serde_json::from_str(&response::extract_body_to_string(response)).unwrap()
+        };
+
+        let mut config = Config::default();
+        config.atlas.path = "../glacio/data".to_string();
+        let json = get(&Api::new(config).unwrap());
+        assert_eq!(
+            "2017-08-25T15:01:06+00:00",
+            json["latest_heartbeat"]["datetime"]
+        );
+        assert_eq!(Value::Null, json["heartbeat_error"]);
+
+        let mut config = Config::default();
+        config.atlas.path = "../glacio/data/not-mounted".to_string();
+        let json = get(&Api::new(config).unwrap());
+        assert_eq!(Value::Null, json["latest_heartbeat"]);
+        assert!(json["heartbeat_error"].is_string());
+
+        let config = Config::default();
+        let cameras = Arc::new(config.cameras.clone());
+        let json = get(&Api::with_sources(config, Arc::new(Empty), cameras.clone()).unwrap());
+        assert_eq!(Value::Null, json["latest_heartbeat"]);
+        assert_eq!(Value::Null, json["heartbeat_error"]);
+
+        let json = get(&Api::with_sources(Config::default(), Arc::new(Shuffled), cameras.clone())
+            .unwrap());
+        assert_eq!(
+            "2017-08-25T15:01:06+00:00",
+            json["latest_heartbeat"]["datetime"]
+        );
+
+        let json = get(&Api::with_sources(Config::default(), Arc::new(Broken), cameras).unwrap());
+        assert_eq!(Value::Null, json["latest_heartbeat"]);
+        assert_eq!(
+            "api configuration error: the heartbeat source is broken",
+            json["heartbeat_error"]
+        );
+    }
+
+    #[test]
+    fn latest_heartbeat_as_of() {
+        let get = |api: &Api, as_of: &str| -> Value {
+            let response = request::get(
+                &format!("http://localhost:3000/atlas/heartbeats/latest?as_of={}", as_of),
+                Headers::new(),
+                api,
+            ).unwrap();
+            serde_json::from_str(&response::extract_body_to_string(response)).unwrap()
+        };
+
+        let mut config = Config::default();
+        config.atlas.path = "../glacio/data".to_string();
+        let api = Api::new(config).unwrap();
+        let cameras = Arc::new(Config::default().cameras);
+        let shuffled = Api::with_sources(Config::default(), Arc::new(Shuffled), cameras).unwrap();
+        for api in &[api, shuffled] {
+            assert_eq!(
+                "2017-08-25T15:01:06+00:00",
+                get(api, "2017-08-25T15:01:07Z")["latest_heartbeat"]["datetime"]
+            );
+            assert_eq!(
+                "2017-08-25T15:01:06+00:00",
+                get(api, "2017-08-25T15:01:06Z")["latest_heartbeat"]["datetime"]
+            );
+            assert_eq!(
+                "2017-08-01T00:00:55+00:00",
+                get(api, "2017-08-25T15:01:05Z")["latest_heartbeat"]["datetime"]
+            );
+            assert_eq!(Value::Null, get(api, "2017-07-01")["latest_heartbeat"]);
+        }
+
+        let mut config = Config::default();
+        config.atlas.path = "../glacio/data".to_string();
+        let (status, json) = get_error(
+            &Api::new(config).unwrap(),
+            "http://localhost:3000/atlas/heartbeats/latest?as_of=yesterday",
+        );
+        assert_eq!(Some(Status::BadRequest), status);
+        assert_eq!("as_of", json["param"]);
+    }
+
     #[test]
     fn status() {
         let mut config = Config::default();
@@ -53,16 +440,26 @@ mod tests {
             .unwrap();
         let status: Value = serde_json::from_str(&response::extract_body_to_string(response))
             .unwrap();
+        assert_eq!(Value::Null, status["description"]);
         assert_eq!(
             "2017-08-25T15:01:06+00:00",
             status["last_heartbeat_received"]
         );
+        assert_eq!(
+            "2016-07-19T19:31:36+00:00",
+            status["last_test_message"]["datetime"]
+        );
+        assert_eq!("test", status["last_test_message"]["text"]);
         assert_eq!("2017-08-25T12:02:08+00:00", status["last_scan"]["start"]);
         assert_eq!("2017-08-25T12:41:42+00:00", status["last_scan"]["end"]);
         assert_eq!(1, status["batteries"][0]["id"]);
         assert_eq!(85.461, status["batteries"][0]["state_of_charge"]);
         assert_eq!(2, status["batteries"][1]["id"]);
         assert_eq!(86.604, status["batteries"][1]["state_of_charge"]);
+        let energy = |value: &Value| value.as_f64().unwrap();
+        assert!((1025.532 - energy(&status["batteries"][0]["total_energy_wh"])).abs() < 1e-3);
+        assert!((1039.248 - energy(&status["batteries"][1]["total_energy_wh"])).abs() < 1e-3);
+        assert!((2064.78 - energy(&status["total_energy_wh"])).abs() < 1e-3);
 
         assert_eq!(1, status["efoys"][0]["id"]);
         assert_eq!("auto off", status["efoys"][0]["state"]);
@@ -7,3 +7,22 @@ mod status;
 
 pub use self::config::Config;
 use self::status::Status;
+use chrono::{DateTime, Utc};
+use glacio::atlas::Heartbeat;
+
+/// Sorts heartbeats newest first, keeping only the ones at or after `since` and at most `limit`.
+///
+/// Heartbeats come out of SBD storage in whatever order the storage was walked, so anything that
+/// shows heartbeats to a person should go through here.
+pub fn newest_first(
+    mut heartbeats: Vec<Heartbeat>,
+    since: Option<DateTime<Utc>>,
+    limit: usize,
+) -> Vec<Heartbeat> {
+    if let Some(since) = since {
+        heartbeats.retain(|heartbeat| heartbeat.datetime >= since);
+    }
+    heartbeats.sort_by(|a, b| b.datetime.cmp(&a.datetime));
+    heartbeats.truncate(limit);
+    heartbeats
+}
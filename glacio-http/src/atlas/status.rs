@@ -1,15 +1,19 @@
-use Result;
+use {Error, Result};
 use atlas::Config;
-use glacio::atlas::{Efoy, Heartbeat, efoy};
+use glacio::atlas::{Efoy, Heartbeat, TestMessage, battery, efoy};
 use std::collections::BTreeMap;
 
 /// An ATLAS status report.
 #[derive(Debug, Serialize)]
 pub struct Status {
+    /// A human-readable description of what this ATLAS system monitors, if one is configured.
+    pub description: Option<String>,
     /// The date and time that the last heartbeat was received.
     pub last_heartbeat_received: String,
     /// A list of battery status information.
     pub batteries: Vec<BatteryStatus>,
+    /// The energy remaining across all of the batteries, in watt-hours.
+    pub total_energy_wh: f32,
     /// A list of efoy status information.
     pub efoys: Vec<EfoyStatus>,
     /// Information about the last scan.
@@ -18,6 +22,26 @@ pub struct Status {
     pub timeseries: Timeseries,
     /// Are the Riegl systems powered?
     pub is_riegl_switch_on: bool,
+    /// The most recent test message sent by hand from the site, if there's been one.
+    pub last_test_message: Option<TestMessage>,
+}
+
+/// A slimmed-down status report, for dashboards that poll often.
+///
+/// This only needs the latest heartbeat, so it's much cheaper to build and much smaller to send
+/// than a full `Status`.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    /// A human-readable description of what this ATLAS system monitors, if one is configured.
+    pub description: Option<String>,
+    /// The date and time that the last heartbeat was received.
+    pub last_heartbeat_received: String,
+    /// The mean state of charge of the batteries, or `None` if the heartbeat has no batteries.
+    pub battery_avg_soc: Option<f32>,
+    /// The energy remaining across all of the batteries, in watt-hours.
+    pub total_energy_wh: f32,
+    /// Are the Riegl systems powered?
+    pub is_riegl_switch_on: bool,
 }
 
 /// The status of one of the battery systems.
@@ -27,6 +51,8 @@ pub struct BatteryStatus {
     pub id: u8,
     /// The state of charge of the battery system, as a percentage between zero and 100.
     pub state_of_charge: f32,
+    /// The energy remaining in the battery system, in watt-hours.
+    pub total_energy_wh: f32,
 }
 
 /// The status of one of the EFOY fuel cell systems.
@@ -89,9 +115,15 @@ pub struct LastScan {
 }
 
 impl Status {
-    /// Creates a new status from a configuration and a request.
-    pub fn new(config: &Config) -> Result<Status> {
-        let mut heartbeats = config.heartbeats()?;
+    /// Creates a new status from a configuration and the heartbeats to report on.
+    ///
+    /// The heartbeats don't need to be sorted. Returns an error if there are no heartbeats.
+    pub fn new(config: &Config, mut heartbeats: Vec<Heartbeat>) -> Result<Status> {
+        if heartbeats.is_empty() {
+            return Err(Error::Config(
+                "No heartbeats available for status report".to_string(),
+            ));
+        }
         heartbeats.sort();
         let mut timeseries = Timeseries::new(config, &heartbeats[0])?;
         for heartbeat in &heartbeats {
@@ -101,26 +133,65 @@ impl Status {
         let batteries = heartbeat
             .batteries
             .iter()
-            .map(|(&i, battery)| {
-                BatteryStatus::new(i, battery.state_of_charge)
-            })
+            .map(|(&i, battery)| BatteryStatus::new(i, battery))
             .collect();
+        let total_energy_wh = battery::total_energy_wh_all(
+            &heartbeat.batteries.values().cloned().collect::<Vec<_>>(),
+        );
         Ok(Status {
+            description: config.description.clone(),
             last_heartbeat_received: heartbeat.datetime.to_rfc3339(),
             batteries: batteries,
+            total_energy_wh: total_energy_wh,
             efoys: timeseries.efoys(&heartbeat),
             timeseries: timeseries,
             is_riegl_switch_on: heartbeat.is_riegl_switch_on,
             last_scan: LastScan::new(&heartbeat),
+            last_test_message: None,
+        })
+    }
+}
+
+impl Summary {
+    /// Creates a new summary from a configuration and the latest heartbeat.
+    ///
+    /// Returns an error if there is no latest heartbeat, just like `Status::new`.
+    pub fn new(config: &Config, heartbeat: Option<Heartbeat>) -> Result<Summary> {
+        let heartbeat = match heartbeat {
+            Some(heartbeat) => heartbeat,
+            None => {
+                return Err(Error::Config(
+                    "No heartbeats available for status report".to_string(),
+                ))
+            }
+        };
+        let batteries = heartbeat.batteries.values().cloned().collect::<Vec<_>>();
+        let battery_avg_soc = if batteries.is_empty() {
+            None
+        } else {
+            Some(
+                batteries
+                    .iter()
+                    .map(|battery| battery.state_of_charge)
+                    .sum::<f32>() / batteries.len() as f32,
+            )
+        };
+        Ok(Summary {
+            description: config.description.clone(),
+            last_heartbeat_received: heartbeat.datetime.to_rfc3339(),
+            battery_avg_soc: battery_avg_soc,
+            total_energy_wh: battery::total_energy_wh_all(&batteries),
+            is_riegl_switch_on: heartbeat.is_riegl_switch_on,
         })
     }
 }
 
 impl BatteryStatus {
-    fn new(id: u8, state_of_charge: f32) -> BatteryStatus {
+    fn new(id: u8, battery: &battery::Heartbeat) -> BatteryStatus {
         BatteryStatus {
             id: id,
-            state_of_charge: state_of_charge,
+            state_of_charge: battery.state_of_charge,
+            total_energy_wh: battery.total_energy_wh(),
         }
     }
 }
@@ -227,3 +298,26 @@ impl LastScan {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glacio::atlas::SbdSource;
+    use serde_json::{self, Value};
+
+    #[test]
+    fn summary_without_batteries() {
+        let mut heartbeat = SbdSource::new("../glacio/data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .last()
+            .unwrap();
+        heartbeat.batteries.clear();
+        let summary = Summary::new(&Config::default(), Some(heartbeat)).unwrap();
+        assert_eq!(None, summary.battery_avg_soc);
+        assert_eq!(0., summary.total_energy_wh);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(Value::Null, json["battery_avg_soc"]);
+    }
+}
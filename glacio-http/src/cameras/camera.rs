@@ -1,5 +1,7 @@
 use {Error, Result};
 use cameras::{CameraConfig, Config, image};
+use chrono::{DateTime, Duration, Utc};
+use glacio::Image;
 use iron::Request;
 
 /// A serializable summary of a camera.
@@ -17,6 +19,8 @@ pub struct Summary {
     pub latest_image_redirect_url: String,
     /// The hourly interval that this camera takes pictures.
     pub interval: f32,
+    /// The group that this camera belongs to, if any.
+    pub group: Option<String>,
 }
 
 /// A serializable detail about camera data.
@@ -34,6 +38,58 @@ pub struct Detail {
     pub latest_image: image::Summary,
     /// The hourly interval that this camera takes pictures.
     pub interval: f32,
+    /// The group that this camera belongs to, if any.
+    pub group: Option<String>,
+}
+
+/// A camera's most recent image, for listing the latest images across every camera.
+#[derive(Serialize, Debug)]
+pub struct Latest {
+    /// The name of the camera.
+    pub name: String,
+    /// A description of the camera's location and its use.
+    pub description: String,
+    /// The url to retrieve detailed information about this camera.
+    pub url: String,
+    /// The most recent image captured by this camera, or `None` if it has no images.
+    pub latest_image: Option<image::Summary>,
+}
+
+/// How much a camera has been taking pictures, and whether it still is.
+#[derive(Serialize, Debug)]
+pub struct Stats {
+    /// The number of images.
+    pub image_count: usize,
+    /// The configured interval between pictures, in seconds.
+    pub interval_seconds: i64,
+    /// The datetime of the first image, or `None` if there are no images.
+    pub first: Option<DateTime<Utc>>,
+    /// The datetime of the latest image, or `None` if there are no images.
+    pub latest: Option<DateTime<Utc>>,
+    /// Is the latest image no more than two intervals old?
+    pub active: bool,
+    /// Every stretch between consecutive images that was longer than two intervals.
+    pub gaps: Vec<Gap>,
+}
+
+/// A camera's latest image and whether it's still active, for the overview.
+#[derive(Serialize, Debug)]
+pub struct Overview {
+    /// The name of the camera.
+    pub id: String,
+    /// The most recent image captured by this camera, or `None` if it has no images.
+    pub latest_image: Option<image::Summary>,
+    /// Is the latest image no more than two intervals old?
+    pub is_active: bool,
+}
+
+/// A stretch of time where a camera should have taken pictures, but didn't.
+#[derive(Serialize, Debug)]
+pub struct Gap {
+    /// The datetime of the image before the gap.
+    pub start: DateTime<Utc>,
+    /// The datetime of the image after the gap.
+    pub end: DateTime<Utc>,
 }
 
 impl Summary {
@@ -53,36 +109,129 @@ impl Summary {
                     .as_ref()
                     .to_string(),
             interval: camera.interval,
+            group: camera.group.clone(),
         }
     }
 }
 
 impl Detail {
-    /// Creates a new detail from a configuration and a request.
+    /// Creates a new detail from a configuration, a request, and the camera's images.
+    ///
+    /// The images don't need to be sorted. Returns an error if there are no images.
     pub fn new(
         request: &mut Request,
         camera_config: &CameraConfig,
+        images: &[Image],
         config: &Config,
     ) -> Result<Detail> {
         let summary = Summary::new(request, camera_config);
-        let camera = camera_config.to_camera()?;
-        let mut images = camera
-            .images()?
-            .filter_map(|result| result.ok())
-            .collect::<Vec<_>>();
-        if images.is_empty() {
-            return Err(Error::Config(
-                format!("No images found for camera: {:?}", camera),
-            ));
-        }
-        images.sort();
+        let latest_image = match images.iter().max() {
+            Some(image) => image,
+            None => {
+                return Err(Error::Config(
+                    format!("No images found for camera: {}", camera_config.name),
+                ))
+            }
+        };
         Ok(Detail {
             name: summary.name,
             description: summary.description,
             url: summary.url,
             images_url: summary.images_url,
-            latest_image: image::Summary::new(&images.pop().unwrap(), &config)?,
+            latest_image: image::Summary::new(latest_image, &camera_config.name, config)?,
             interval: summary.interval,
+            group: summary.group,
+        })
+    }
+}
+
+impl Latest {
+    /// Creates a new latest-image entry from a request, a configuration, and the camera's latest
+    /// image, if it has one.
+    pub fn new(
+        request: &mut Request,
+        camera_config: &CameraConfig,
+        latest_image: Option<&Image>,
+        config: &Config,
+    ) -> Result<Latest> {
+        Ok(Latest {
+            name: camera_config.name.clone(),
+            description: camera_config.description.clone(),
+            url: url_for!(request, "camera", "name" => camera_config.name.clone())
+                .as_ref()
+                .to_string(),
+            latest_image: match latest_image {
+                Some(image) => Some(image::Summary::new(image, &camera_config.name, config)?),
+                None => None,
+            },
         })
     }
 }
+
+impl Stats {
+    /// Creates stats from a camera's configuration and its images, as of `now`.
+    ///
+    /// The images don't need to be sorted.
+    pub fn new(camera_config: &CameraConfig, images: &[Image], now: DateTime<Utc>) -> Stats {
+        let interval = interval(camera_config);
+        let mut datetimes = images.iter().map(|image| image.datetime()).collect::<Vec<_>>();
+        datetimes.sort();
+        let latest = datetimes.last().cloned();
+        Stats {
+            image_count: datetimes.len(),
+            interval_seconds: interval.num_seconds(),
+            first: datetimes.first().cloned(),
+            latest: latest,
+            active: is_active(camera_config, latest, now),
+            gaps: datetimes
+                .windows(2)
+                .filter(|window| window[1].signed_duration_since(window[0]) > interval * 2)
+                .map(|window| {
+                    Gap {
+                        start: window[0],
+                        end: window[1],
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Overview {
+    /// Creates an overview from a camera's configuration and its latest image, as of `now`.
+    pub fn new(
+        camera_config: &CameraConfig,
+        latest_image: Option<&Image>,
+        config: &Config,
+        now: DateTime<Utc>,
+    ) -> Result<Overview> {
+        Ok(Overview {
+            id: camera_config.name.clone(),
+            latest_image: match latest_image {
+                Some(image) => Some(image::Summary::new(image, &camera_config.name, config)?),
+                None => None,
+            },
+            is_active: is_active(
+                camera_config,
+                latest_image.map(|image| image.datetime()),
+                now,
+            ),
+        })
+    }
+}
+
+fn interval(camera_config: &CameraConfig) -> Duration {
+    Duration::seconds((camera_config.interval * 3600.).round() as i64)
+}
+
+fn is_active(
+    camera_config: &CameraConfig,
+    latest: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> bool {
+    latest
+        .map(|latest| {
+            now.signed_duration_since(latest) <= interval(camera_config) * 2
+        })
+        .unwrap_or(false)
+}
@@ -1,13 +1,33 @@
 use {Error, Result};
-use glacio::camera::{Camera, Server};
+use chrono::FixedOffset;
+use glacio::camera::{Camera, Image, Server};
+use serde::{Deserialize, Deserializer};
+
+/// The default maximum number of images in one response.
+pub const DEFAULT_MAX_IMAGES_PER_RESPONSE: usize = 10_000;
 
 /// Global configuration for our remote cameras.
-#[derive(Default, Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 pub struct Config {
     /// The document root that is used to turn local paths into a url.
     pub document_root: String,
+    /// The base urls of the image servers.
+    ///
+    /// In TOML, this can be either a single string or an array of strings. The first server is
+    /// the primary, the rest are mirrors. If empty, glacio's default image server is used.
+    #[serde(default, deserialize_with = "string_or_vec")]
+    pub image_server: Vec<String>,
     /// A vector of cameras.
     pub cameras: Vec<CameraConfig>,
+    /// The maximum number of images that an unpaginated (`?all=true`) request can return.
+    ///
+    /// A misconfigured camera path can point at a whole archive, and serializing every image
+    /// in it can take down the server. Requests over this limit get a 413.
+    #[serde(default = "default_max_images_per_response")]
+    pub max_images_per_response: usize,
+    /// Let `?all=true` requests return more than `max_images_per_response` images.
+    #[serde(default)]
+    pub allow_unlimited_images: bool,
 }
 
 /// Configuration for a single camera.
@@ -24,10 +44,33 @@ pub struct CameraConfig {
     pub path: String,
     /// The expected hourly interval between pictures.
     pub interval: f32,
+    /// The UTC offset of the datetimes in this camera's image filenames, e.g. `"-03:00"`.
+    ///
+    /// Offsets can be written as `+HH:MM`, `+HHMM`, or `+HH`, or as `UTC` or `Z`. There's no time
+    /// zone database here, so IANA names like `America/Nuuk` aren't accepted. If not provided,
+    /// filenames are assumed to be in UTC.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// The group that this camera belongs to, e.g. `"greenland"`, so clients can list cameras by
+    /// region.
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            document_root: String::new(),
+            image_server: Vec::new(),
+            cameras: Vec::new(),
+            max_images_per_response: DEFAULT_MAX_IMAGES_PER_RESPONSE,
+            allow_unlimited_images: false,
+        }
+    }
 }
 
 impl Config {
-    /// Returns the image server for this configuration.
+    /// Returns the primary image server for this configuration.
     ///
     /// # Examples
     ///
@@ -37,10 +80,104 @@ impl Config {
     /// let server = config.server();
     /// ```
     pub fn server(&self) -> Result<Server> {
-        Server::new(&self.document_root).map_err(Error::from)
+        match self.image_server.first() {
+            Some(base_url) => Server::with_base_url(&self.document_root, base_url),
+            None => Server::new(&self.document_root),
+        }.map_err(Error::from)
+    }
+
+    /// Returns all image servers for this configuration, primary first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio_http::cameras::Config;
+    /// let config = Config { document_root: ".".to_string(), ..Default::default() };
+    /// assert_eq!(1, config.servers().unwrap().len());
+    /// ```
+    pub fn servers(&self) -> Result<Vec<Server>> {
+        if self.image_server.is_empty() {
+            return Ok(vec![self.server()?]);
+        }
+        self.image_server
+            .iter()
+            .map(|base_url| {
+                Server::with_base_url(&self.document_root, base_url).map_err(Error::from)
+            })
+            .collect()
+    }
+
+    /// Returns the image's url on the primary image server.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate glacio;
+    /// # extern crate glacio_http;
+    /// # use glacio::Image;
+    /// # use glacio_http::cameras::Config;
+    /// # fn main() {
+    /// let config = Config { document_root: "../glacio/data".to_string(), ..Default::default() };
+    /// let image = Image::new("../glacio/data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+    /// assert_eq!(
+    ///     "http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+    ///     config.image_url(&image).unwrap()
+    /// );
+    /// # }
+    /// ```
+    pub fn image_url(&self, image: &Image) -> Result<String> {
+        Ok(self.server()?.url_for(image)?.as_ref().to_string())
+    }
+
+    /// Returns the image's url on every configured image server, so clients can fail over.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate glacio;
+    /// # extern crate glacio_http;
+    /// # use glacio::Image;
+    /// # use glacio_http::cameras::Config;
+    /// # fn main() {
+    /// let config = Config { document_root: "../glacio/data".to_string(), ..Default::default() };
+    /// let image = Image::new("../glacio/data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+    /// assert_eq!(1, config.image_urls(&image).unwrap().len());
+    /// # }
+    /// ```
+    pub fn image_urls(&self, image: &Image) -> Result<Vec<String>> {
+        self.servers()?
+            .iter()
+            .map(|server| {
+                server
+                    .url_for(image)
+                    .map(|url| url.as_ref().to_string())
+                    .map_err(Error::from)
+            })
+            .collect()
     }
 }
 
+fn default_max_images_per_response() -> usize {
+    DEFAULT_MAX_IMAGES_PER_RESPONSE
+}
+
+fn string_or_vec<'de, D>(deserializer: D) -> ::std::result::Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrVec {
+        String(String),
+        Vec(Vec<String>),
+    }
+
+    Ok(match StringOrVec::deserialize(deserializer)? {
+        StringOrVec::String(s) => vec![s],
+        StringOrVec::Vec(v) => v,
+    })
+}
+
 impl CameraConfig {
     /// Returns the glacio camera for this configuration.
     ///
@@ -52,6 +189,226 @@ impl CameraConfig {
     /// let camera = config.to_camera().unwrap();
     /// ```
     pub fn to_camera(&self) -> Result<Camera> {
-        Camera::new(&self.path).map_err(Error::from)
+        Camera::with_offset(&self.path, self.offset()?).map_err(Error::from)
+    }
+
+    /// Returns the UTC offset of this camera's image filenames.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio_http;
+    /// # use chrono::FixedOffset;
+    /// # use glacio_http::cameras::CameraConfig;
+    /// # fn main() {
+    /// let mut config = CameraConfig::default();
+    /// assert_eq!(FixedOffset::east(0), config.offset().unwrap());
+    /// config.timezone = Some("-03:00".to_string());
+    /// assert_eq!(FixedOffset::west(3 * 3600), config.offset().unwrap());
+    /// config.timezone = Some("America/Nuuk".to_string());
+    /// assert!(config.offset().is_err());
+    /// # }
+    /// ```
+    pub fn offset(&self) -> Result<FixedOffset> {
+        match self.timezone {
+            Some(ref timezone) => {
+                parse_offset(timezone).ok_or_else(|| {
+                    Error::Config(format!(
+                        "invalid timezone for camera {}: {} (expected a UTC offset, e.g. -03:00)",
+                        self.name,
+                        timezone
+                    ))
+                })
+            }
+            None => Ok(FixedOffset::east(0)),
+        }
+    }
+}
+
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    if s == "UTC" || s == "Z" {
+        return Some(FixedOffset::east(0));
+    }
+    let (sign, rest) = match s.chars().next() {
+        Some('+') => (1, &s[1..]),
+        Some('-') => (-1, &s[1..]),
+        _ => return None,
+    };
+    let digits = match rest.len() {
+        5 if rest.as_bytes()[2] == b':' => format!("{}{}", &rest[0..2], &rest[3..5]),
+        2 | 4 => rest.to_string(),
+        _ => return None,
+    };
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let hours = digits[0..2].parse::<i32>().ok()?;
+    let minutes = if digits.len() == 4 {
+        digits[2..4].parse::<i32>().ok()?
+    } else {
+        0
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glacio::Image;
+    use toml;
+
+    const IMAGE: &'static str = "../glacio/data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg";
+
+    #[test]
+    fn one_image_server() {
+        let config: Config = toml::from_str(
+            r#"
+            document_root = "../glacio/data"
+            image_server = "http://one.example.com"
+            cameras = []
+            "#,
+        ).unwrap();
+        assert_eq!(vec!["http://one.example.com"], config.image_server);
+        let image = Image::new(IMAGE).unwrap();
+        assert_eq!(
+            "http://one.example.com/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+            config.image_url(&image).unwrap()
+        );
+    }
+
+    #[test]
+    fn max_images_per_response() {
+        let config: Config = toml::from_str(
+            r#"
+            document_root = "../glacio/data"
+            cameras = []
+            "#,
+        ).unwrap();
+        assert_eq!(DEFAULT_MAX_IMAGES_PER_RESPONSE, config.max_images_per_response);
+        assert!(!config.allow_unlimited_images);
+        let config: Config = toml::from_str(
+            r#"
+            document_root = "../glacio/data"
+            cameras = []
+            max_images_per_response = 5
+            allow_unlimited_images = true
+            "#,
+        ).unwrap();
+        assert_eq!(5, config.max_images_per_response);
+        assert!(config.allow_unlimited_images);
+    }
+
+    #[test]
+    fn offsets() {
+        let cases = [
+            ("UTC", Some(0)),
+            ("Z", Some(0)),
+            ("+00:00", Some(0)),
+            ("-03:00", Some(-3 * 3600)),
+            ("+0530", Some(5 * 3600 + 30 * 60)),
+            ("-08", Some(-8 * 3600)),
+            ("-3", None),
+            ("03:00", None),
+            ("+03:60", None),
+            ("+030:0", None),
+            ("+24:00", None),
+            ("America/Nuuk", None),
+            ("", None),
+        ];
+        for &(s, expected) in &cases {
+            assert_eq!(
+                expected.map(FixedOffset::east),
+                parse_offset(s),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn camera_timezone() {
+        let config: Config = toml::from_str(
+            r#"
+            document_root = "../glacio/data"
+
+            [[cameras]]
+            name = "ATLAS_CAM"
+            description = "Greenland time"
+            path = "../glacio/data/ATLAS_CAM"
+            interval = 1.0
+            timezone = "-03:00"
+            "#,
+        ).unwrap();
+        let camera = &config.cameras[0];
+        assert_eq!(Some("-03:00".to_string()), camera.timezone);
+        let image = camera.to_camera().unwrap().latest_image().unwrap();
+        assert_eq!("2017-08-06T18:25:00+00:00", image.datetime().to_rfc3339());
+    }
+
+    #[test]
+    fn camera_group() {
+        let config: Config = toml::from_str(
+            r#"
+            document_root = "../glacio/data"
+
+            [[cameras]]
+            name = "ATLAS_CAM"
+            description = "In a group"
+            path = "../glacio/data/ATLAS_CAM"
+            interval = 1.0
+            group = "greenland"
+
+            [[cameras]]
+            name = "ATLAS_CAM2"
+            description = "Not in a group"
+            path = "../glacio/data/ATLAS_CAM2"
+            interval = 1.0
+            "#,
+        ).unwrap();
+        assert_eq!(Some("greenland".to_string()), config.cameras[0].group);
+        assert_eq!(None, config.cameras[1].group);
+    }
+
+    #[test]
+    fn two_image_servers() {
+        let config: Config = toml::from_str(
+            r#"
+            document_root = "../glacio/data"
+            image_server = ["http://one.example.com", "http://two.example.com/mirror/"]
+            cameras = []
+            "#,
+        ).unwrap();
+        let image = Image::new(IMAGE).unwrap();
+        assert_eq!(
+            vec![
+                "http://one.example.com/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+                "http://two.example.com/mirror/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+            ],
+            config.image_urls(&image).unwrap()
+        );
+        assert_eq!(
+            "http://one.example.com/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+            config.image_url(&image).unwrap()
+        );
+    }
+
+    #[test]
+    fn no_image_server() {
+        let config: Config = toml::from_str(
+            r#"
+            document_root = "../glacio/data"
+            cameras = []
+            "#,
+        ).unwrap();
+        assert!(config.image_server.is_empty());
+        let image = Image::new(IMAGE).unwrap();
+        assert_eq!(
+            vec!["http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg"],
+            config.image_urls(&image).unwrap()
+        );
     }
 }
@@ -2,11 +2,15 @@
 
 use {Error, Paginate, Result};
 use cameras::{CameraConfig, Config, camera, image};
+use chrono::Utc;
 use glacio::Image;
 use iron::{IronResult, Request, Response, status};
 use iron::headers::Location;
 use json;
+use param;
 use router::Router;
+use source::ImageSource;
+use std::sync::Arc;
 
 /// A multi-route handler for camera-based requests.
 ///
@@ -18,21 +22,36 @@ use router::Router;
 #[derive(Clone, Debug)]
 pub struct Cameras {
     config: Config,
+    source: Arc<ImageSource>,
 }
 
 impl From<Config> for Cameras {
     fn from(config: Config) -> Cameras {
-        Cameras { config: config }
+        let source = Arc::new(config.clone());
+        Cameras::new(config, source)
     }
 }
 
 impl Cameras {
+    /// Creates a new handler that reads its images from the provided source.
+    pub fn new(config: Config, source: Arc<ImageSource>) -> Cameras {
+        Cameras {
+            config: config,
+            source: source,
+        }
+    }
+
     /// Returns a list of all configured cameras.
+    ///
+    /// If a `group` parameter is provided, only the cameras in that group are returned, so
+    /// cameras without a group are only listed when there's no filter.
     pub fn summary(&self, request: &mut Request) -> IronResult<Response> {
+        let group: Option<String> = param::parse_param(request, "group", "a camera group")?;
         json::response(
             self.config
                 .cameras
                 .iter()
+                .filter(|config| group.is_none() || config.group == group)
                 .map(|config| camera::Summary::new(request, config))
                 .collect::<Vec<_>>(),
         )
@@ -41,39 +60,69 @@ impl Cameras {
     /// Returns detail about one camera, as requested in the parameters.
     pub fn detail(&self, request: &mut Request) -> IronResult<Response> {
         let camera_config = iexpect!(self.camera_config(request));
-        json::response(itry!(
-            camera::Detail::new(request, camera_config, &self.config)
-        ))
+        let images = self.camera_config_images(camera_config)?;
+        json::response(camera::Detail::new(
+            request,
+            camera_config,
+            &images,
+            &self.config,
+        )?)
     }
 
     /// Returns a (paginated) list of images associated with the asked-for camera, starting with
     /// the most recent images.
+    ///
+    /// If a `since` parameter is provided, only images taken at or after that datetime are
+    /// returned.
+    ///
+    /// With `all=true`, every image is returned instead of a page. Because that can be a lot of
+    /// images, the count is checked against `max_images_per_response` before anything is
+    /// serialized, and a 413 is returned if there are too many (unless `allow_unlimited_images`
+    /// is set).
     pub fn images(&self, request: &mut Request) -> IronResult<Response> {
         let camera_config = iexpect!(self.camera_config(request));
-        let mut images = itry!(self.camera_config_images(camera_config));
+        let mut images = self.camera_config_images(camera_config)?;
+        if let Some(since) = param::parse_datetime_param(request, "since")? {
+            images.retain(|image| image.datetime() >= since);
+        }
         images.sort_by(|a, b| b.cmp(a));
-        let image_summaries = itry!(images.into_iter().paginate(request).and_then(|iter| {
-            iter.map(|image| image::Summary::new(&image, &self.config))
-                .collect::<Result<Vec<_>>>()
-        }));
+        let all = param::parse_param(request, "all", "true or false")?.unwrap_or(false);
+        let images = if all {
+            if images.len() > self.config.max_images_per_response &&
+                !self.config.allow_unlimited_images
+            {
+                return Err(
+                    Error::TooManyImages {
+                        count: images.len(),
+                        max: self.config.max_images_per_response,
+                    }.into(),
+                );
+            }
+            images
+        } else {
+            images.into_iter().paginate(request)?.collect()
+        };
+        let image_summaries = images
+            .iter()
+            .map(|image| {
+                image::Summary::new(image, &camera_config.name, &self.config)
+            })
+            .collect::<Result<Vec<_>>>()?;
         json::response(image_summaries)
     }
 
+    /// Returns how many images the camera has, when it took them, and whether it's still active.
+    pub fn stats(&self, request: &mut Request) -> IronResult<Response> {
+        let camera_config = iexpect!(self.camera_config(request));
+        let images = self.camera_config_images(camera_config)?;
+        json::response(camera::Stats::new(camera_config, &images, Utc::now()))
+    }
+
     /// Returns the image nearest to the parsed datetime.
     pub fn nearest_image(&self, request: &mut Request) -> IronResult<Response> {
-        use chrono::{DateTime, Utc};
-
         let camera_config = iexpect!(self.camera_config(request));
-        let images = itry!(self.camera_config_images(camera_config));
-        let datetime: DateTime<Utc> = itry!(
-            request
-                .extensions
-                .get::<Router>()
-                .unwrap()
-                .find("datetime")
-                .unwrap()
-                .parse()
-        );
+        let images = self.camera_config_images(camera_config)?;
+        let datetime = param::parse_datetime_route_param(request, "datetime")?;
         let image = iexpect!(images.iter().min_by_key(|image| {
             image
                 .datetime()
@@ -81,18 +130,68 @@ impl Cameras {
                 .num_seconds()
                 .abs()
         }));
-        json::response(itry!(image::Summary::new(&image, &self.config)))
+        json::response(image::Summary::new(
+            &image,
+            &camera_config.name,
+            &self.config,
+        )?)
+    }
+
+    /// Returns the latest image from every camera, most recent first.
+    ///
+    /// Cameras without any images are left out, unless `include_empty=true`, in which case they
+    /// come last with a `null` latest image. A camera whose images can't be read is logged and
+    /// always included with a `null` latest image, just like in the overview, so one broken
+    /// camera doesn't take down the whole response.
+    pub fn latest_images(&self, request: &mut Request) -> IronResult<Response> {
+        let include_empty = param::parse_param(request, "include_empty", "true or false")?
+            .unwrap_or(false);
+        let mut latest = Vec::new();
+        for camera_config in &self.config.cameras {
+            match self.source.latest_image(camera_config) {
+                Ok(image) => {
+                    if image.is_some() || include_empty {
+                        latest.push((camera_config, image));
+                    }
+                }
+                Err(err) => {
+                    error!(
+                        "could not read the latest image of camera {}: {}",
+                        camera_config.name,
+                        err
+                    );
+                    latest.push((camera_config, None));
+                }
+            }
+        }
+        latest.sort_by(|a, b| b.1.cmp(&a.1));
+        let latest = latest
+            .iter()
+            .map(|&(camera_config, ref image)| {
+                camera::Latest::new(request, camera_config, image.as_ref(), &self.config)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        json::response(latest)
+    }
+
+    /// Returns the latest image for this camera, or a 404 if the camera doesn't have any images.
+    pub fn latest_image(&self, request: &mut Request) -> IronResult<Response> {
+        let camera_config = iexpect!(self.camera_config(request));
+        let image = iexpect!(self.source.latest_image(camera_config)?);
+        json::response(image::Summary::new(
+            &image,
+            &camera_config.name,
+            &self.config,
+        )?)
     }
 
     /// Returns a redirect to the src url for the latest image for this camera.
     pub fn latest_image_redirect(&self, request: &mut Request) -> IronResult<Response> {
         let camera_config = iexpect!(self.camera_config(request));
-        let camera = itry!(camera_config.to_camera());
-        let image = iexpect!(camera.latest_image());
-        let server = itry!(self.config.server());
-        let url = itry!(server.url_for(&image));
+        let image = iexpect!(self.source.latest_image(camera_config)?);
+        let url = self.config.image_url(&image)?;
         let mut response = Response::with(status::Found);
-        response.headers.set(Location(url.to_string()));
+        response.headers.set(Location(url));
         Ok(response)
     }
 
@@ -114,23 +213,34 @@ impl Cameras {
     }
 
     fn camera_config_images(&self, camera_config: &CameraConfig) -> Result<Vec<Image>> {
-        let camera = camera_config.to_camera()?;
-        camera
-            .images()?
-            .map(|r| r.map_err(Error::from))
-            .collect::<Result<Vec<_>>>()
+        self.source.images(camera_config)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use {Api, Config};
+    use {Api, Config, Error, Result};
     use cameras::CameraConfig;
+    use chrono::{Duration, TimeZone, Utc};
+    use glacio::Image;
+    use glacio::camera::fixture::{self, FixtureSpec};
     use iron::Headers;
     use iron::headers::Location;
     use iron::status::Status;
     use iron_test::{ProjectBuilder, request, response};
+    use param;
     use serde_json::{self, Value};
+    use source::ImageSource;
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct Broken;
+
+    impl ImageSource for Broken {
+        fn images(&self, _: &CameraConfig) -> Result<Vec<Image>> {
+            Err(Error::Config("the image source is broken".to_string()))
+        }
+    }
 
     fn build_api(builder: &ProjectBuilder) -> Api {
         let mut config = Config::new();
@@ -171,6 +281,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cameras_group() {
+        let builder = ProjectBuilder::new("cameras-group");
+        let mut config = Config::new();
+        config.cameras.document_root = builder.root().to_string_lossy().into_owned();
+        for &(name, group) in &[
+            ("ATLAS_CAM", Some("greenland")),
+            ("HEL_DUAL_1", Some("greenland")),
+            ("ALASKA_CAM", Some("alaska")),
+            ("UNGROUPED_CAM", None),
+        ]
+        {
+            config.cameras.cameras.push(CameraConfig {
+                name: name.to_string(),
+                path: format!("{}/{}", builder.root().display(), name),
+                interval: 3.,
+                group: group.map(|group| group.to_string()),
+                ..Default::default()
+            });
+        }
+        let api = Api::new(config).unwrap();
+        let names = |url| {
+            let response = request::get(url, Headers::new(), &api).unwrap();
+            let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+                .unwrap();
+            json.as_array()
+                .unwrap()
+                .iter()
+                .map(|camera| camera["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec!["ATLAS_CAM", "HEL_DUAL_1"],
+            names("http://localhost:3000/cameras?group=greenland")
+        );
+        assert_eq!(
+            vec!["ALASKA_CAM"],
+            names("http://localhost:3000/cameras?group=alaska")
+        );
+        assert!(names("http://localhost:3000/cameras?group=antarctica").is_empty());
+        assert_eq!(4, names("http://localhost:3000/cameras").len());
+
+        let response = request::get("http://localhost:3000/cameras", Headers::new(), &api)
+            .unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!("greenland", json[0]["group"]);
+        assert_eq!(Value::Null, json[3]["group"]);
+    }
+
     #[test]
     fn camera() {
         let builder =
@@ -196,6 +357,7 @@ mod tests {
         );
         assert_eq!(3.0, *camera.get("interval").unwrap());
         let image = camera.get("latest_image").unwrap();
+        assert_eq!("ATLAS_CAM", image.get("camera_name").unwrap());
         assert_eq!("2017-08-06T15:25:00+00:00", image.get("datetime").unwrap());
         assert_eq!(
             "http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
@@ -203,6 +365,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn camera_stats() {
+        let builder = ProjectBuilder::new("camera");
+        builder.build();
+        let start = Utc.ymd(2017, 8, 1).and_hms(0, 0, 0);
+        let spec = FixtureSpec::new(start, Duration::hours(3), 200)
+            .camera("ATLAS_CAM")
+            .gap(start + Duration::days(5), start + Duration::days(7));
+        fixture::build(&builder.root(), &spec).unwrap();
+        let handler = build_api(&builder);
+        let response = request::get(
+            "http://localhost:3000/cameras/ATLAS_CAM/stats",
+            Headers::new(),
+            &handler,
+        ).unwrap();
+        let stats: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!(184, stats["image_count"]);
+        assert_eq!(10800, stats["interval_seconds"]);
+        assert_eq!("2017-08-01T00:00:00+00:00", stats["first"]);
+        assert_eq!("2017-08-25T21:00:00+00:00", stats["latest"]);
+        assert_eq!(false, stats["active"]);
+        assert_eq!(1, stats["gaps"].as_array().unwrap().len());
+        assert_eq!("2017-08-05T21:00:00+00:00", stats["gaps"][0]["start"]);
+        assert_eq!("2017-08-08T00:00:00+00:00", stats["gaps"][0]["end"]);
+
+        let err = request::get(
+            "http://localhost:3000/cameras/NOT_A_CAM/stats",
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(Some(Status::NotFound), err.response.status);
+    }
+
+    #[test]
+    fn camera_images_every_page() {
+        let builder = ProjectBuilder::new("camera");
+        builder.build();
+        let start = Utc.ymd(2017, 8, 1).and_hms(0, 0, 0);
+        let spec = FixtureSpec::new(start, Duration::hours(1), 250).camera("ATLAS_CAM");
+        fixture::build(&builder.root(), &spec).unwrap();
+        let handler = build_api(&builder);
+        let mut datetimes = Vec::new();
+        for page in 1..5 {
+            let response = request::get(
+                &format!(
+                    "http://localhost:3000/cameras/ATLAS_CAM/images?per_page=100&page={}",
+                    page
+                ),
+                Headers::new(),
+                &handler,
+            ).unwrap();
+            let images: Value = serde_json::from_str(&response::extract_body_to_string(response))
+                .unwrap();
+            datetimes.extend(images.as_array().unwrap().iter().map(|image| {
+                image["datetime"].as_str().unwrap().to_string()
+            }));
+        }
+        assert_eq!(250, datetimes.len());
+        assert_eq!("2017-08-11T09:00:00+00:00", datetimes[0]);
+        assert_eq!("2017-08-01T00:00:00+00:00", datetimes[249]);
+        assert!(datetimes.windows(2).all(|window| window[0] > window[1]));
+    }
+
     #[test]
     fn camera_images() {
         let mut builder = ProjectBuilder::new("camera");
@@ -219,6 +445,7 @@ mod tests {
         let images: Value = serde_json::from_str(&response::extract_body_to_string(response))
             .unwrap();
         let image = images.get(0).unwrap();
+        assert_eq!("ATLAS_CAM", image.get("camera_name").unwrap());
         assert_eq!("2017-08-06T15:25:07+00:00", image.get("datetime").unwrap());
         assert_eq!(
             "http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_152507.jpg",
@@ -233,6 +460,175 @@ mod tests {
         assert_eq!(None, images.get(2));
     }
 
+    #[test]
+    fn camera_images_since() {
+        let mut builder = ProjectBuilder::new("camera");
+        for i in 0..10 {
+            builder = builder.file(format!("ATLAS_CAM/ATLAS_CAM_20170806_15250{}.jpg", i), "");
+        }
+        builder.build();
+        let handler = build_api(&builder);
+        let get = |url| {
+            let response = request::get(url, Headers::new(), &handler).unwrap();
+            serde_json::from_str::<Value>(&response::extract_body_to_string(response)).unwrap()
+        };
+        let images =
+            get("http://localhost:3000/cameras/ATLAS_CAM/images?since=2017-08-06T15:25:07Z");
+        assert_eq!(3, images.as_array().unwrap().len());
+        assert_eq!("2017-08-06T15:25:07+00:00", images[2]["datetime"]);
+        let images = get("http://localhost:3000/cameras/ATLAS_CAM/images?since=20170807");
+        assert_eq!(0, images.as_array().unwrap().len());
+        let images = get("http://localhost:3000/cameras/ATLAS_CAM/images?since=-1d");
+        assert_eq!(0, images.as_array().unwrap().len());
+
+        let err = request::get(
+            "http://localhost:3000/cameras/ATLAS_CAM/images?since=soon",
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(Some(Status::BadRequest), err.response.status);
+    }
+
+    #[test]
+    fn camera_images_all() {
+        let mut builder = ProjectBuilder::new("camera");
+        for i in 0..5 {
+            builder = builder.file(format!("ATLAS_CAM/ATLAS_CAM_20170806_15250{}.jpg", i), "");
+        }
+        builder.build();
+        let api = |max, allow_unlimited| {
+            let mut config = Config::new();
+            config.cameras.document_root = builder.root().to_string_lossy().into_owned();
+            config.cameras.max_images_per_response = max;
+            config.cameras.allow_unlimited_images = allow_unlimited;
+            config.cameras.cameras.push(CameraConfig {
+                name: "ATLAS_CAM".to_string(),
+                path: format!("{}/ATLAS_CAM", builder.root().display()),
+                ..Default::default()
+            });
+            Api::new(config).unwrap()
+        };
+        let count = |api: &Api, url: &str| {
+            let response = request::get(url, Headers::new(), api).unwrap();
+            let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+                .unwrap();
+            json.as_array().unwrap().len()
+        };
+        let url = "http://localhost:3000/cameras/ATLAS_CAM/images";
+        let all = &format!("{}?all=true", url);
+
+        assert_eq!(5, count(&api(5, false), all));
+        assert_eq!(2, count(&api(5, false), &format!("{}?per_page=2", url)));
+        assert_eq!(5, count(&api(2, true), all));
+        assert_eq!(
+            2,
+            count(
+                &api(2, false),
+                &format!("{}&since=2017-08-06T15:25:03Z", all),
+            )
+        );
+
+        let err = request::get(all, Headers::new(), &api(2, false)).unwrap_err();
+        assert_eq!(Some(Status::PayloadTooLarge), err.response.status);
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(err.response))
+            .unwrap();
+        assert!(json["message"].as_str().unwrap().contains("paginate"));
+
+        let err = request::get(&format!("{}?all=maybe", url), Headers::new(), &api(5, false))
+            .unwrap_err();
+        assert_eq!(Some(Status::BadRequest), err.response.status);
+    }
+
+    #[test]
+    fn camera_images_skips_bad_files() {
+        let builder = ProjectBuilder::new("camera")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", "")
+            .file("ATLAS_CAM/ATLAS_CAM.jpg", "");
+        builder.build();
+        let handler = build_api(&builder);
+        let response = request::get(
+            "http://localhost:3000/cameras/ATLAS_CAM/images",
+            Headers::new(),
+            &handler,
+        ).unwrap();
+        assert_eq!(Some(Status::Ok), response.status);
+        let images: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!(1, images.as_array().unwrap().len());
+    }
+
+    #[test]
+    fn camera_nearest_image_bad_datetime() {
+        let builder =
+            ProjectBuilder::new("camera").file("ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", "");
+        builder.build();
+        let handler = build_api(&builder);
+        let err = request::get(
+            "http://localhost:3000/cameras/ATLAS_CAM/images/nearest/banana",
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(Some(Status::BadRequest), err.response.status);
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(err.response))
+            .unwrap();
+        assert_eq!("datetime", json["param"]);
+        assert_eq!("banana", json["value"]);
+        assert_eq!(param::DATETIME_EXPECTED, json["expected"]);
+    }
+
+    #[test]
+    fn camera_images_negative_page() {
+        let builder =
+            ProjectBuilder::new("camera").file("ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", "");
+        builder.build();
+        let handler = build_api(&builder);
+        let err = request::get(
+            "http://localhost:3000/cameras/ATLAS_CAM/images?page=-1",
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(Some(Status::BadRequest), err.response.status);
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(err.response))
+            .unwrap();
+        assert_eq!("page", json["param"]);
+        assert_eq!("-1", json["value"]);
+    }
+
+    #[test]
+    fn camera_latest_image() {
+        let builder = ProjectBuilder::new("camera")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", "")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_182500.jpg", "")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_122500.jpg", "");
+        builder.build();
+        let handler = build_api(&builder);
+        let response = request::get(
+            "http://localhost:3000/cameras/ATLAS_CAM/images/latest",
+            Headers::new(),
+            &handler,
+        ).unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!("2017-08-06T18:25:00+00:00", json["datetime"]);
+        assert_eq!(
+            "http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_182500.jpg",
+            json["url"]
+        );
+    }
+
+    #[test]
+    fn camera_latest_image_empty() {
+        let builder = ProjectBuilder::new("camera").file("ATLAS_CAM/ATLAS_CAM.jpg", "");
+        builder.build();
+        let handler = build_api(&builder);
+        let err = request::get(
+            "http://localhost:3000/cameras/ATLAS_CAM/images/latest",
+            Headers::new(),
+            &handler,
+        ).unwrap_err();
+        assert_eq!(Some(Status::NotFound), err.response.status);
+    }
+
     #[test]
     fn camera_latest_image_src() {
         let mut builder = ProjectBuilder::new("camera");
@@ -254,4 +650,92 @@ mod tests {
             response.headers.get::<Location>().unwrap()
         );
     }
+
+    #[test]
+    fn latest_images() {
+        let builder = ProjectBuilder::new("camera")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", "")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_182500.jpg", "")
+            .file("HEL_BERGCAM3/HEL_BERGCAM3_20170825_120000.jpg", "")
+            .file("EMPTY_CAM/README", "");
+        builder.build();
+        let mut config = Config::new();
+        config.cameras.document_root = builder.root().to_string_lossy().into_owned();
+        for name in &["ATLAS_CAM", "EMPTY_CAM", "HEL_BERGCAM3"] {
+            config.cameras.cameras.push(CameraConfig {
+                name: name.to_string(),
+                description: format!("The {} camera", name),
+                path: format!("{}/{}", builder.root().display(), name),
+                ..Default::default()
+            });
+        }
+        let api = Api::new(config).unwrap();
+        let get = |url| {
+            let response = request::get(url, Headers::new(), &api).unwrap();
+            serde_json::from_str::<Value>(&response::extract_body_to_string(response)).unwrap()
+        };
+
+        let json = get("http://localhost:3000/images/latest");
+        assert_eq!(2, json.as_array().unwrap().len());
+        assert_eq!("HEL_BERGCAM3", json[0]["name"]);
+        assert_eq!("The HEL_BERGCAM3 camera", json[0]["description"]);
+        assert_eq!("http://localhost:3000/cameras/HEL_BERGCAM3", json[0]["url"]);
+        assert_eq!("HEL_BERGCAM3", json[0]["latest_image"]["camera_name"]);
+        assert_eq!(
+            "2017-08-25T12:00:00+00:00",
+            json[0]["latest_image"]["datetime"]
+        );
+        assert_eq!("ATLAS_CAM", json[1]["name"]);
+        assert_eq!(
+            "http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_182500.jpg",
+            json[1]["latest_image"]["url"]
+        );
+
+        let json = get("http://localhost:3000/images/latest?include_empty=true");
+        assert_eq!(3, json.as_array().unwrap().len());
+        assert_eq!("EMPTY_CAM", json[2]["name"]);
+        assert_eq!("The EMPTY_CAM camera", json[2]["description"]);
+        assert_eq!(Value::Null, json[2]["latest_image"]);
+
+        let err = request::get(
+            "http://localhost:3000/images/latest?include_empty=maybe",
+            Headers::new(),
+            &api,
+        ).unwrap_err();
+        assert_eq!(Some(Status::BadRequest), err.response.status);
+    }
+
+    #[test]
+    fn camera_images_broken_source() {
+        let mut config = Config::new();
+        config.cameras.cameras.push(CameraConfig {
+            name: "ATLAS_CAM".to_string(),
+            ..Default::default()
+        });
+        let heartbeats = Arc::new(config.atlas.clone());
+        let api = Api::with_sources(config, heartbeats, Arc::new(Broken)).unwrap();
+        for url in &[
+            "http://localhost:3000/cameras/ATLAS_CAM",
+            "http://localhost:3000/cameras/ATLAS_CAM/images",
+            "http://localhost:3000/cameras/ATLAS_CAM/images/latest/redirect",
+        ]
+        {
+            let err = request::get(url, Headers::new(), &api).unwrap_err();
+            assert_eq!(Some(Status::InternalServerError), err.response.status);
+            let json: Value =
+                serde_json::from_str(&response::extract_body_to_string(err.response)).unwrap();
+            assert_eq!(
+                "api configuration error: the image source is broken",
+                json["message"]
+            );
+        }
+
+        let response = request::get("http://localhost:3000/images/latest", Headers::new(), &api)
+            .unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+        assert_eq!(1, json.as_array().unwrap().len());
+        assert_eq!("ATLAS_CAM", json[0]["name"]);
+        assert_eq!(Value::Null, json[0]["latest_image"]);
+    }
 }
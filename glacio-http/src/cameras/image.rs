@@ -5,19 +5,28 @@ use glacio::camera::Image;
 /// A summary of information about an image.
 #[derive(Debug, Serialize)]
 pub struct Summary {
+    /// The name of the camera that took the image.
+    ///
+    /// Included so that images make sense on their own, e.g. when images from several cameras
+    /// are mixed together.
+    pub camera_name: String,
     /// The image's date and time, as a string.
     pub datetime: String,
-    /// The image's url on a remote server.
+    /// The image's url on the primary image server.
     pub url: String,
+    /// The image's urls on every configured image server, primary first.
+    pub urls: Vec<String>,
 }
 
 impl Summary {
-    /// Creates a new summary from a server and an `Image`.
-    pub fn new(image: &Image, config: &Config) -> Result<Summary> {
-        let server = config.server()?;
+    /// Creates a new summary for an `Image` taken by the named camera.
+    pub fn new(image: &Image, camera_name: &str, config: &Config) -> Result<Summary> {
+        let urls = config.image_urls(image)?;
         Ok(Summary {
+            camera_name: camera_name.to_string(),
             datetime: image.datetime().to_rfc3339(),
-            url: server.url_for(image)?.as_ref().to_string(),
+            url: urls[0].clone(),
+            urls: urls,
         })
     }
 }
@@ -9,4 +9,5 @@ mod camera;
 mod config;
 mod image;
 
+pub use self::camera::Overview as CameraOverview;
 pub use self::config::{CameraConfig, Config};
@@ -14,6 +14,32 @@ pub struct Config {
     pub atlas: atlas::Config,
     /// Configuration for our remote cameras.
     pub cameras: cameras::Config,
+    /// The origins that are allowed to make cross-origin requests, e.g.
+    /// `["https://dashboard.example.com"]`.
+    ///
+    /// If empty, which is the default, any origin is allowed.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Serve the api over HTTPS, rather than plain HTTP.
+    ///
+    /// The api itself doesn't care, this is for the server that runs it. The `glacio` binary
+    /// needs to be built with its `tls` feature to use it, and only reads it at startup, so
+    /// reloading the configuration doesn't change it.
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+/// HTTPS configuration.
+#[derive(Clone, Deserialize, Default, Debug)]
+pub struct TlsConfig {
+    /// The path to a PKCS #12 archive with the server's certificate chain and private key.
+    ///
+    /// To make one from PEM files, use e.g. `openssl pkcs12 -export -inkey key.pem -in cert.pem
+    /// -out identity.p12`.
+    pub identity_file: String,
+    /// The password for the PKCS #12 archive.
+    #[serde(default)]
+    pub password: String,
 }
 
 impl Config {
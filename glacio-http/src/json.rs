@@ -1,5 +1,7 @@
-use iron::{IronResult, Response, status};
+use Error;
+use iron::{IronError, IronResult, Response, status};
 use iron::headers::ContentType;
+use iron::modifiers::Header;
 use serde::Serialize;
 use serde_json;
 
@@ -9,3 +11,26 @@ pub fn response<S: Serialize>(data: S) -> IronResult<Response> {
     response.headers.set(ContentType::json());
     Ok(response)
 }
+
+/// Turns an error into an Iron error with a JSON body.
+///
+/// The body always has a `message`. Parameter errors also say which parameter was bad and what we
+/// expected it to be.
+pub fn error(err: Error, status: status::Status) -> IronError {
+    let body = match err {
+        Error::InvalidParam {
+            ref name,
+            ref value,
+            expected,
+        } => {
+            json!({
+                "message": err.to_string(),
+                "param": name,
+                "value": value,
+                "expected": expected,
+            })
+        }
+        _ => json!({ "message": err.to_string() }),
+    };
+    IronError::new(err, (status, body.to_string(), Header(ContentType::json())))
+}
@@ -13,6 +13,8 @@ extern crate glacio;
 extern crate iron;
 #[cfg(test)]
 extern crate iron_test;
+#[macro_use]
+extern crate log;
 extern crate logger;
 extern crate params;
 extern crate percent_encoding;
@@ -27,16 +29,28 @@ extern crate toml;
 
 pub mod atlas;
 pub mod cameras;
+pub mod overview;
 pub mod paginate;
+pub mod param;
+pub mod source;
 
 mod api;
 mod config;
 mod json;
 
 pub use api::Api;
-pub use config::Config;
+pub use config::{Config, TlsConfig};
 pub use paginate::Paginate;
 
+/// The version of this crate, as recorded in its `Cargo.toml`.
+///
+/// # Examples
+///
+/// ```
+/// assert!(!glacio_http::VERSION.is_empty());
+/// ```
+pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
 /// Our custom error enum.
 #[derive(Debug)]
 pub enum Error {
@@ -46,12 +60,28 @@ pub enum Error {
     Camera(glacio::camera::Error),
     /// Invalid configuration.
     Config(String),
+    /// A request parameter could not be parsed.
+    InvalidParam {
+        /// The name of the parameter.
+        name: String,
+        /// The value that was provided.
+        value: String,
+        /// A description of what we expected, e.g. "a positive integer".
+        expected: &'static str,
+    },
     /// Wrapper around `std::io::Error`.
     Io(std::io::Error),
     /// Wrapper around `std::num::ParseIntError`.
     ParseInt(std::num::ParseIntError),
     /// Wrapper around `toml::de::Error`.
     TomlDe(toml::de::Error),
+    /// A request would have returned more images than the configured maximum.
+    TooManyImages {
+        /// The number of images that would have been returned.
+        count: usize,
+        /// The configured maximum number of images per response.
+        max: usize,
+    },
 }
 
 /// Our custom result type.
@@ -93,9 +123,11 @@ impl std::error::Error for Error {
             Error::Atlas(ref err) => err.description(),
             Error::Camera(ref err) => err.description(),
             Error::Config(_) => "api configuration error",
+            Error::InvalidParam { .. } => "invalid request parameter",
             Error::Io(ref err) => err.description(),
             Error::ParseInt(ref err) => err.description(),
             Error::TomlDe(ref err) => err.description(),
+            Error::TooManyImages { .. } => "too many images for one response",
         }
     }
 
@@ -104,9 +136,11 @@ impl std::error::Error for Error {
             Error::Atlas(ref err) => Some(err),
             Error::Camera(ref err) => Some(err),
             Error::Config(_) => None,
+            Error::InvalidParam { .. } => None,
             Error::Io(ref err) => Some(err),
             Error::ParseInt(ref err) => Some(err),
             Error::TomlDe(ref err) => Some(err),
+            Error::TooManyImages { .. } => None,
         }
     }
 }
@@ -117,9 +151,35 @@ impl std::fmt::Display for Error {
             Error::Atlas(ref err) => write!(f, "atlas error: {}", err),
             Error::Camera(ref err) => write!(f, "camera error: {}", err),
             Error::Config(ref msg) => write!(f, "api configuration error: {}", msg),
+            Error::InvalidParam {
+                ref name,
+                ref value,
+                expected,
+            } => write!(f, "invalid value for {}: {} (expected {})", name, value, expected),
             Error::Io(ref err) => write!(f, "io error: {}", err),
             Error::ParseInt(ref err) => write!(f, "parse int error: {}", err),
             Error::TomlDe(ref err) => write!(f, "toml de error: {}", err),
+            Error::TooManyImages { count, max } => {
+                write!(
+                    f,
+                    "{} images is more than the maximum of {} per response, paginate with \
+                     page and per_page or filter with since",
+                    count,
+                    max
+                )
+            }
+        }
+    }
+}
+
+impl From<Error> for iron::IronError {
+    fn from(err: Error) -> iron::IronError {
+        use iron::status;
+
+        match err {
+            Error::InvalidParam { .. } => json::error(err, status::BadRequest),
+            Error::TooManyImages { .. } => json::error(err, status::PayloadTooLarge),
+            _ => json::error(err, status::InternalServerError),
         }
     }
 }
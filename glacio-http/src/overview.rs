@@ -0,0 +1,234 @@
+//! One request for the landing page: each site's latest heartbeat, along with its cameras.
+
+use Result;
+use atlas;
+use cameras::{self, CameraOverview};
+use chrono::{DateTime, Utc};
+use glacio::atlas::Heartbeat;
+use iron::{IronResult, Request, Response};
+use json;
+use source::{HeartbeatSource, ImageSource};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// The name of the ATLAS site in the overview.
+pub const ATLAS_SITE: &'static str = "atlas";
+
+/// Handler for the overview.
+///
+/// Like the other multi-source handlers, this doesn't implement `Handler`, its method is passed
+/// to the router in a closure.
+#[derive(Clone, Debug)]
+pub struct Overview {
+    atlas: atlas::Config,
+    cameras: cameras::Config,
+    heartbeats: Arc<HeartbeatSource>,
+    images: Arc<ImageSource>,
+}
+
+/// Every site, then the cameras that aren't linked to any site.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    /// The sites, with their linked cameras.
+    pub sites: Vec<Site>,
+    /// The cameras that aren't linked to a site, in configuration order.
+    pub unassigned_cameras: Vec<CameraOverview>,
+}
+
+/// One site's latest heartbeat and cameras.
+#[derive(Debug, Serialize)]
+pub struct Site {
+    /// The name of the site.
+    pub site: String,
+    /// The site's latest heartbeat, or `None` if there isn't one or it couldn't be read.
+    pub latest_heartbeat_summary: Option<HeartbeatSummary>,
+    /// The site's cameras, in the order that they're linked.
+    pub cameras: Vec<CameraOverview>,
+}
+
+/// The parts of a heartbeat that are worth a glance.
+#[derive(Debug, Serialize)]
+pub struct HeartbeatSummary {
+    /// The date and time of the heartbeat.
+    pub datetime: DateTime<Utc>,
+    /// The state of charge of each battery, by battery id.
+    pub states_of_charge: BTreeMap<u8, f32>,
+    /// Are the Riegl systems powered?
+    pub is_riegl_switch_on: bool,
+}
+
+impl Overview {
+    /// Creates a new overview handler.
+    pub fn new(
+        atlas: atlas::Config,
+        cameras: cameras::Config,
+        heartbeats: Arc<HeartbeatSource>,
+        images: Arc<ImageSource>,
+    ) -> Overview {
+        Overview {
+            atlas: atlas,
+            cameras: cameras,
+            heartbeats: heartbeats,
+            images: images,
+        }
+    }
+
+    /// Returns the overview of every site and camera.
+    ///
+    /// A heartbeat or a latest image that can't be read is logged and reported as `null`, so one
+    /// broken SBD storage or camera directory doesn't take down the landing page. Camera names
+    /// that are linked to a site but aren't configured are skipped.
+    pub fn overview(&self, _: &mut Request) -> IronResult<Response> {
+        let now = Utc::now();
+        let latest_heartbeat = match self.heartbeats.latest_heartbeat() {
+            Ok(latest_heartbeat) => latest_heartbeat,
+            Err(err) => {
+                error!("could not read the latest heartbeat for the overview: {}", err);
+                None
+            }
+        };
+        let mut cameras = Vec::new();
+        for name in &self.atlas.cameras {
+            match self.cameras.cameras.iter().find(|config| &config.name == name) {
+                Some(camera_config) => cameras.push(self.camera_overview(camera_config, now)?),
+                None => warn!("site {} links to unknown camera {}", ATLAS_SITE, name),
+            }
+        }
+        let mut unassigned_cameras = Vec::new();
+        for camera_config in &self.cameras.cameras {
+            if !self.atlas.cameras.contains(&camera_config.name) {
+                unassigned_cameras.push(self.camera_overview(camera_config, now)?);
+            }
+        }
+        json::response(Summary {
+            sites: vec![
+                Site {
+                    site: ATLAS_SITE.to_string(),
+                    latest_heartbeat_summary: latest_heartbeat.as_ref().map(HeartbeatSummary::new),
+                    cameras: cameras,
+                },
+            ],
+            unassigned_cameras: unassigned_cameras,
+        })
+    }
+
+    fn camera_overview(
+        &self,
+        camera_config: &cameras::CameraConfig,
+        now: DateTime<Utc>,
+    ) -> Result<CameraOverview> {
+        let latest_image = match self.images.latest_image(camera_config) {
+            Ok(latest_image) => latest_image,
+            Err(err) => {
+                error!(
+                    "could not read the latest image of camera {} for the overview: {}",
+                    camera_config.name,
+                    err
+                );
+                None
+            }
+        };
+        CameraOverview::new(camera_config, latest_image.as_ref(), &self.cameras, now)
+    }
+}
+
+impl HeartbeatSummary {
+    fn new(heartbeat: &Heartbeat) -> HeartbeatSummary {
+        HeartbeatSummary {
+            datetime: heartbeat.datetime,
+            states_of_charge: heartbeat
+                .batteries
+                .iter()
+                .map(|(&id, battery)| (id, battery.state_of_charge))
+                .collect(),
+            is_riegl_switch_on: heartbeat.is_riegl_switch_on,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Api, Config};
+    use cameras::CameraConfig;
+    use iron::Headers;
+    use iron_test::{ProjectBuilder, request, response};
+    use serde_json::{self, Value};
+
+    #[test]
+    fn overview() {
+        let builder = ProjectBuilder::new("overview")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", "")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_182500.jpg", "")
+            .file("HEL_DUAL_1/HEL_DUAL_1_20170806_152500.jpg", "")
+            .file("EMPTY_CAM/EMPTY_CAM.jpg", "");
+        builder.build();
+        let mut config = Config::new();
+        config.atlas.path = "../glacio/data".to_string();
+        config.atlas.cameras = vec!["ATLAS_CAM".to_string(), "NOT_A_CAM".to_string()];
+        config.cameras.document_root = builder.root().to_string_lossy().into_owned();
+        for name in &["HEL_DUAL_1", "ATLAS_CAM", "EMPTY_CAM"] {
+            config.cameras.cameras.push(CameraConfig {
+                name: name.to_string(),
+                path: format!("{}/{}", builder.root().display(), name),
+                interval: 3.,
+                ..Default::default()
+            });
+        }
+        let api = Api::new(config).unwrap();
+        let response = request::get("http://localhost:3000/overview", Headers::new(), &api)
+            .unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+
+        let site = &json["sites"][0];
+        assert_eq!("atlas", site["site"]);
+        let heartbeat = &site["latest_heartbeat_summary"];
+        assert_eq!("2017-08-25T15:01:06+00:00", heartbeat["datetime"]);
+        assert_eq!(85.461, heartbeat["states_of_charge"]["1"]);
+        assert_eq!(1, site["cameras"].as_array().unwrap().len());
+        assert_eq!("ATLAS_CAM", site["cameras"][0]["id"]);
+        assert_eq!(
+            "2017-08-06T18:25:00+00:00",
+            site["cameras"][0]["latest_image"]["datetime"]
+        );
+        assert_eq!(false, site["cameras"][0]["is_active"]);
+
+        let unassigned_cameras = json["unassigned_cameras"].as_array().unwrap();
+        assert_eq!(2, unassigned_cameras.len());
+        assert_eq!("HEL_DUAL_1", unassigned_cameras[0]["id"]);
+        assert_eq!("EMPTY_CAM", unassigned_cameras[1]["id"]);
+        assert_eq!(Value::Null, unassigned_cameras[1]["latest_image"]);
+    }
+
+    #[test]
+    fn overview_unreadable_camera() {
+        let builder = ProjectBuilder::new("overview-unreadable-camera")
+            .file("ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", "");
+        builder.build();
+        let mut config = Config::new();
+        config.atlas.path = "../glacio/data".to_string();
+        config.cameras.document_root = builder.root().to_string_lossy().into_owned();
+        for name in &["ATLAS_CAM", "NOT_MOUNTED"] {
+            config.cameras.cameras.push(CameraConfig {
+                name: name.to_string(),
+                path: format!("{}/{}", builder.root().display(), name),
+                interval: 3.,
+                ..Default::default()
+            });
+        }
+        let api = Api::new(config).unwrap();
+        let response = request::get("http://localhost:3000/overview", Headers::new(), &api)
+            .unwrap();
+        let json: Value = serde_json::from_str(&response::extract_body_to_string(response))
+            .unwrap();
+
+        let unassigned_cameras = json["unassigned_cameras"].as_array().unwrap();
+        assert_eq!(2, unassigned_cameras.len());
+        assert_eq!(
+            "2017-08-06T15:25:00+00:00",
+            unassigned_cameras[0]["latest_image"]["datetime"]
+        );
+        assert_eq!("NOT_MOUNTED", unassigned_cameras[1]["id"]);
+        assert_eq!(Value::Null, unassigned_cameras[1]["latest_image"]);
+    }
+}
@@ -4,8 +4,8 @@
 //! iterator based on Iron request parameters.
 
 use Result;
-use iron::{Plugin, Request};
-use params::{Params, Value};
+use iron::Request;
+use param;
 use std::iter::{Skip, Take};
 
 /// The default page, if one is not specified in the request.
@@ -53,20 +53,13 @@ impl<I: Iterator> Paginate<I> for I {
 
 impl Pagination {
     pub fn new(request: &mut Request) -> Result<Pagination> {
-        let map = request.get::<Params>().unwrap();
-        let mut page = match map.find(&["page"]) {
-            Some(&Value::U64(page)) => page as usize,
-            Some(&Value::String(ref page)) => page.parse::<usize>()?,
-            _ => DEFAULT_PAGE,
-        };
+        let mut page = param::parse_param(request, "page", "a positive integer")?
+            .unwrap_or(DEFAULT_PAGE);
         if page == 0 {
             page = 1;
         }
-        let mut per_page = match map.find(&["per_page"]) {
-            Some(&Value::U64(per_page)) => per_page as usize,
-            Some(&Value::String(ref per_page)) => per_page.parse::<usize>()?,
-            _ => DEFAULT_PER_PAGE,
-        };
+        let mut per_page = param::parse_param(request, "per_page", "a positive integer")?
+            .unwrap_or(DEFAULT_PER_PAGE);
         if per_page >= MAX_PER_PAGE {
             per_page = MAX_PER_PAGE;
         } else if per_page == 0 {
@@ -0,0 +1,240 @@
+//! Parse request parameters, with errors that tell the client what went wrong.
+//!
+//! Every handler should go through these functions, so that a bad parameter always turns into the
+//! same 400 response, naming the parameter and what we expected it to be.
+
+use {Error, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use iron::{Plugin, Request};
+use params::{Params, Value};
+use router::Router;
+use std::str::FromStr;
+
+/// What we tell clients when a datetime parameter can't be parsed.
+pub const DATETIME_EXPECTED: &'static str = "an RFC 3339 datetime, a date as YYYY-MM-DD or \
+                                             YYYYMMDD (midnight UTC), or a time relative to \
+                                             now, e.g. -7d or -24h";
+
+/// Relative times further back than about a thousand years are rejected, rather than overflowing.
+const MAX_RELATIVE_SECONDS: i64 = 1_000 * 366 * 24 * 60 * 60;
+
+/// Parses a query (or form) parameter, returning `None` if the parameter isn't present.
+///
+/// `expected` describes what the parameter should look like, e.g. "a positive integer", and is
+/// returned to the client if parsing fails.
+pub fn parse_param<T: FromStr>(
+    request: &mut Request,
+    name: &str,
+    expected: &'static str,
+) -> Result<Option<T>> {
+    let map = request.get::<Params>().unwrap();
+    let value = match map.find(&[name]) {
+        None => return Ok(None),
+        Some(&Value::String(ref value)) => value.clone(),
+        Some(&Value::I64(value)) => value.to_string(),
+        Some(&Value::U64(value)) => value.to_string(),
+        Some(&Value::F64(value)) => value.to_string(),
+        Some(value) => format!("{:?}", value),
+    };
+    parse(name, &value, expected).map(Some)
+}
+
+/// Parses a parameter captured by the router, e.g. `:datetime` in `/images/nearest/:datetime`.
+///
+/// A missing route parameter is a programming error (the route wouldn't have matched), so this
+/// panics if the parameter doesn't exist.
+pub fn parse_route_param<T: FromStr>(
+    request: &Request,
+    name: &str,
+    expected: &'static str,
+) -> Result<T> {
+    let value = request
+        .extensions
+        .get::<Router>()
+        .unwrap()
+        .find(name)
+        .unwrap();
+    parse(name, value, expected)
+}
+
+/// Parses a query (or form) parameter as a datetime, returning `None` if it isn't present.
+///
+/// See `parse_datetime` for the accepted formats.
+pub fn parse_datetime_param(request: &mut Request, name: &str) -> Result<Option<DateTime<Utc>>> {
+    let value: Option<String> = parse_param(request, name, DATETIME_EXPECTED)?;
+    value
+        .map(|value| datetime_or_error(name, &value))
+        .map_or(Ok(None), |result| result.map(Some))
+}
+
+/// Parses a parameter captured by the router as a datetime.
+///
+/// See `parse_datetime` for the accepted formats.
+pub fn parse_datetime_route_param(request: &Request, name: &str) -> Result<DateTime<Utc>> {
+    let value: String = parse_route_param(request, name, DATETIME_EXPECTED)?;
+    datetime_or_error(name, &value)
+}
+
+/// Parses a datetime in any of the formats that we accept in requests.
+///
+/// The accepted formats are:
+///
+/// - RFC 3339, e.g. `2017-08-06T15:25:00Z`.
+/// - A date, either `YYYY-MM-DD` or `YYYYMMDD`, which is midnight UTC on that day.
+/// - A time relative to now, e.g. `-30d`. The units are `s`, `m`, `h`, `d`, and `w`. Relative
+///   times let dashboards link to things like `?since=-30d` without the link going stale.
+///
+/// Returns `None` if the string isn't in any of these formats.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate chrono;
+/// # extern crate glacio_http;
+/// # use chrono::{TimeZone, Utc};
+/// # use glacio_http::param;
+/// # fn main() {
+/// let datetime = Utc.ymd(2017, 8, 6).and_hms(0, 0, 0);
+/// assert_eq!(Some(datetime), param::parse_datetime("2017-08-06"));
+/// assert_eq!(Some(datetime), param::parse_datetime("20170806"));
+/// assert!(param::parse_datetime("-7d").unwrap() < Utc::now());
+/// assert_eq!(None, param::parse_datetime("yesterday"));
+/// # }
+/// ```
+pub fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    parse_datetime_relative_to(value, Utc::now())
+}
+
+fn parse_datetime_relative_to(value: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    if let Ok(datetime) = value.parse::<DateTime<Utc>>() {
+        return Some(datetime);
+    }
+    for format in &["%Y-%m-%d", "%Y%m%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Some(DateTime::from_utc(date.and_hms(0, 0, 0), Utc));
+        }
+    }
+    if !value.is_ascii() || !value.starts_with('-') || value.len() < 3 {
+        return None;
+    }
+    let (amount, unit) = value[1..].split_at(value.len() - 2);
+    if !amount.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let seconds_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let seconds = amount.parse::<i64>().ok()?.checked_mul(seconds_per_unit)?;
+    if seconds > MAX_RELATIVE_SECONDS {
+        return None;
+    }
+    let duration = Duration::seconds(seconds);
+    now.checked_sub_signed(duration)
+}
+
+fn datetime_or_error(name: &str, value: &str) -> Result<DateTime<Utc>> {
+    parse_datetime(value).ok_or_else(|| {
+        Error::InvalidParam {
+            name: name.to_string(),
+            value: value.to_string(),
+            expected: DATETIME_EXPECTED,
+        }
+    })
+}
+
+fn parse<T: FromStr>(name: &str, value: &str, expected: &'static str) -> Result<T> {
+    value.parse().map_err(|_| {
+        Error::InvalidParam {
+            name: name.to_string(),
+            value: value.to_string(),
+            expected: expected,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn datetimes() {
+        use chrono::TimeZone;
+
+        let now = Utc.ymd(2017, 8, 6).and_hms(15, 25, 0);
+        let parse = |value| parse_datetime_relative_to(value, now);
+        assert_eq!(Some(now), parse("2017-08-06T15:25:00Z"));
+        assert_eq!(Some(now), parse("2017-08-06T17:25:00+02:00"));
+        assert_eq!(Some(now), parse("2017-08-06 15:25:00 UTC"));
+        assert_eq!(Some(Utc.ymd(2017, 8, 6).and_hms(0, 0, 0)), parse("2017-08-06"));
+        assert_eq!(Some(Utc.ymd(2017, 8, 6).and_hms(0, 0, 0)), parse("20170806"));
+        assert_eq!(Some(Utc.ymd(2017, 8, 6).and_hms(15, 24, 30)), parse("-30s"));
+        assert_eq!(Some(Utc.ymd(2017, 8, 6).and_hms(15, 15, 0)), parse("-10m"));
+        assert_eq!(Some(Utc.ymd(2017, 8, 5).and_hms(15, 25, 0)), parse("-24h"));
+        assert_eq!(Some(Utc.ymd(2017, 7, 7).and_hms(15, 25, 0)), parse("-30d"));
+        assert_eq!(Some(Utc.ymd(2017, 7, 30).and_hms(15, 25, 0)), parse("-1w"));
+        assert_eq!(Some(now), parse("-0d"));
+        for value in &[
+            "",
+            "banana",
+            "2017-13-06",
+            "201708",
+            "7d",
+            "+7d",
+            "-d",
+            "--7d",
+            "-+7d",
+            "-7y",
+            "-7.5d",
+            "-99999999999999999999d",
+            "-9999999999999999w",
+            "-é",
+            "-7é",
+            "-é7d",
+        ]
+        {
+            assert_eq!(None, parse(value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn datetime_error_names_formats() {
+        match datetime_or_error("since", "banana").unwrap_err() {
+            Error::InvalidParam { name, expected, .. } => {
+                assert_eq!("since", name);
+                assert!(expected.contains("RFC 3339"));
+                assert!(expected.contains("YYYY-MM-DD"));
+                assert!(expected.contains("-7d"));
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn parse_ok() {
+        assert_eq!(42, parse::<usize>("page", "42", "a positive integer").unwrap());
+    }
+
+    #[test]
+    fn parse_error() {
+        match parse::<usize>("page", "-1", "a positive integer").unwrap_err() {
+            Error::InvalidParam {
+                name,
+                value,
+                expected,
+            } => {
+                assert_eq!("page", name);
+                assert_eq!("-1", value);
+                assert_eq!("a positive integer", expected);
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Where the api gets its data from.
+//!
+//! The handlers don't read heartbeats or images directly, they ask a `HeartbeatSource` or an
+//! `ImageSource`. The TOML-backed configurations are the default sources, but anything that
+//! implements these traits can be handed to `Api::with_sources`, e.g. a fake that always fails.
+
+use {Error, Result};
+use atlas;
+use cameras::{self, CameraConfig};
+use chrono::{DateTime, Utc};
+use glacio;
+use glacio::atlas::{Heartbeat, TestMessage};
+use glacio::camera::Image;
+use std::fmt::Debug;
+
+/// A source of ATLAS heartbeats.
+pub trait HeartbeatSource: Debug + Send + Sync {
+    /// Returns all available heartbeats, in no particular order.
+    fn heartbeats(&self) -> Result<Vec<Heartbeat>>;
+
+    /// Returns the number of available heartbeats.
+    ///
+    /// The default implementation loads all heartbeats and counts them. Implementors that can
+    /// count more cheaply should override this.
+    fn heartbeat_count(&self) -> Result<usize> {
+        self.heartbeats().map(|heartbeats| heartbeats.len())
+    }
+
+    /// Returns the most recent heartbeat, or `None` if there are no heartbeats.
+    ///
+    /// No heartbeats is not an error, so an `Err` should always mean that the heartbeats couldn't
+    /// be read. The default implementation loads all heartbeats and picks the latest one.
+    fn latest_heartbeat(&self) -> Result<Option<Heartbeat>> {
+        self.heartbeats().map(|heartbeats| {
+            glacio::atlas::latest_heartbeat(&heartbeats).cloned()
+        })
+    }
+
+    /// Returns the latest heartbeat at or before `as_of`, or `None` if there isn't one.
+    ///
+    /// The default implementation loads all heartbeats and picks the latest one that isn't after
+    /// `as_of`.
+    fn latest_heartbeat_as_of(&self, as_of: DateTime<Utc>) -> Result<Option<Heartbeat>> {
+        self.heartbeats().map(|heartbeats| {
+            heartbeats
+                .into_iter()
+                .filter(|heartbeat| heartbeat.datetime <= as_of)
+                .max_by_key(|heartbeat| heartbeat.datetime)
+        })
+    }
+
+    /// Returns the heartbeats at or after `since`, newest first, and no more than `limit` of them.
+    ///
+    /// The default implementation loads all heartbeats, then filters, sorts, and truncates them.
+    fn recent_heartbeats(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<Heartbeat>> {
+        self.heartbeats().map(|heartbeats| {
+            atlas::newest_first(heartbeats, since, limit)
+        })
+    }
+
+    /// Returns the test messages that were sent by hand from the site, in no particular order.
+    ///
+    /// The default implementation has no test messages.
+    fn test_messages(&self) -> Result<Vec<TestMessage>> {
+        Ok(Vec::new())
+    }
+
+    /// Returns all available heartbeats along with all test messages.
+    ///
+    /// The default implementation calls `heartbeats` and `test_messages`. Implementors that read
+    /// both from the same place should override this to only read once.
+    fn heartbeats_and_test_messages(&self) -> Result<(Vec<Heartbeat>, Vec<TestMessage>)> {
+        Ok((self.heartbeats()?, self.test_messages()?))
+    }
+}
+
+/// A source of camera images.
+pub trait ImageSource: Debug + Send + Sync {
+    /// Returns all available images for the camera, in no particular order.
+    ///
+    /// Images that can't be read should be skipped, rather than failing the whole request.
+    fn images(&self, camera: &CameraConfig) -> Result<Vec<Image>>;
+
+    /// Returns the camera's latest image, or `None` if it doesn't have any images.
+    ///
+    /// The default implementation loads all images and picks the latest one. Implementors that
+    /// can find the latest image more cheaply should override this.
+    fn latest_image(&self, camera: &CameraConfig) -> Result<Option<Image>> {
+        self.images(camera).map(|images| images.into_iter().max())
+    }
+}
+
+impl HeartbeatSource for atlas::Config {
+    fn heartbeats(&self) -> Result<Vec<Heartbeat>> {
+        atlas::Config::heartbeats(self)
+    }
+
+    fn heartbeat_count(&self) -> Result<usize> {
+        atlas::Config::heartbeat_count(self)
+    }
+
+    fn latest_heartbeat(&self) -> Result<Option<Heartbeat>> {
+        atlas::Config::latest_heartbeat(self)
+    }
+
+    fn latest_heartbeat_as_of(&self, as_of: DateTime<Utc>) -> Result<Option<Heartbeat>> {
+        atlas::Config::latest_heartbeat_as_of(self, as_of)
+    }
+
+    fn recent_heartbeats(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<Heartbeat>> {
+        atlas::Config::recent_heartbeats(self, since, limit)
+    }
+
+    fn test_messages(&self) -> Result<Vec<TestMessage>> {
+        atlas::Config::test_messages(self)
+    }
+
+    fn heartbeats_and_test_messages(&self) -> Result<(Vec<Heartbeat>, Vec<TestMessage>)> {
+        atlas::Config::heartbeats_and_test_messages(self)
+    }
+}
+
+impl ImageSource for cameras::Config {
+    fn images(&self, camera: &CameraConfig) -> Result<Vec<Image>> {
+        let (images, _) = camera.to_camera()?.images_with_errors()?;
+        Ok(images)
+    }
+
+    fn latest_image(&self, camera: &CameraConfig) -> Result<Option<Image>> {
+        camera.to_camera()?.latest().map_err(Error::from)
+    }
+}
@@ -0,0 +1,42 @@
+//! Replays an SBD root through a channel, printing each heartbeat as it comes out the other side.
+//!
+//! A DirectIP receiver would send each message down the channel as it arrives. Here, a thread
+//! stands in for the receiver:
+//!
+//! ```bash
+//! cargo run --example stream --features stream -- data
+//! ```
+
+extern crate glacio;
+extern crate sbd;
+
+use glacio::atlas::stream::Stream;
+use sbd::storage::{FilesystemStorage, Storage};
+use std::env;
+use std::sync::mpsc;
+use std::thread;
+
+fn main() {
+    let root = env::args().nth(1).unwrap_or_else(|| "data".to_string());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut messages = FilesystemStorage::open(root).unwrap().messages().unwrap();
+        messages.sort_by(|a, b| a.time_of_session().cmp(&b.time_of_session()));
+        for message in messages {
+            sender.send(message).unwrap();
+        }
+    });
+    for (imei, result) in Stream::new(receiver) {
+        match result {
+            Ok(heartbeat) => {
+                println!(
+                    "{} {}: version {} heartbeat",
+                    heartbeat.datetime,
+                    imei,
+                    heartbeat.version
+                )
+            }
+            Err(err) => eprintln!("{}: {}", imei, err),
+        }
+    }
+}
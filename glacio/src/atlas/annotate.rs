@@ -0,0 +1,337 @@
+//! Label every field of a raw heartbeat with its byte range and parsed value.
+//!
+//! When a heartbeat won't parse, `Heartbeat` only says that the format is invalid. Annotating the
+//! heartbeat walks the same sections in the same order, but records where each one lives and what
+//! it parses to, and keeps going after a section fails so every problem shows up at once.
+//!
+//! # Examples
+//!
+//! ```
+//! use glacio::atlas::annotate;
+//! use std::fs::File;
+//! use std::io::Read;
+//!
+//! let mut bytes = Vec::new();
+//! File::open("data/170825_150106.hb").unwrap().read_to_end(&mut bytes).unwrap();
+//! for annotation in annotate::annotate(&bytes) {
+//!     println!("{} {}", annotation.offset, annotation.label);
+//! }
+//! ```
+
+use atlas::{Error, Format, battery, efoy};
+use atlas::scanner::{ScanStop, ScannerPowerOn};
+use std::cmp;
+use std::fmt::Debug;
+use std::str::{self, FromStr};
+use sutron;
+
+const LINE_BREAK: &'static [u8] = b"\r\n";
+
+/// The labels of the lines that come after the header line, in order.
+///
+/// The battery line is split into its fields, so it's labeled separately.
+const LINES: [&'static str; 9] = [
+    "scanner_power_on",
+    "weather",
+    "scan_start",
+    "scan_stop",
+    "scan_skip",
+    "batteries",
+    "efoy1",
+    "efoy2",
+    "riegl_switch",
+];
+
+/// One labeled range of bytes in a heartbeat.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Annotation {
+    /// The offset of the first byte, from the start of the heartbeat.
+    pub offset: usize,
+    /// The number of bytes.
+    ///
+    /// Zero for a section that should be here but isn't.
+    pub len: usize,
+    /// The name of the section, e.g. `scan_start`.
+    pub label: String,
+    /// The value the bytes parse to, or `None` if they didn't parse or aren't parsed at all.
+    pub parsed_value: Option<String>,
+    /// Why the bytes didn't parse, or `None` if they're fine.
+    pub error: Option<String>,
+}
+
+/// Annotates the bytes of one reassembled heartbeat.
+///
+/// Annotations are returned in byte order. Line breaks aren't annotated, and sections that are
+/// only read by the heartbeat regex (e.g. the weather line) have neither a value nor an error.
+///
+/// # Examples
+///
+/// ```
+/// use glacio::atlas::annotate;
+/// let annotations = annotate::annotate(b"ATHB03\r\nnot a scanner power on");
+/// assert_eq!("version", annotations[1].label);
+/// assert_eq!(Some("3".to_string()), annotations[1].parsed_value);
+/// assert!(annotations[3].error.is_some());
+/// ```
+pub fn annotate(bytes: &[u8]) -> Vec<Annotation> {
+    annotate_with_format(bytes, &Format::default())
+}
+
+/// Annotates the bytes of one reassembled heartbeat in another product line's format.
+///
+/// The magic number is checked against the format's, instead of ATLAS's.
+///
+/// # Examples
+///
+/// ```
+/// use glacio::atlas::{Format, annotate};
+/// let format = Format::new("AMHB").unwrap();
+/// let annotations = annotate::annotate_with_format(b"AMHB03\r\n", &format);
+/// assert_eq!("magic", annotations[0].label);
+/// assert_eq!(None, annotations[0].error);
+/// ```
+pub fn annotate_with_format(bytes: &[u8], format: &Format) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    let mut lines = split_lines(bytes).into_iter();
+    match lines.next() {
+        Some((offset, line)) => {
+            annotate_header(&mut annotations, offset, line, bytes.len(), format)
+        }
+        None => annotations.push(Annotation::missing("header", 0)),
+    }
+    for &label in &LINES {
+        match lines.next() {
+            Some((offset, line)) => annotate_line(&mut annotations, label, offset, line),
+            None => annotations.push(Annotation::missing(label, bytes.len())),
+        }
+    }
+    for (offset, line) in lines {
+        let mut annotation = Annotation::unparsed("extra", offset, line.len());
+        annotation.error = Some("unexpected line after the riegl switch".to_string());
+        annotations.push(annotation);
+    }
+    annotations
+}
+
+impl Annotation {
+    fn unparsed(label: &str, offset: usize, len: usize) -> Annotation {
+        Annotation {
+            offset: offset,
+            len: len,
+            label: label.to_string(),
+            parsed_value: None,
+            error: None,
+        }
+    }
+
+    fn missing(label: &str, offset: usize) -> Annotation {
+        let mut annotation = Annotation::unparsed(label, offset, 0);
+        annotation.error = Some("missing".to_string());
+        annotation
+    }
+
+    fn parsed<F, T, E>(label: &str, offset: usize, bytes: &[u8], parse: F) -> Annotation
+    where
+        F: Fn(&str) -> Result<T, E>,
+        T: Debug,
+        Error: From<E>,
+    {
+        let mut annotation = Annotation::unparsed(label, offset, bytes.len());
+        match str::from_utf8(bytes) {
+            Ok(s) => {
+                match parse(s) {
+                    Ok(value) => annotation.parsed_value = Some(format!("{:?}", value)),
+                    Err(err) => annotation.error = Some(Error::from(err).to_string()),
+                }
+            }
+            Err(err) => annotation.error = Some(err.to_string()),
+        }
+        annotation
+    }
+}
+
+fn split_lines(bytes: &[u8]) -> Vec<(usize, &[u8])> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i + LINE_BREAK.len() <= bytes.len() {
+        if &bytes[i..i + LINE_BREAK.len()] == LINE_BREAK {
+            lines.push((start, &bytes[start..i]));
+            i += LINE_BREAK.len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    if start < bytes.len() || !lines.is_empty() {
+        lines.push((start, &bytes[start..]));
+    }
+    lines
+}
+
+fn annotate_header(
+    annotations: &mut Vec<Annotation>,
+    offset: usize,
+    line: &[u8],
+    total: usize,
+    format: &Format,
+) {
+    let expected = format.magic();
+    let magic_len = cmp::min(expected.len(), line.len());
+    let mut magic = Annotation::unparsed("magic", offset, magic_len);
+    if &line[..magic_len] != expected.as_bytes() {
+        magic.error = Some(format!("expected {}", expected));
+    }
+    annotations.push(magic);
+
+    let version_len = cmp::min(2, line.len() - magic_len);
+    let version = &line[magic_len..magic_len + version_len];
+    annotations.push(Annotation::parsed(
+        "version",
+        offset + magic_len,
+        version,
+        u8::from_str,
+    ));
+
+    let bytes_offset = magic_len + version_len;
+    let mut bytes = Annotation::parsed(
+        "bytes",
+        offset + bytes_offset,
+        &line[bytes_offset..],
+        usize::from_str,
+    );
+    if bytes.parsed_value.is_some() && bytes.parsed_value != Some(total.to_string()) {
+        bytes.error = Some(format!("the heartbeat is actually {} bytes", total));
+    }
+    annotations.push(bytes);
+}
+
+fn annotate_line(annotations: &mut Vec<Annotation>, label: &str, offset: usize, line: &[u8]) {
+    let annotation = match label {
+        "scanner_power_on" => Annotation::parsed(label, offset, line, ScannerPowerOn::from_str),
+        "scan_start" => Annotation::parsed(label, offset, line, sutron::parse_datetime::<Error>),
+        "scan_stop" => Annotation::parsed(label, offset, line, ScanStop::from_str),
+        "batteries" => return annotate_batteries(annotations, offset, line),
+        "efoy1" | "efoy2" => Annotation::parsed(label, offset, line, efoy::Heartbeat::from_str),
+        "riegl_switch" => Annotation::parsed(label, offset, line, parse_riegl_switch),
+        _ => Annotation::unparsed(label, offset, line.len()),
+    };
+    annotations.push(annotation);
+}
+
+fn parse_riegl_switch(s: &str) -> Result<bool, Error> {
+    Ok(s == "on")
+}
+
+fn annotate_batteries(annotations: &mut Vec<Annotation>, offset: usize, line: &[u8]) {
+    let commas = line.iter()
+        .enumerate()
+        .filter(|&(_, &byte)| byte == b',')
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if commas.len() < 2 {
+        let mut annotation = Annotation::unparsed("batteries", offset, line.len());
+        annotation.error = Some("expected at least two battery states of charge".to_string());
+        annotations.push(annotation);
+        return;
+    }
+    let soc1 = commas[commas.len() - 2] + 1;
+    let soc2 = commas[commas.len() - 1] + 1;
+    annotations.push(Annotation::unparsed("batteries", offset, soc1 - 1));
+    annotations.push(Annotation::parsed(
+        "soc1",
+        offset + soc1,
+        &line[soc1..soc2 - 1],
+        battery::Heartbeat::from_str,
+    ));
+    annotations.push(Annotation::parsed(
+        "soc2",
+        offset + soc2,
+        &line[soc2..],
+        battery::Heartbeat::from_str,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEARTBEAT: &'static [u8] = include_bytes!("../../data/170825_150106.hb");
+
+    fn find<'a>(annotations: &'a [Annotation], label: &str) -> &'a Annotation {
+        annotations
+            .iter()
+            .find(|annotation| annotation.label == label)
+            .unwrap()
+    }
+
+    #[test]
+    fn heartbeat() {
+        let annotations = annotate(HEARTBEAT);
+        assert!(annotations.iter().all(|annotation| annotation.error.is_none()));
+
+        let version = find(&annotations, "version");
+        assert_eq!((4, 2), (version.offset, version.len));
+        assert_eq!(Some("3".to_string()), version.parsed_value);
+
+        let bytes = find(&annotations, "bytes");
+        assert_eq!((6, 3), (bytes.offset, bytes.len));
+        assert_eq!(Some("313".to_string()), bytes.parsed_value);
+
+        let scanner_power_on = find(&annotations, "scanner_power_on");
+        assert_eq!(11, scanner_power_on.offset);
+
+        let scan_start = find(&annotations, "scan_start");
+        assert_eq!((92, 17), (scan_start.offset, scan_start.len));
+        assert_eq!(
+            Some("2017-08-25T12:02:08Z".to_string()),
+            scan_start.parsed_value
+        );
+
+        let soc2 = find(&annotations, "soc2");
+        assert_eq!(&b"86.604"[..], &HEARTBEAT[soc2.offset..soc2.offset + soc2.len]);
+
+        let riegl_switch = annotations.last().unwrap();
+        assert_eq!("riegl_switch", riegl_switch.label);
+        assert_eq!(HEARTBEAT.len(), riegl_switch.offset + riegl_switch.len);
+        assert_eq!(Some("true".to_string()), riegl_switch.parsed_value);
+    }
+
+    #[test]
+    fn format() {
+        let heartbeat = String::from_utf8(HEARTBEAT.to_vec())
+            .unwrap()
+            .replacen("ATHB", "AMHB", 1);
+        let format = Format::new("AMHB").unwrap();
+        let annotations = annotate_with_format(heartbeat.as_bytes(), &format);
+        assert!(annotations.iter().all(|annotation| annotation.error.is_none()));
+        assert_eq!(
+            Some("expected ATHB".to_string()),
+            annotate(heartbeat.as_bytes())[0].error
+        );
+    }
+
+    #[test]
+    fn keeps_going_after_a_failure() {
+        let mut heartbeat = HEARTBEAT.to_vec();
+        heartbeat[11] = b'x';
+        let annotations = annotate(&heartbeat);
+        assert!(find(&annotations, "scanner_power_on").error.is_some());
+        assert!(find(&annotations, "bytes").error.is_none());
+        assert!(find(&annotations, "scan_stop").parsed_value.is_some());
+        assert!(find(&annotations, "efoy2").parsed_value.is_some());
+    }
+
+    #[test]
+    fn truncated() {
+        let annotations = annotate(&HEARTBEAT[..120]);
+        let bytes = find(&annotations, "bytes");
+        assert_eq!(
+            Some("the heartbeat is actually 120 bytes".to_string()),
+            bytes.error
+        );
+        let efoy1 = find(&annotations, "efoy1");
+        assert_eq!((120, 0), (efoy1.offset, efoy1.len));
+        assert_eq!(Some("missing".to_string()), efoy1.error);
+    }
+}
@@ -3,6 +3,11 @@
 use atlas::{Error, Result};
 use std::str::FromStr;
 
+/// The nominal capacity of one of ATLAS's K2 battery banks, in watt-hours.
+///
+/// Each bank is 100 Ah at 12 V.
+pub const K2_CAPACITY_WH: f32 = 1200.0;
+
 /// A battery's heartbeat information.
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Serialize)]
 pub struct Heartbeat {
@@ -16,3 +21,36 @@ impl FromStr for Heartbeat {
         Ok(Heartbeat { state_of_charge: s.parse()? })
     }
 }
+
+impl Heartbeat {
+    /// Returns the energy remaining in this battery, in watt-hours.
+    ///
+    /// Assumes a K2 battery bank, i.e. `K2_CAPACITY_WH` when fully charged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::battery::Heartbeat;
+    /// let battery = Heartbeat { state_of_charge: 50.0 };
+    /// assert_eq!(600.0, battery.total_energy_wh());
+    /// ```
+    pub fn total_energy_wh(&self) -> f32 {
+        self.state_of_charge / 100.0 * K2_CAPACITY_WH
+    }
+}
+
+/// Returns the energy remaining across all of the batteries, in watt-hours.
+///
+/// # Examples
+///
+/// ```
+/// # use glacio::atlas::battery::{self, Heartbeat};
+/// let batteries = [
+///     Heartbeat { state_of_charge: 50.0 },
+///     Heartbeat { state_of_charge: 100.0 },
+/// ];
+/// assert_eq!(1800.0, battery::total_energy_wh_all(&batteries));
+/// ```
+pub fn total_energy_wh_all(batteries: &[Heartbeat]) -> f32 {
+    batteries.iter().map(|battery| battery.total_energy_wh()).sum()
+}
@@ -18,6 +18,9 @@ lazy_static! {
         $").unwrap();
 }
 
+/// The total fuel percentage below which an EFOY is considered low on fuel.
+pub const LOW_FUEL_THRESHOLD: f32 = 10.0;
+
 /// Instantaneous status report from one of our EFOY fuel cell systems.
 #[derive(Clone, Debug, Default, PartialEq, PartialOrd, Serialize)]
 pub struct Heartbeat {
@@ -99,6 +102,23 @@ impl Heartbeat {
             _ => false,
         }
     }
+
+    /// Returns the efoy's output power, in watts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::efoy::Heartbeat;
+    /// let heartbeat = Heartbeat {
+    ///     voltage: 26.0,
+    ///     current: 2.5,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(65.0, heartbeat.output_power());
+    /// ```
+    pub fn output_power(&self) -> f32 {
+        self.voltage * self.current
+    }
 }
 
 impl Default for State {
@@ -237,6 +257,58 @@ impl Efoy {
         100. * fuel / capacity
     }
 
+    /// Returns the fuel remaining in this EFOY, measured in full cartridges rather than liters.
+    ///
+    /// Each cartridge contributes the fraction of its own capacity that remains, so cartridges of
+    /// different sizes can be mixed. Use `total_fuel` for the remaining fuel in liters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::efoy::{Efoy, Heartbeat};
+    /// let mut efoy = Efoy::new();
+    /// efoy.add_cartridge("1.1", 8.0).unwrap();
+    /// efoy.add_cartridge("1.2", 8.0).unwrap();
+    /// let heartbeat = Heartbeat {
+    ///     cartridge: "1.1".to_string(),
+    ///     consumed: 4.0,
+    ///     ..Default::default()
+    /// };
+    /// efoy.process(&heartbeat).unwrap();
+    /// assert_eq!(1.5, efoy.full_cartridges_remaining());
+    /// ```
+    pub fn full_cartridges_remaining(&self) -> f32 {
+        self.cartridges
+            .iter()
+            .map(|cartridge| cartridge.fuel() / cartridge.capacity)
+            .sum()
+    }
+
+    /// Is this EFOY's total fuel percentage below `LOW_FUEL_THRESHOLD`?
+    ///
+    /// An EFOY without any cartridges is not considered low on fuel, since we don't know anything
+    /// about it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::efoy::{Efoy, Heartbeat};
+    /// let mut efoy = Efoy::new();
+    /// assert!(!efoy.is_low_fuel());
+    /// efoy.add_cartridge("1.1", 8.0).unwrap();
+    /// assert!(!efoy.is_low_fuel());
+    /// let heartbeat = Heartbeat {
+    ///     cartridge: "1.1".to_string(),
+    ///     consumed: 7.5,
+    ///     ..Default::default()
+    /// };
+    /// efoy.process(&heartbeat).unwrap();
+    /// assert!(efoy.is_low_fuel());
+    /// ```
+    pub fn is_low_fuel(&self) -> bool {
+        !self.cartridges.is_empty() && self.total_fuel_percentage() < LOW_FUEL_THRESHOLD
+    }
+
     /// Process an efoy heartbeat.
     ///
     /// The named cartridge is set to the starting fuel level minus the consumed fuel. All
@@ -370,6 +442,24 @@ mod tests {
         assert_eq!(100.0, efoy.total_fuel_percentage());
     }
 
+    #[test]
+    fn efoy_low_fuel() {
+        let mut efoy = Efoy::new();
+        efoy.add_cartridge("1.1", 8.0).unwrap();
+        efoy.add_cartridge("1.2", 8.0).unwrap();
+        let mut heartbeat = Heartbeat {
+            cartridge: "1.2".to_string(),
+            consumed: 6.0,
+            ..Default::default()
+        };
+        efoy.process(&heartbeat).unwrap();
+        assert_eq!(0.25, efoy.full_cartridges_remaining());
+        assert!(!efoy.is_low_fuel());
+        heartbeat.consumed = 6.5;
+        efoy.process(&heartbeat).unwrap();
+        assert!(efoy.is_low_fuel());
+    }
+
     #[test]
     fn efoy_process() {
         let mut efoy = Efoy::new();
@@ -0,0 +1,401 @@
+//! One JSON-ready document with everything the field team needs to know about a site.
+//!
+//! Before a deployment, the team wants a single snapshot of a site's recent history: the last few
+//! heartbeats, how well messages are getting through, where the batteries and EFOYs have been,
+//! and which heartbeats couldn't be read. A `SiteExport` pulls all of that out of an SBD root.
+//!
+//! # Examples
+//!
+//! ```
+//! use glacio::atlas::export::Export;
+//! let export = Export::new("data").imei("300234063556840").last(1).build().unwrap();
+//! assert_eq!(1, export.heartbeats.len());
+//! assert!(export.bad_heartbeats.is_empty());
+//! ```
+
+use atlas::{BadHeartbeat, Heartbeat, MessageStatus, MomsnGap, Result, SbdSource};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The default number of heartbeats to include in an export, one day's worth.
+pub const DEFAULT_LAST: usize = 24;
+
+/// Configuration for exporting a site from an SBD root.
+#[derive(Debug)]
+pub struct Export {
+    path: PathBuf,
+    imei: Option<String>,
+    since: Option<DateTime<Utc>>,
+    last: usize,
+}
+
+/// A combined status document for one site.
+#[derive(Debug, Serialize)]
+pub struct SiteExport {
+    /// The IMEI of the site's modem, or `None` if every IMEI in the root was exported.
+    pub imei: Option<String>,
+    /// The start of the export's window, or `None` if the window covers all time.
+    pub since: Option<DateTime<Utc>>,
+    /// The most recent heartbeats, newest first.
+    pub heartbeats: Vec<Heartbeat>,
+    /// How well messages made it through Iridium during the window.
+    pub reception: Reception,
+    /// Each battery's state of charge over the window, by battery id.
+    pub batteries: BTreeMap<u8, Trend>,
+    /// Each EFOY's readings over the window, by EFOY id.
+    pub efoys: BTreeMap<u8, EfoyTrends>,
+    /// Every message during the window that couldn't be turned into a heartbeat, oldest first.
+    ///
+    /// Test messages aren't heartbeats to begin with, so they aren't included.
+    pub bad_heartbeats: Vec<BadHeartbeat>,
+}
+
+/// Reception statistics for a site.
+#[derive(Debug, Serialize)]
+pub struct Reception {
+    /// The number of reassembled messages, not counting test messages.
+    pub messages: usize,
+    /// The number of messages that were valid heartbeats.
+    pub heartbeats: usize,
+    /// The fraction of messages that were valid heartbeats, or `None` if there were no messages.
+    pub success_rate: Option<f32>,
+    /// The gaps in the MOMSN sequence, i.e. SBD messages that Iridium dropped.
+    pub gaps: Vec<MomsnGap>,
+}
+
+/// The range and latest value of one reading.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Trend {
+    /// The smallest value.
+    pub min: f32,
+    /// The largest value.
+    pub max: f32,
+    /// The value from the most recent heartbeat.
+    pub latest: f32,
+}
+
+/// The trends of one EFOY's readings.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct EfoyTrends {
+    /// The EFOY's voltage.
+    pub voltage: Trend,
+    /// The EFOY's current.
+    pub current: Trend,
+    /// The fuel consumed from the active cartridge.
+    pub consumed: Trend,
+}
+
+impl Export {
+    /// Creates a new export of every IMEI in an SBD root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::export::Export;
+    /// let export = Export::new("data");
+    /// ```
+    pub fn new<P: AsRef<Path>>(path: P) -> Export {
+        Export {
+            path: path.as_ref().to_path_buf(),
+            imei: None,
+            since: None,
+            last: DEFAULT_LAST,
+        }
+    }
+
+    /// Only exports messages from this IMEI.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::export::Export;
+    /// let export = Export::new("data").imei("300234063556840");
+    /// ```
+    pub fn imei(mut self, imei: &str) -> Export {
+        self.imei = Some(imei.to_string());
+        self
+    }
+
+    /// Only exports messages whose first SBD message arrived at or after this datetime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio::atlas::export::Export;
+    /// # fn main() {
+    /// let export = Export::new("data").since(Utc.ymd(2017, 8, 1).and_hms(0, 0, 0));
+    /// # }
+    /// ```
+    pub fn since(mut self, since: DateTime<Utc>) -> Export {
+        self.since = Some(since);
+        self
+    }
+
+    /// Sets the number of heartbeats to include, defaults to `DEFAULT_LAST`.
+    ///
+    /// Only the list of heartbeats is limited. The reception report and the trends cover every
+    /// message in the window.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::export::Export;
+    /// let export = Export::new("data").last(48);
+    /// ```
+    pub fn last(mut self, last: usize) -> Export {
+        self.last = last;
+        self
+    }
+
+    /// Reads the SBD root and builds the export.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::export::Export;
+    /// let export = Export::new("data").build().unwrap();
+    /// assert_eq!(Some(1.), export.reception.success_rate);
+    /// assert!(Export::new("not/a/directory").build().is_err());
+    /// ```
+    pub fn build(&self) -> Result<SiteExport> {
+        let mut source = SbdSource::new(&self.path);
+        if let Some(ref imei) = self.imei {
+            source = source.imeis(&[imei.as_str()]);
+        }
+        Ok(self.build_from(
+            source.messages_with_status()?,
+            source.momsn_gaps()?,
+        ))
+    }
+
+    /// Builds the export from messages and MOMSN gaps that have already been read.
+    ///
+    /// Neither needs to be sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// # use glacio::atlas::export::Export;
+    /// let source = SbdSource::new("data");
+    /// let export = Export::new("data").build_from(
+    ///     source.messages_with_status().unwrap(),
+    ///     source.momsn_gaps().unwrap(),
+    /// );
+    /// ```
+    pub fn build_from(&self, statuses: Vec<MessageStatus>, gaps: Vec<MomsnGap>) -> SiteExport {
+        let in_window = |datetime: DateTime<Utc>| {
+            self.since.map_or(true, |since| datetime >= since)
+        };
+        let mut heartbeats = Vec::new();
+        let mut bad_heartbeats = Vec::new();
+        let mut messages = 0;
+        let statuses = statuses.into_iter().filter(|status| {
+            !status.test_message && in_window(status.datetime)
+        });
+        for status in statuses {
+            messages += 1;
+            match status.heartbeat {
+                Ok(heartbeat) => heartbeats.push(heartbeat),
+                Err(error) => {
+                    bad_heartbeats.push(BadHeartbeat {
+                        datetime: status.datetime,
+                        packets: status.packets,
+                        error: error,
+                    })
+                }
+            }
+        }
+        heartbeats.sort();
+        bad_heartbeats.sort_by_key(|bad_heartbeat| bad_heartbeat.datetime);
+        let mut gaps = gaps.into_iter()
+            .filter(|gap| in_window(gap.datetime))
+            .collect::<Vec<_>>();
+        gaps.sort_by_key(|gap| gap.datetime);
+
+        let mut batteries = BTreeMap::new();
+        let mut efoys = BTreeMap::new();
+        for heartbeat in &heartbeats {
+            for (&id, battery) in &heartbeat.batteries {
+                let state_of_charge = battery.state_of_charge;
+                batteries
+                    .entry(id)
+                    .or_insert_with(|| Trend::new(state_of_charge))
+                    .add(state_of_charge);
+            }
+            for (&id, efoy) in &heartbeat.efoys {
+                let trends = efoys.entry(id).or_insert_with(|| {
+                    EfoyTrends {
+                        voltage: Trend::new(efoy.voltage),
+                        current: Trend::new(efoy.current),
+                        consumed: Trend::new(efoy.consumed),
+                    }
+                });
+                trends.voltage.add(efoy.voltage);
+                trends.current.add(efoy.current);
+                trends.consumed.add(efoy.consumed);
+            }
+        }
+
+        let reception = Reception {
+            messages: messages,
+            heartbeats: heartbeats.len(),
+            success_rate: if messages == 0 {
+                None
+            } else {
+                Some(heartbeats.len() as f32 / messages as f32)
+            },
+            gaps: gaps,
+        };
+        heartbeats.reverse();
+        heartbeats.truncate(self.last);
+        SiteExport {
+            imei: self.imei.clone(),
+            since: self.since,
+            heartbeats: heartbeats,
+            reception: reception,
+            batteries: batteries,
+            efoys: efoys,
+            bad_heartbeats: bad_heartbeats,
+        }
+    }
+}
+
+impl Trend {
+    fn new(value: f32) -> Trend {
+        Trend {
+            min: value,
+            max: value,
+            latest: value,
+        }
+    }
+
+    /// Adds a value that is newer than every value added so far.
+    fn add(&mut self, value: f32) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.latest = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas::Error;
+    use chrono::TimeZone;
+
+    fn status(heartbeat: Result<Heartbeat>, datetime: DateTime<Utc>) -> MessageStatus {
+        MessageStatus {
+            message: None,
+            datetime: datetime,
+            last_datetime: datetime,
+            packets: 2,
+            test_message: false,
+            heartbeat: heartbeat,
+        }
+    }
+
+    fn good(hour: u32, soc: f32, voltage: f32) -> MessageStatus {
+        let mut heartbeat = SbdSource::new("data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .next()
+            .unwrap();
+        let datetime = Utc.ymd(2017, 8, 1).and_hms(hour, 0, 0);
+        heartbeat.datetime = datetime;
+        heartbeat.batteries.get_mut(&1).unwrap().state_of_charge = soc;
+        heartbeat.efoys.get_mut(&2).unwrap().voltage = voltage;
+        status(Ok(heartbeat), datetime)
+    }
+
+    fn gap(hour: u32) -> MomsnGap {
+        MomsnGap {
+            imei: "300234063556840".to_string(),
+            before: hour as u16,
+            after: hour as u16 + 3,
+            datetime: Utc.ymd(2017, 8, 1).and_hms(hour, 0, 0),
+        }
+    }
+
+    fn statuses() -> Vec<MessageStatus> {
+        let mut test_message = status(
+            Err(Error::HeartbeatFormat("test".to_string())),
+            Utc.ymd(2017, 8, 1).and_hms(1, 30, 0),
+        );
+        test_message.test_message = true;
+        vec![
+            good(3, 90., 24.),
+            good(1, 95., 25.),
+            test_message,
+            status(
+                Err(Error::HeartbeatFormat("ATHB".to_string())),
+                Utc.ymd(2017, 8, 1).and_hms(2, 0, 0),
+            ),
+            good(2, 80., 26.),
+        ]
+    }
+
+    #[test]
+    fn aggregates() {
+        let export = Export::new("data").last(2).build_from(
+            statuses(),
+            vec![gap(2), gap(0)],
+        );
+        assert_eq!(None, export.imei);
+        assert_eq!(2, export.heartbeats.len());
+        assert_eq!(Utc.ymd(2017, 8, 1).and_hms(3, 0, 0), export.heartbeats[0].datetime);
+        assert_eq!(Utc.ymd(2017, 8, 1).and_hms(2, 0, 0), export.heartbeats[1].datetime);
+
+        assert_eq!(4, export.reception.messages);
+        assert_eq!(3, export.reception.heartbeats);
+        assert_eq!(Some(0.75), export.reception.success_rate);
+        assert_eq!(vec![gap(0), gap(2)], export.reception.gaps);
+
+        assert_eq!(
+            Trend {
+                min: 80.,
+                max: 95.,
+                latest: 90.,
+            },
+            export.batteries[&1]
+        );
+        assert_eq!(
+            Trend {
+                min: 24.,
+                max: 26.,
+                latest: 24.,
+            },
+            export.efoys[&2].voltage
+        );
+
+        assert_eq!(1, export.bad_heartbeats.len());
+        assert_eq!(2, export.bad_heartbeats[0].packets);
+        assert!(export.bad_heartbeats[0].error.to_string().contains("ATHB"));
+    }
+
+    #[test]
+    fn since() {
+        let export = Export::new("data")
+            .since(Utc.ymd(2017, 8, 1).and_hms(2, 30, 0))
+            .build_from(statuses(), vec![gap(2), gap(3)]);
+        assert_eq!(1, export.heartbeats.len());
+        assert_eq!(1, export.reception.messages);
+        assert_eq!(Some(1.), export.reception.success_rate);
+        assert_eq!(vec![gap(3)], export.reception.gaps);
+        assert_eq!(90., export.batteries[&1].min);
+        assert!(export.bad_heartbeats.is_empty());
+    }
+
+    #[test]
+    fn empty() {
+        let export = Export::new("data").build_from(Vec::new(), Vec::new());
+        assert_eq!(None, export.reception.success_rate);
+        assert!(export.batteries.is_empty());
+        assert!(export.efoys.is_empty());
+    }
+}
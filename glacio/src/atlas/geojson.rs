@@ -0,0 +1,166 @@
+//! Heartbeats as GeoJSON, for putting a site's status on a map.
+//!
+//! A heartbeat doesn't say where it came from, so every feature is placed at its site's location.
+//! The types here serialize to a GeoJSON `FeatureCollection` of `Point` features.
+//!
+//! # Examples
+//!
+//! ```
+//! use glacio::atlas::SbdSource;
+//! use glacio::atlas::geojson::{self, Site};
+//! let heartbeats = SbdSource::new("data")
+//!     .iter()
+//!     .unwrap()
+//!     .filter_map(|result| result.ok())
+//!     .collect::<Vec<_>>();
+//! let site = Site::new("ATLAS", 66.35, -38.2);
+//! let feature_collection = geojson::feature_collection(&site, &heartbeats);
+//! assert_eq!(heartbeats.len(), feature_collection.features.len());
+//! ```
+
+use atlas::Heartbeat;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+/// A named location that heartbeats come from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Site {
+    /// The name of the site, e.g. "ATLAS".
+    pub name: String,
+    /// The latitude of the site, in decimal degrees north.
+    pub latitude: f64,
+    /// The longitude of the site, in decimal degrees east.
+    pub longitude: f64,
+}
+
+/// A GeoJSON feature collection.
+#[derive(Debug, Serialize)]
+pub struct FeatureCollection {
+    /// Always `FeatureCollection`.
+    #[serde(rename = "type")]
+    pub type_: &'static str,
+    /// One feature per heartbeat, in the same order as the heartbeats.
+    pub features: Vec<Feature>,
+}
+
+/// A GeoJSON feature for one heartbeat.
+#[derive(Debug, Serialize)]
+pub struct Feature {
+    /// Always `Feature`.
+    #[serde(rename = "type")]
+    pub type_: &'static str,
+    /// The site's location.
+    pub geometry: Point,
+    /// A summary of the heartbeat.
+    pub properties: Properties,
+}
+
+/// A GeoJSON point.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Point {
+    /// Always `Point`.
+    #[serde(rename = "type")]
+    pub type_: &'static str,
+    /// Longitude then latitude, as GeoJSON requires.
+    pub coordinates: [f64; 2],
+}
+
+/// The heartbeat summary that is attached to each feature.
+#[derive(Debug, Serialize)]
+pub struct Properties {
+    /// The name of the site.
+    pub site: String,
+    /// The date and time of the heartbeat.
+    pub datetime: DateTime<Utc>,
+    /// The version of the heartbeat message.
+    pub version: u8,
+    /// The state of charge of each battery, by battery id.
+    pub states_of_charge: BTreeMap<u8, f32>,
+    /// The datetime of the last scan start.
+    pub scan_start: DateTime<Utc>,
+    /// Is the Riegl switch enabled?
+    pub is_riegl_switch_on: bool,
+}
+
+impl Site {
+    /// Creates a new site.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::geojson::Site;
+    /// let site = Site::new("ATLAS", 66.35, -38.2);
+    /// ```
+    pub fn new(name: &str, latitude: f64, longitude: f64) -> Site {
+        Site {
+            name: name.to_string(),
+            latitude: latitude,
+            longitude: longitude,
+        }
+    }
+
+    fn point(&self) -> Point {
+        Point {
+            type_: "Point",
+            coordinates: [self.longitude, self.latitude],
+        }
+    }
+}
+
+/// Returns a feature collection with one feature per heartbeat, each positioned at the site.
+pub fn feature_collection(site: &Site, heartbeats: &[Heartbeat]) -> FeatureCollection {
+    FeatureCollection {
+        type_: "FeatureCollection",
+        features: heartbeats
+            .iter()
+            .map(|heartbeat| {
+                Feature {
+                    type_: "Feature",
+                    geometry: site.point(),
+                    properties: Properties {
+                        site: site.name.clone(),
+                        datetime: heartbeat.datetime,
+                        version: heartbeat.version,
+                        states_of_charge: heartbeat
+                            .batteries
+                            .iter()
+                            .map(|(&id, battery)| (id, battery.state_of_charge))
+                            .collect(),
+                        scan_start: heartbeat.scan_start,
+                        is_riegl_switch_on: heartbeat.is_riegl_switch_on,
+                    },
+                }
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas::SbdSource;
+    use chrono::TimeZone;
+
+    #[test]
+    fn feature_collection() {
+        let heartbeats = SbdSource::new("data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .collect::<Vec<_>>();
+        let site = Site::new("ATLAS", 66.35, -38.2);
+        let feature_collection = super::feature_collection(&site, &heartbeats);
+        assert_eq!("FeatureCollection", feature_collection.type_);
+        assert_eq!(2, feature_collection.features.len());
+        let feature = &feature_collection.features[1];
+        assert_eq!("Feature", feature.type_);
+        assert_eq!("Point", feature.geometry.type_);
+        assert_eq!([-38.2, 66.35], feature.geometry.coordinates);
+        assert_eq!("ATLAS", feature.properties.site);
+        assert_eq!(
+            Utc.ymd(2017, 8, 25).and_hms(15, 1, 6),
+            feature.properties.datetime
+        );
+        assert_eq!(Some(&85.461), feature.properties.states_of_charge.get(&1));
+    }
+}
@@ -1,16 +1,21 @@
 use atlas::{Error, Result, battery, efoy};
 use atlas::scanner::{ScanStop, ScannerPowerOn};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, Utc};
 use regex::Regex;
 use sbd::mo::Message;
+use serde::Serializer;
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
+use std::fmt::Display;
 use std::path::{Path, PathBuf};
+use std::result;
+use std::str;
 use std::vec::IntoIter;
+use sutron;
 
 lazy_static! {
     static ref RE: Regex = Regex::new(r"(?x)^
-        ATHB(?P<version>\d{2})(?P<bytes>\d+)\r\n
+        (?P<magic>[[:alpha:]]{4})(?P<version>\d{2})(?P<bytes>\d+)\r\n
         (?P<scanner_power_on>.*)\r\n
         .*\r\n # external temp, pressure, rh
         (?P<scan_start>.*)\r\n
@@ -52,16 +57,34 @@ pub struct Heartbeat {
     /// There's a hardware switch that disables the housing and scanner. The switch is controlled
     /// by the data logger, which flips the switch when the state of charges get too low.
     pub is_riegl_switch_on: bool,
+    /// The reassembled message that this heartbeat was parsed from.
+    ///
+    /// This is `None` for heartbeats that weren't parsed from a message, e.g. ones built by hand.
+    #[serde(skip_serializing)]
+    pub source_bytes: Option<Vec<u8>>,
+}
+
+/// The framing of a heartbeat message, which can differ between product lines.
+///
+/// ATLAS heartbeats start with `ATHB`. Other systems that send the same battery and EFOY sections
+/// can use their own magic number, e.g. `AMHB` for the AMIGOS buoys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Format {
+    magic: String,
 }
 
 /// Structure for retrieving ATLAS heartbeats from SBD messages.
 ///
-/// Configure the source to fetch heartbeats of one or more versions from a filesystem sbd storage.
-#[derive(Debug)]
+/// Configure the source to fetch heartbeats of one or more versions from one or more filesystem
+/// sbd storages.
+#[derive(Clone, Debug)]
 pub struct SbdSource {
-    path: PathBuf,
+    paths: Vec<PathBuf>,
     imeis: Vec<String>,
     versions: Vec<u8>,
+    format: Format,
+    until: Option<DateTime<Utc>>,
+    skip_failed_sessions: bool,
 }
 
 /// An iterator over heartbeats provided by an `SbdSource`.
@@ -72,9 +95,85 @@ pub struct SbdSource {
 pub struct ReadSbd {
     iter: IntoIter<Message>,
     versions: Vec<u8>,
+    format: Format,
+    last_time_of_session: Option<DateTime<Utc>>,
+    last_imei_and_momsn: Option<(String, u16)>,
+    last_complete: Option<(u8, String)>,
+}
+
+/// A reassembled message, along with what happened when we tried to parse it as a heartbeat.
+///
+/// Useful for debugging, when you want to see the raw text of a heartbeat that won't parse.
+#[derive(Debug)]
+pub struct MessageStatus {
+    /// The reassembled message.
+    ///
+    /// This is `None` if the SBD messages could not be reassembled, in which case the heartbeat
+    /// error explains why.
+    pub message: Option<sutron::Message>,
+    /// The time of session of the first SBD message in the message.
+    pub datetime: DateTime<Utc>,
+    /// The time of session of the last SBD message in the message.
+    pub last_datetime: DateTime<Utc>,
+    /// The number of SBD messages that went into the message, including one that failed to be
+    /// added.
+    pub packets: usize,
+    /// Is this a test message, i.e. does its first SBD message have a forced transmission or
+    /// command response type byte?
+    pub test_message: bool,
+    /// The parsed heartbeat, or the error encountered while reassembling or parsing.
+    pub heartbeat: Result<Heartbeat>,
+}
+
+/// A message that couldn't be turned into a heartbeat, along with when it arrived.
+///
+/// The error alone doesn't say when the bad heartbeat was sent, which is usually the first thing
+/// an operator wants to know.
+#[derive(Debug, Serialize)]
+pub struct BadHeartbeat {
+    /// The time of session of the first SBD message in the message.
+    pub datetime: DateTime<Utc>,
+    /// The number of SBD messages that went into the message.
+    pub packets: usize,
+    /// What went wrong, serialized as its message.
+    #[serde(serialize_with = "serialize_display")]
+    pub error: Error,
+}
+
+/// A test message that someone sent from a site by hand.
+///
+/// These are SBD messages whose type byte marks them as a forced transmission or a command
+/// response, rather than a self-timed heartbeat.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TestMessage {
+    /// The time of session of the SBD message.
+    pub datetime: DateTime<Utc>,
+    /// The payload after the type byte, decoded as lossy UTF-8.
+    pub text: String,
+}
+
+/// A jump in the MOMSN sequence of one IMEI.
+///
+/// Every mobile-originated SBD message carries a mobile originated message sequence number
+/// (MOMSN), which the modem increments by one for each message. A jump of more than one between
+/// consecutive messages means that Iridium dropped something, even if no heartbeat was affected.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MomsnGap {
+    /// The IMEI of the modem that sent the messages.
+    pub imei: String,
+    /// The MOMSN of the message before the gap.
+    pub before: u16,
+    /// The MOMSN of the message after the gap.
+    pub after: u16,
+    /// The time of session of the message after the gap.
+    pub datetime: DateTime<Utc>,
 }
 
 impl PartialEq for Heartbeat {
+    /// Heartbeats are equal if they have the same datetime, which keeps equality consistent with
+    /// their ordering.
+    ///
+    /// Use `Heartbeat::is_identical_to` to compare every field.
     fn eq(&self, other: &Heartbeat) -> bool {
         self.datetime == other.datetime
     }
@@ -88,12 +187,122 @@ impl Ord for Heartbeat {
     }
 }
 
+impl Format {
+    /// Creates a new format with the given magic number.
+    ///
+    /// The magic number has to be four ASCII letters, since that's all the heartbeat format can
+    /// match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::Format;
+    /// let format = Format::new("AMHB").unwrap();
+    /// assert_ne!(Format::default(), format);
+    /// assert!(Format::new("AM1").is_err());
+    /// ```
+    pub fn new(magic: &str) -> Result<Format> {
+        if magic.len() == 4 && magic.bytes().all(|byte| byte.is_ascii_alphabetic()) {
+            Ok(Format { magic: magic.to_string() })
+        } else {
+            Err(Error::InvalidMagicNumber(magic.to_string()))
+        }
+    }
+
+    /// Returns the four characters at the start of every heartbeat.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::Format;
+    /// assert_eq!("ATHB", Format::default().magic());
+    /// assert_eq!("AMHB", Format::new("AMHB").unwrap().magic());
+    /// ```
+    pub fn magic(&self) -> &str {
+        &self.magic
+    }
+}
+
+impl Default for Format {
+    fn default() -> Format {
+        Format { magic: "ATHB".to_string() }
+    }
+}
+
 impl Heartbeat {
-    fn new(message: &str, datetime: DateTime<Utc>) -> Result<Heartbeat> {
-        use sutron;
+    /// Parses an ATLAS heartbeat from a reassembled message.
+    ///
+    /// `datetime` is when the heartbeat was sent, usually the time of session of its first SBD
+    /// message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio::atlas::Heartbeat;
+    /// # use std::fs::File;
+    /// # use std::io::Read;
+    /// # fn main() {
+    /// let mut message = String::new();
+    /// File::open("data/170825_150106.hb").unwrap().read_to_string(&mut message).unwrap();
+    /// let datetime = Utc.ymd(2017, 8, 25).and_hms(15, 1, 6);
+    /// let heartbeat = Heartbeat::new(&message, datetime).unwrap();
+    /// assert_eq!(3, heartbeat.version);
+    /// # }
+    /// ```
+    pub fn new(message: &str, datetime: DateTime<Utc>) -> Result<Heartbeat> {
+        Heartbeat::new_with_format(message, datetime, &Format::default())
+    }
+
+    /// Parses a heartbeat with another product line's format, e.g. from an AMIGOS buoy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio::atlas::{Format, Heartbeat};
+    /// # use std::fs::File;
+    /// # use std::io::Read;
+    /// # fn main() {
+    /// let mut message = String::new();
+    /// File::open("data/170825_150106.hb").unwrap().read_to_string(&mut message).unwrap();
+    /// let message = message.replacen("ATHB", "AMHB", 1);
+    /// let datetime = Utc.ymd(2017, 8, 25).and_hms(15, 1, 6);
+    /// let format = Format::new("AMHB").unwrap();
+    /// let heartbeat = Heartbeat::new_with_format(&message, datetime, &format).unwrap();
+    /// assert!(Heartbeat::new(&message, datetime).is_err());
+    /// # }
+    /// ```
+    pub fn new_with_format(
+        message: &str,
+        datetime: DateTime<Utc>,
+        format: &Format,
+    ) -> Result<Heartbeat> {
         use std::collections::BTreeMap;
 
+        trace!("parsing a heartbeat from {} bytes", message.len());
         if let Some(ref captures) = RE.captures(message) {
+            for name in RE.capture_names().filter_map(|name| name) {
+                if let Some(section) = captures.name(name) {
+                    trace!(
+                        "heartbeat section {} is bytes {}..{}",
+                        name,
+                        section.start(),
+                        section.end()
+                    );
+                }
+            }
+            let magic = captures.name("magic").unwrap().as_str();
+            if magic != format.magic() {
+                return Err(Error::MagicNumber {
+                    expected: format.magic().to_string(),
+                    found: magic.to_string(),
+                });
+            }
             let mut batteries = BTreeMap::new();
             batteries.insert(1, parse_name_from_captures!(captures, "soc1"));
             batteries.insert(2, parse_name_from_captures!(captures, "soc2"));
@@ -111,11 +320,235 @@ impl Heartbeat {
                 )?,
                 scan_stop: parse_name_from_captures!(captures, "scan_stop"),
                 is_riegl_switch_on: captures.name("riegl_switch").unwrap().as_str() == "on",
+                source_bytes: Some(message.as_bytes().to_vec()),
             })
         } else {
+            debug!(
+                "{} bytes starting with {:?} did not match the heartbeat format",
+                message.len(),
+                message.chars().take(8).collect::<String>()
+            );
             Err(Error::HeartbeatFormat(message.to_string()))
         }
     }
+
+    /// Returns the battery in the given physical slot, or `None` if that slot didn't report.
+    ///
+    /// Slots are zero-indexed, so slot zero is the battery with id 1 in `batteries`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let heartbeat = SbdSource::new("data")
+    ///     .iter()
+    ///     .unwrap()
+    ///     .filter_map(|result| result.ok())
+    ///     .next()
+    ///     .unwrap();
+    /// assert_eq!(94.208, heartbeat.battery_slot(0).unwrap().state_of_charge);
+    /// assert!(heartbeat.battery_slot(3).is_none());
+    /// ```
+    pub fn battery_slot(&self, slot: usize) -> Option<&battery::Heartbeat> {
+        if slot < 255 {
+            self.batteries.get(&(slot as u8 + 1))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if every parsed field of this heartbeat is equal to the other's.
+    ///
+    /// `==` only compares datetimes, so heartbeats sort and dedupe by time. This is for regression
+    /// tests, where two heartbeats with the same datetime should also have the same contents.
+    /// Floats are compared exactly, so a `NaN` in either heartbeat makes them not identical. The
+    /// raw bytes aren't compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let heartbeats = SbdSource::new("data")
+    ///     .iter()
+    ///     .unwrap()
+    ///     .filter_map(|result| result.ok())
+    ///     .collect::<Vec<_>>();
+    /// assert!(heartbeats[0].is_identical_to(&heartbeats[0].clone()));
+    /// assert!(!heartbeats[0].is_identical_to(&heartbeats[1]));
+    /// ```
+    pub fn is_identical_to(&self, other: &Heartbeat) -> bool {
+        self.version == other.version && self.datetime == other.datetime &&
+            self.batteries == other.batteries &&
+            self.scanner_power_on == other.scanner_power_on &&
+            self.scan_start == other.scan_start &&
+            self.scan_stop == other.scan_stop && self.efoys == other.efoys &&
+            self.is_riegl_switch_on == other.is_riegl_switch_on
+    }
+
+    /// Returns the exact bytes of the reassembled message that this heartbeat was parsed from.
+    ///
+    /// Keep these around to re-parse a heartbeat with a newer version of this crate. They aren't
+    /// serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let heartbeat = SbdSource::new("data")
+    ///     .iter()
+    ///     .unwrap()
+    ///     .filter_map(|result| result.ok())
+    ///     .next()
+    ///     .unwrap();
+    /// assert!(heartbeat.raw_bytes().unwrap().starts_with(b"ATHB03"));
+    /// ```
+    pub fn raw_bytes(&self) -> Option<&[u8]> {
+        self.source_bytes.as_ref().map(|bytes| bytes.as_slice())
+    }
+}
+
+impl MomsnGap {
+    /// Returns the number of messages that are missing in this gap.
+    ///
+    /// MOMSNs are sixteen bits and wrap from 65535 back to zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::Utc;
+    /// # use glacio::atlas::MomsnGap;
+    /// # fn main() {
+    /// let gap = MomsnGap {
+    ///     imei: "300234063556840".to_string(),
+    ///     before: 65535,
+    ///     after: 2,
+    ///     datetime: Utc::now(),
+    /// };
+    /// assert_eq!(2, gap.missing());
+    /// # }
+    /// ```
+    pub fn missing(&self) -> u16 {
+        self.after.wrapping_sub(self.before).wrapping_sub(1)
+    }
+}
+
+impl MessageStatus {
+    /// Returns the time between the first and the last SBD message of a multi-packet message.
+    ///
+    /// Returns `None` for single-packet messages. Large latencies suggest that packets are being
+    /// held at the Iridium gateway.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let statuses = SbdSource::new("data").messages_with_status().unwrap();
+    /// assert_eq!(None, statuses[0].reassembly_latency());
+    /// assert_eq!(20, statuses[1].reassembly_latency().unwrap().num_seconds());
+    /// ```
+    pub fn reassembly_latency(&self) -> Option<Duration> {
+        if self.packets < 2 {
+            None
+        } else {
+            Some(self.last_datetime.signed_duration_since(self.datetime))
+        }
+    }
+
+    /// Turns this status into a bad heartbeat, or returns `None` if it is a valid heartbeat or a
+    /// test message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let mut statuses = SbdSource::new("data").messages_with_status().unwrap();
+    /// assert!(statuses.pop().unwrap().into_bad_heartbeat().is_none());
+    /// // The first message is a test message, which isn't a heartbeat but isn't bad either.
+    /// assert!(statuses.remove(0).into_bad_heartbeat().is_none());
+    /// ```
+    pub fn into_bad_heartbeat(self) -> Option<BadHeartbeat> {
+        if self.test_message {
+            return None;
+        }
+        match self.heartbeat {
+            Ok(_) => None,
+            Err(error) => Some(BadHeartbeat {
+                datetime: self.datetime,
+                packets: self.packets,
+                error: error,
+            }),
+        }
+    }
+}
+
+/// Returns the most recent heartbeat in the slice, or `None` if the slice is empty.
+///
+/// Heartbeats are compared by their datetime, so the slice does not need to be sorted. Use this
+/// instead of `.last()` when you aren't sure where your heartbeats came from.
+///
+/// # Examples
+///
+/// ```
+/// # use glacio::atlas::{self, SbdSource};
+/// let mut heartbeats = SbdSource::new("data")
+///     .iter()
+///     .unwrap()
+///     .filter_map(|result| result.ok())
+///     .collect::<Vec<_>>();
+/// heartbeats.reverse();
+/// let latest = atlas::latest_heartbeat(&heartbeats).unwrap();
+/// assert_eq!(heartbeats[0].datetime, latest.datetime);
+/// ```
+pub fn latest_heartbeat(heartbeats: &[Heartbeat]) -> Option<&Heartbeat> {
+    heartbeats.iter().max_by_key(|heartbeat| heartbeat.datetime)
+}
+
+/// Returns how often these heartbeats were sent, i.e. the most common time between them.
+///
+/// The heartbeats don't need to be sorted. Ties go to the shorter duration. Returns `None` if
+/// there are fewer than two heartbeats.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate chrono;
+/// # extern crate glacio;
+/// # use chrono::Duration;
+/// # use glacio::atlas::{self, SbdSource};
+/// # fn main() {
+/// let heartbeats = SbdSource::new("data")
+///     .iter()
+///     .unwrap()
+///     .filter_map(|result| result.ok())
+///     .collect::<Vec<_>>();
+/// let rate = atlas::heartbeat_rate(&heartbeats).unwrap();
+/// assert!(rate > Duration::zero());
+/// assert_eq!(None, atlas::heartbeat_rate(&heartbeats[..1]));
+/// # }
+/// ```
+pub fn heartbeat_rate(heartbeats: &[Heartbeat]) -> Option<Duration> {
+    let mut datetimes = heartbeats
+        .iter()
+        .map(|heartbeat| heartbeat.datetime)
+        .collect::<Vec<_>>();
+    datetimes.sort();
+    let mut counts: BTreeMap<Duration, usize> = BTreeMap::new();
+    for window in datetimes.windows(2) {
+        *counts
+            .entry(window[1].signed_duration_since(window[0]))
+            .or_insert(0) += 1;
+    }
+    let mut rate = None;
+    let mut max_count = 0;
+    for (&duration, &count) in &counts {
+        if count > max_count {
+            rate = Some(duration);
+            max_count = count;
+        }
+    }
+    rate
 }
 
 impl SbdSource {
@@ -129,12 +562,32 @@ impl SbdSource {
     /// ```
     pub fn new<P: AsRef<Path>>(path: P) -> SbdSource {
         SbdSource {
-            path: path.as_ref().to_path_buf(),
+            paths: vec![path.as_ref().to_path_buf()],
             imeis: Vec::new(),
             versions: Vec::new(),
+            format: Format::default(),
+            until: None,
+            skip_failed_sessions: false,
         }
     }
 
+    /// Adds another local filesystem path to read SBD messages from, e.g. an archive volume.
+    ///
+    /// Messages from every path are merged into one time-ordered stream, so a heartbeat can be
+    /// pieced together from packets stored in different paths. A message that is in more than
+    /// one path, e.g. while it's being moved to the archive, is only read once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let source = SbdSource::new("/var/iridium").add_path("/archive/iridium");
+    /// ```
+    pub fn add_path<P: AsRef<Path>>(mut self, path: P) -> SbdSource {
+        self.paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
     /// Sets (or clears) the imei numbers to be used as heartbeat sources.
     ///
     /// If the slice is empty, this clears the imei filter and all imeis will be used.
@@ -164,6 +617,218 @@ impl SbdSource {
         self
     }
 
+    /// Sets the heartbeat format, e.g. to read another product line's heartbeats.
+    ///
+    /// Defaults to the ATLAS format. Messages with any other magic number are bad heartbeats.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::{Format, SbdSource};
+    /// let source = SbdSource::new("data").format(Format::new("AMHB").unwrap());
+    /// assert_eq!(0, source.iter().unwrap().filter_map(|result| result.ok()).count());
+    /// ```
+    pub fn format(mut self, format: Format) -> SbdSource {
+        self.format = format;
+        self
+    }
+
+    /// Only reads SBD messages whose time of session is at or before `until`.
+    ///
+    /// Filesystem storages are laid out as `IMEI/YYYY/MM`, so the year and month directories after
+    /// `until` are skipped without opening any of their files, which keeps this cheap even when
+    /// the storage holds years of newer messages. Messages from `until`'s own month are read, then
+    /// dropped by time of session. A heartbeat whose last packet arrived after `until` won't be
+    /// reassembled, because it hadn't all arrived yet, which is what you want when reproducing
+    /// what we knew at that instant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio::atlas::SbdSource;
+    /// # fn main() {
+    /// let source = SbdSource::new("data").until(Utc.ymd(2017, 8, 2).and_hms(0, 0, 0));
+    /// let heartbeats = source
+    ///     .iter()
+    ///     .unwrap()
+    ///     .filter_map(|result| result.ok())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(1, heartbeats.len());
+    /// # }
+    /// ```
+    pub fn until(mut self, until: DateTime<Utc>) -> SbdSource {
+        self.until = Some(until);
+        self
+    }
+
+    /// Returns the latest heartbeat at or before `as_of`, or `None` if there isn't one.
+    ///
+    /// Only the messages that had arrived by `as_of` are read, see `until`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio::atlas::SbdSource;
+    /// # fn main() {
+    /// let as_of = Utc.ymd(2017, 8, 2).and_hms(0, 0, 0);
+    /// let heartbeat = SbdSource::new("data").latest_heartbeat_as_of(as_of).unwrap().unwrap();
+    /// assert!(heartbeat.datetime <= as_of);
+    /// # }
+    /// ```
+    pub fn latest_heartbeat_as_of(&self, as_of: DateTime<Utc>) -> Result<Option<Heartbeat>> {
+        let mut source = self.clone();
+        if source.until.map_or(true, |until| as_of < until) {
+            source.until = Some(as_of);
+        }
+        Ok(
+            source
+                .iter()?
+                .filter_map(|result| result.ok())
+                .filter(|heartbeat| heartbeat.datetime <= as_of)
+                .max_by_key(|heartbeat| heartbeat.datetime),
+        )
+    }
+
+    /// Sets whether to skip SBD messages whose session didn't complete successfully.
+    ///
+    /// A failed session, e.g. a timeout or a lost RF link, often goes along with a corrupt
+    /// packet. Skipped messages are left out of everything this source reads, including MOMSN
+    /// gaps. Defaults to false.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let source = SbdSource::new("data").skip_failed_sessions(true);
+    /// assert_eq!(2, source.iter().unwrap().filter_map(|result| result.ok()).count());
+    /// ```
+    pub fn skip_failed_sessions(mut self, skip_failed_sessions: bool) -> SbdSource {
+        self.skip_failed_sessions = skip_failed_sessions;
+        self
+    }
+
+    /// Returns the gaps in the MOMSN sequences of this source's SBD messages.
+    ///
+    /// Each IMEI has its own sequence, and messages are ordered by their time of session. A
+    /// repeated MOMSN is not a gap. The versions filter is ignored, since a dropped message could
+    /// have been part of any heartbeat.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let gaps = SbdSource::new("data").momsn_gaps().unwrap();
+    /// for gap in gaps {
+    ///     println!("{} missing {} message(s) before {}", gap.imei, gap.missing(), gap.datetime);
+    /// }
+    /// ```
+    pub fn momsn_gaps(&self) -> Result<Vec<MomsnGap>> {
+        let mut sequences = BTreeMap::new();
+        for message in self.messages()? {
+            sequences
+                .entry(message.imei().to_string())
+                .or_insert_with(Vec::new)
+                .push((message.momsn(), message.time_of_session()));
+        }
+        let mut gaps = Vec::new();
+        for (imei, sequence) in sequences {
+            gaps.extend(momsn_gaps(&imei, &sequence));
+        }
+        gaps.sort_by_key(|gap| gap.datetime);
+        Ok(gaps)
+    }
+
+    /// Returns every message in this source, each paired with its datetime and the result of
+    /// parsing it as a heartbeat.
+    ///
+    /// Messages with heartbeat versions that aren't configured are skipped, just like `iter`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let statuses = SbdSource::new("data").messages_with_status().unwrap();
+    /// for status in statuses {
+    ///     if let Err(err) = status.heartbeat {
+    ///         println!("{}: {:?} ({})", status.datetime, status.message, err);
+    ///     }
+    /// }
+    /// ```
+    pub fn messages_with_status(&self) -> Result<Vec<MessageStatus>> {
+        let mut read_sbd = self.iter()?;
+        let mut statuses = Vec::new();
+        while let Some(status) = read_sbd.next_status() {
+            statuses.push(status);
+        }
+        Ok(statuses)
+    }
+
+    /// Returns every message in this source that couldn't be turned into a heartbeat, oldest
+    /// first.
+    ///
+    /// Test messages were never meant to be heartbeats, so they aren't included. Use
+    /// `test_messages` to get them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let bad_heartbeats = SbdSource::new("data").bad_heartbeats().unwrap();
+    /// for bad_heartbeat in bad_heartbeats {
+    ///     println!("{}: {}", bad_heartbeat.datetime, bad_heartbeat.error);
+    /// }
+    /// ```
+    pub fn bad_heartbeats(&self) -> Result<Vec<BadHeartbeat>> {
+        Ok(
+            self.messages_with_status()?
+                .into_iter()
+                .filter_map(|status| status.into_bad_heartbeat())
+                .collect(),
+        )
+    }
+
+    /// Returns every test message in this source, oldest first.
+    ///
+    /// A test message is any SBD message whose first byte is a forced transmission or command
+    /// response type byte. Each SBD message is its own test message, even if it's an extended
+    /// packet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let test_messages = SbdSource::new("data").test_messages().unwrap();
+    /// assert_eq!("test", test_messages[0].text);
+    /// ```
+    pub fn test_messages(&self) -> Result<Vec<TestMessage>> {
+        Ok(self.messages()?.iter().filter_map(test_message).collect())
+    }
+
+    /// Returns an iterator over the heartbeats in this source along with its test messages.
+    ///
+    /// The storage is only read once, so this is cheaper than calling `iter` and
+    /// `test_messages` separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let (heartbeats, test_messages) = SbdSource::new("data").iter_with_test_messages().unwrap();
+    /// assert_eq!(3, heartbeats.count());
+    /// assert_eq!("test", test_messages[0].text);
+    /// ```
+    pub fn iter_with_test_messages(&self) -> Result<(ReadSbd, Vec<TestMessage>)> {
+        let messages = self.messages()?;
+        let test_messages = messages.iter().filter_map(test_message).collect();
+        Ok((self.read_sbd(messages), test_messages))
+    }
+
     /// Returns an iterator over the heartbeats in this source.
     ///
     /// Returns an error if the underlying storage can't be opened.
@@ -177,59 +842,323 @@ impl SbdSource {
     ///     println!("{:?}", heartbeat);
     /// }
     pub fn iter(&self) -> Result<ReadSbd> {
+        Ok(self.read_sbd(self.messages()?))
+    }
+
+    fn read_sbd(&self, messages: Vec<Message>) -> ReadSbd {
+        ReadSbd {
+            iter: messages.into_iter(),
+            versions: self.versions.clone(),
+            format: self.format.clone(),
+            last_time_of_session: None,
+            last_imei_and_momsn: None,
+            last_complete: None,
+        }
+    }
+
+    /// Returns this source's SBD messages, sorted by time of session.
+    ///
+    /// Messages with the same IMEI, MOMSN, and time of session are duplicates, and only the first
+    /// one is kept.
+    fn messages(&self) -> Result<Vec<Message>> {
         use sbd::storage::{FilesystemStorage, Storage};
-        let storage = FilesystemStorage::open(&self.path)?;
+        use std::collections::BTreeSet;
+
         let mut messages = Vec::new();
-        if self.imeis.is_empty() {
-            messages = storage.messages()?;
-        } else {
-            for imei in &self.imeis {
-                messages.extend(storage.messages_from_imei(imei)?);
+        for path in &self.paths {
+            let storage = FilesystemStorage::open(path)?;
+            if let Some(until) = self.until {
+                read_messages_until(path, 1, None, &self.imeis, until, &mut messages)?;
+            } else if self.imeis.is_empty() {
+                messages.extend(storage.messages()?);
+            } else {
+                for imei in &self.imeis {
+                    messages.extend(storage.messages_from_imei(imei)?);
+                }
             }
         }
+        let mut seen = BTreeSet::new();
+        messages.retain(|message| {
+            seen.insert((
+                message.imei().to_string(),
+                message.momsn(),
+                message.time_of_session(),
+            ))
+        });
+        if let Some(until) = self.until {
+            messages.retain(|message| message.time_of_session() <= until);
+        }
+        if self.skip_failed_sessions {
+            messages.retain(|message| if session_ok(message) {
+                true
+            } else {
+                debug!(
+                    "skipping sbd message from {} with momsn {} because its session failed",
+                    message.imei(),
+                    message.momsn()
+                );
+                false
+            });
+        }
         messages.sort_by(|a, b| a.time_of_session().cmp(&b.time_of_session()));
-        Ok(ReadSbd {
-            iter: messages.into_iter(),
-            versions: self.versions.clone(),
+        Ok(messages)
+    }
+}
+
+/// Returns true if the SBD session that delivered this message completed successfully.
+///
+/// The three "ok" Iridium session statuses are successful transfers, and anything else (e.g. a
+/// timeout or a lost RF link) means the transfer failed or was only partly delivered.
+pub fn session_ok(message: &Message) -> bool {
+    use sbd::mo::SessionStatus;
+
+    match message.session_status() {
+        SessionStatus::Ok |
+        SessionStatus::OkMobileTerminatedTooLarge |
+        SessionStatus::OkLocationUnacceptableQuality => true,
+        _ => false,
+    }
+}
+
+/// Returns the message as a test message, if its first byte is a test message type byte.
+fn test_message(message: &Message) -> Option<TestMessage> {
+    let payload = message.payload_ref();
+    if is_test_payload(payload) {
+        Some(TestMessage {
+            datetime: message.time_of_session(),
+            text: String::from_utf8_lossy(&payload[1..]).into_owned(),
         })
+    } else {
+        None
     }
 }
 
-impl Iterator for ReadSbd {
-    type Item = Result<Heartbeat>;
+/// Is the first byte of this payload a test message type byte?
+fn is_test_payload(payload: &[u8]) -> bool {
+    use sutron::message::Type;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        use sutron::Message;
-        let mut message = Message::new();
+    match payload.first().map(|&byte| Type::from(byte)) {
+        Some(Type::ForcedTransmission) |
+        Some(Type::CommandResponse) => true,
+        _ => false,
+    }
+}
+
+/// Reads the SBD messages under `dir`, skipping the year and month directories after `until`.
+///
+/// `depth` is how far `dir`'s entries are below the storage root: IMEI directories are at one,
+/// years at two, and months at three. Directories that aren't named like a year or a month are
+/// read like any other.
+fn read_messages_until(
+    dir: &Path,
+    depth: usize,
+    year: Option<i32>,
+    imeis: &[String],
+    until: DateTime<Utc>,
+    messages: &mut Vec<Message>,
+) -> Result<()> {
+    use std::fs;
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            let mut year = year;
+            match depth {
+                1 if !imeis.is_empty() && !imeis.iter().any(|imei| imei == name) => continue,
+                2 => {
+                    year = name.parse().ok();
+                    if year.map_or(false, |year| year > until.year()) {
+                        continue;
+                    }
+                }
+                3 => {
+                    let month = name.parse::<u32>().ok();
+                    if let (Some(year), Some(month)) = (year, month) {
+                        if (year, month) > (until.year(), until.month()) {
+                            continue;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            read_messages_until(&path, depth + 1, year, imeis, until, messages)?;
+        } else if path.extension().map_or(false, |extension| extension == "sbd") {
+            messages.push(Message::from_path(&path)?);
+        }
+    }
+    Ok(())
+}
+
+/// Finds the gaps in one IMEI's sequence of (MOMSN, time of session) pairs.
+fn momsn_gaps(imei: &str, sequence: &[(u16, DateTime<Utc>)]) -> Vec<MomsnGap> {
+    sequence
+        .windows(2)
+        .filter_map(|window| {
+            let (before, _) = window[0];
+            let (after, datetime) = window[1];
+            if after == before || after == before.wrapping_add(1) {
+                None
+            } else {
+                Some(MomsnGap {
+                    imei: imei.to_string(),
+                    before: before,
+                    after: after,
+                    datetime: datetime,
+                })
+            }
+        })
+        .collect()
+}
+
+impl ReadSbd {
+    /// Returns the time of session of the last SBD message that this iterator has consumed.
+    ///
+    /// After a call to `next`, this is the time of the last message that went into the returned
+    /// heartbeat (or error).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let mut read_sbd = SbdSource::new("data").iter().unwrap();
+    /// assert_eq!(None, read_sbd.last_time_of_session());
+    /// read_sbd.next();
+    /// assert!(read_sbd.last_time_of_session().is_some());
+    /// ```
+    pub fn last_time_of_session(&self) -> Option<DateTime<Utc>> {
+        self.last_time_of_session
+    }
+
+    /// Returns the IMEI and MOMSN of the last SBD message that this iterator has consumed.
+    ///
+    /// Two SBD messages can share a time of session, so this is what tells them apart.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// let mut read_sbd = SbdSource::new("data").iter().unwrap();
+    /// assert_eq!(None, read_sbd.last_imei_and_momsn());
+    /// read_sbd.next();
+    /// assert_eq!("300234063556840", read_sbd.last_imei_and_momsn().unwrap().0);
+    /// ```
+    pub fn last_imei_and_momsn(&self) -> Option<(&str, u16)> {
+        self.last_imei_and_momsn.as_ref().map(
+            |&(ref imei, momsn)| (imei.as_str(), momsn),
+        )
+    }
+
+    fn next_status(&mut self) -> Option<MessageStatus> {
+        let mut message = sutron::Message::new();
         let mut datetime = None;
+        let mut packets = 0;
+        let mut test_message = false;
         while let Some(sbd_message) = self.iter.next() {
-            if datetime.is_none() {
-                datetime = Some(sbd_message.time_of_session());
+            let time_of_session = sbd_message.time_of_session();
+            trace!(
+                "read sbd message from {} with momsn {} at {}",
+                sbd_message.imei(),
+                sbd_message.momsn(),
+                time_of_session
+            );
+            self.last_time_of_session = Some(time_of_session);
+            self.last_imei_and_momsn = Some((sbd_message.imei().to_string(), sbd_message.momsn()));
+            let first_datetime = *datetime.get_or_insert(time_of_session);
+            packets += 1;
+            if packets == 1 {
+                test_message = is_test_payload(sbd_message.payload_ref());
+            }
+            let payload = match str::from_utf8(sbd_message.payload_ref()) {
+                Ok(payload) => payload,
+                Err(err) => {
+                    return Some(MessageStatus {
+                        message: None,
+                        datetime: first_datetime,
+                        last_datetime: time_of_session,
+                        packets: packets,
+                        test_message: test_message,
+                        heartbeat: Err(err.into()),
+                    })
+                }
+            };
+            if message.packets() == Some(0) && self.is_duplicate_of_last_complete(payload) {
+                datetime = None;
+                packets = 0;
+                continue;
             }
-            match message.add(sbd_message.payload_str().unwrap()) {
+            let id = message.id();
+            match message.add(payload) {
                 Ok(new_message) => {
                     if new_message.is_complete() {
-                        match Heartbeat::new(&String::from(new_message), datetime.unwrap()) {
-                            Ok(heartbeat) => {
-                                if self.versions.is_empty() ||
-                                    self.versions.contains(&heartbeat.version)
-                                {
-                                    return Some(Ok(heartbeat));
-                                } else {
-                                    message = Message::new();
-                                }
+                        let data = String::from(new_message.clone());
+                        self.last_complete = id.map(|id| (id, data.clone()));
+                        let heartbeat =
+                            Heartbeat::new_with_format(&data, first_datetime, &self.format);
+                        if let Ok(ref heartbeat) = heartbeat {
+                            if !self.versions.is_empty() &&
+                                !self.versions.contains(&heartbeat.version)
+                            {
+                                message = sutron::Message::new();
+                                datetime = None;
+                                packets = 0;
+                                continue;
                             }
-                            Err(err) => return Some(Err(err)),
                         }
+                        return Some(MessageStatus {
+                            message: Some(new_message),
+                            datetime: first_datetime,
+                            last_datetime: time_of_session,
+                            packets: packets,
+                            test_message: test_message,
+                            heartbeat: heartbeat,
+                        });
                     } else {
                         message = new_message;
                     }
                 }
-                Err(err) => return Some(Err(err.into())),
+                Err(err) => {
+                    return Some(MessageStatus {
+                        message: None,
+                        datetime: first_datetime,
+                        last_datetime: time_of_session,
+                        packets: packets,
+                        test_message: test_message,
+                        heartbeat: Err(err.into()),
+                    })
+                }
             }
         }
         None
     }
+
+    fn is_duplicate_of_last_complete(&self, payload: &str) -> bool {
+        if let Some((id, ref data)) = self.last_complete {
+            if let Ok(packet) = payload.parse::<sutron::message::Packet>() {
+                if packet.is_duplicate_of(id, data) {
+                    debug!("dropping a repeat of the last packet of message {}", id);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+fn serialize_display<T, S>(value: &T, serializer: S) -> result::Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+impl Iterator for ReadSbd {
+    type Item = Result<Heartbeat>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_status().map(|status| status.heartbeat)
+    }
 }
 
 #[cfg(test)]
@@ -244,6 +1173,322 @@ mod tests {
         assert_eq!(3, heartbeats.len());
     }
 
+    #[test]
+    fn format() {
+        let message = include_str!("../../data/170825_150106.hb").replacen("ATHB", "AMHB", 1);
+        let datetime = Utc.ymd(2017, 8, 25).and_hms(15, 1, 6);
+        let format = Format::new("AMHB").unwrap();
+        let heartbeat = Heartbeat::new_with_format(&message, datetime, &format).unwrap();
+        assert_eq!(3, heartbeat.version);
+        match Heartbeat::new(&message, datetime).unwrap_err() {
+            Error::MagicNumber { expected, found } => {
+                assert_eq!("ATHB", expected);
+                assert_eq!("AMHB", found);
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+        for magic in &["", "ATH", "ATHB0", "AT1B", "AT B", "ATHÉ"] {
+            match Format::new(magic).unwrap_err() {
+                Error::InvalidMagicNumber(ref s) => assert_eq!(magic, s),
+                err => panic!("unexpected error: {}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn raw_bytes() {
+        let heartbeats = SbdSource::new("data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .collect::<Vec<_>>();
+        assert_eq!(2, heartbeats.len());
+        for heartbeat in heartbeats {
+            let bytes = heartbeat.raw_bytes().unwrap();
+            let message = ::std::str::from_utf8(bytes).unwrap();
+            let reparsed = Heartbeat::new(message, heartbeat.datetime).unwrap();
+            assert!(heartbeat.is_identical_to(&reparsed));
+            assert_eq!(bytes, reparsed.raw_bytes().unwrap());
+        }
+    }
+
+    #[test]
+    fn is_identical_to() {
+        let message = include_str!("../../data/170825_150106.hb");
+        let datetime = Utc.ymd(2017, 8, 25).and_hms(15, 1, 6);
+        let heartbeat = Heartbeat::new(message, datetime).unwrap();
+        assert!(heartbeat.is_identical_to(&Heartbeat::new(message, datetime).unwrap()));
+
+        let mut changed = heartbeat.clone();
+        changed.batteries.get_mut(&1).unwrap().state_of_charge += 0.001;
+        assert_eq!(heartbeat, changed);
+        assert!(!heartbeat.is_identical_to(&changed));
+
+        let mut nan = heartbeat.clone();
+        nan.batteries.get_mut(&1).unwrap().state_of_charge = ::std::f32::NAN;
+        assert!(!nan.is_identical_to(&nan.clone()));
+    }
+
+    #[test]
+    fn messages_with_status() {
+        let statuses = SbdSource::new("data").messages_with_status().unwrap();
+        assert_eq!(3, statuses.len());
+        assert!(statuses[0].heartbeat.is_err());
+        assert_eq!(
+            "test",
+            String::from(statuses[0].message.clone().unwrap())
+        );
+        assert!(statuses[1].heartbeat.is_ok());
+        assert_eq!(Utc.ymd(2017, 8, 1).and_hms(0, 0, 55), statuses[1].datetime);
+        assert_eq!(Utc.ymd(2017, 8, 1).and_hms(0, 1, 15), statuses[1].last_datetime);
+        assert_eq!(2, statuses[1].packets);
+        assert_eq!(1, statuses[2].packets);
+        assert_eq!(None, statuses[2].reassembly_latency());
+        assert!(statuses[2].heartbeat.is_ok());
+    }
+
+    #[test]
+    fn test_messages() {
+        let test_messages = SbdSource::new("data").test_messages().unwrap();
+        assert_eq!(
+            vec![
+                TestMessage {
+                    datetime: Utc.ymd(2016, 7, 19).and_hms(19, 31, 36),
+                    text: "test".to_string(),
+                },
+            ],
+            test_messages
+        );
+    }
+
+    #[test]
+    fn multiple_paths() {
+        use test_util::{self, TempDir};
+
+        let dir = TempDir::new("sbd-source-multiple-paths").unwrap();
+        let copy = |root: &str, subpath: &str| {
+            test_util::copy_sbd(&dir.join(root), subpath, |_| {}).unwrap()
+        };
+        // The first packet of a two-packet heartbeat is in both paths, as if it were being moved
+        // to the archive, and the second packet is only in the live path.
+        copy("archive", "300234063556840/2016/07/160719_193136.sbd");
+        copy("archive", "300234063556840/2017/08/170801_000055.sbd");
+        copy("live", "300234063556840/2017/08/170801_000055.sbd");
+        copy("live", "300234063556840/2017/08/170801_000115.sbd");
+        copy("live", "300234063556840/2017/08/170825_150106.sbd");
+
+        let source = SbdSource::new(dir.join("live")).add_path(dir.join("archive"));
+        let statuses = source.messages_with_status().unwrap();
+        assert_eq!(3, statuses.len());
+        assert!(statuses[0].heartbeat.is_err());
+        assert_eq!(Utc.ymd(2017, 8, 1).and_hms(0, 0, 55), statuses[1].datetime);
+        assert_eq!(2, statuses[1].packets);
+        assert!(statuses[1].heartbeat.is_ok());
+        assert_eq!(Utc.ymd(2017, 8, 25).and_hms(15, 1, 6), statuses[2].datetime);
+        assert_eq!(
+            SbdSource::new("data").momsn_gaps().unwrap(),
+            source.momsn_gaps().unwrap()
+        );
+
+        let heartbeats = SbdSource::new("data")
+            .add_path("data")
+            .iter()
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(3, heartbeats.len());
+    }
+
+    #[test]
+    fn skip_failed_sessions() {
+        use test_util::{self, TempDir};
+
+        let dir = TempDir::new("sbd-source-skip-failed-sessions").unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2017/08/170801_000055.sbd", |_| {}).unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2017/08/170801_000115.sbd", |_| {}).unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2017/08/170825_150106.sbd", |bytes| {
+            // The session status byte comes right after the header's IMEI, and 13 is an RF link
+            // loss.
+            bytes[25] = 13;
+        }).unwrap();
+
+        let count = |source: SbdSource| source.iter().unwrap().filter_map(|r| r.ok()).count();
+        assert_eq!(2, count(SbdSource::new(&dir)));
+        let heartbeats = SbdSource::new(&dir)
+            .skip_failed_sessions(true)
+            .iter()
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect::<Vec<_>>();
+        assert_eq!(1, heartbeats.len());
+        assert_eq!(Utc.ymd(2017, 8, 1).and_hms(0, 0, 55), heartbeats[0].datetime);
+    }
+
+    #[test]
+    fn duplicate_final_packet() {
+        use test_util::{self, TempDir};
+
+        let dir = TempDir::new("sbd-source-duplicate-final-packet").unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2017/08/170801_000055.sbd", |_| {}).unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2017/08/170801_000115.sbd", |_| {}).unwrap();
+        let resent = dir.join("resent");
+        test_util::copy_sbd(&resent, "300234063556840/2017/08/170801_000115.sbd", |bytes| {
+            // Bump the MOMSN and the time of session, as if the modem sent the packet again.
+            bytes[27] += 1;
+            bytes[33] += 5;
+        }).unwrap();
+
+        let statuses = SbdSource::new(&dir).messages_with_status().unwrap();
+        assert_eq!(1, statuses.len());
+        assert!(statuses[0].heartbeat.is_ok());
+    }
+
+    #[test]
+    fn bad_heartbeats() {
+        use test_util::{self, TempDir};
+
+        // The only message in the data that isn't a heartbeat is the "8test" test message.
+        assert!(SbdSource::new("data").bad_heartbeats().unwrap().is_empty());
+        let statuses = SbdSource::new("data").messages_with_status().unwrap();
+        assert!(statuses[0].test_message);
+        assert!(statuses[1..].iter().all(|status| !status.test_message));
+
+        let dir = TempDir::new("sbd-source-bad-heartbeats").unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2016/07/160719_193136.sbd", |bytes| {
+            let n = bytes.len();
+            assert_eq!(b"8test", &bytes[n - 5..]);
+            bytes[n - 5] = b'0';
+        }).unwrap();
+        let bad_heartbeats = SbdSource::new(&dir).bad_heartbeats().unwrap();
+        assert_eq!(1, bad_heartbeats.len());
+        let bad_heartbeat = &bad_heartbeats[0];
+        assert_eq!(Utc.ymd(2016, 7, 19).and_hms(19, 31, 36), bad_heartbeat.datetime);
+        assert_eq!(1, bad_heartbeat.packets);
+        match bad_heartbeat.error {
+            Error::HeartbeatFormat(ref message) => assert_eq!("test", message),
+            ref err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn gaps() {
+        let datetime = |minute| Utc.ymd(2017, 8, 1).and_hms(0, minute, 0);
+        let sequence = [
+            (65533, datetime(0)),
+            (65534, datetime(1)),
+            (65534, datetime(2)),
+            (65535, datetime(3)),
+            (2, datetime(4)),
+            (3, datetime(5)),
+            (7, datetime(6)),
+        ];
+        let gaps = momsn_gaps("300234063556840", &sequence);
+        assert_eq!(2, gaps.len());
+        assert_eq!(65535, gaps[0].before);
+        assert_eq!(2, gaps[0].after);
+        assert_eq!(2, gaps[0].missing());
+        assert_eq!(datetime(4), gaps[0].datetime);
+        assert_eq!(3, gaps[1].before);
+        assert_eq!(7, gaps[1].after);
+        assert_eq!(3, gaps[1].missing());
+        assert_eq!("300234063556840", gaps[1].imei);
+        assert!(momsn_gaps("300234063556840", &sequence[..1]).is_empty());
+    }
+
+    #[test]
+    fn latest() {
+        assert!(latest_heartbeat(&[]).is_none());
+        let mut heartbeats = SbdSource::new("data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .collect::<Vec<_>>();
+        heartbeats.swap(0, 1);
+        assert_eq!(
+            Utc.ymd(2017, 8, 25).and_hms(15, 1, 6),
+            latest_heartbeat(&heartbeats).unwrap().datetime
+        );
+    }
+
+    #[test]
+    fn until_skips_later_months() {
+        use std::fs::{self, File};
+        use std::io::Write;
+        use test_util::{self, TempDir};
+
+        let dir = TempDir::new("sbd-source-until-skips-later-months").unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2017/08/170825_150106.sbd", |_| {}).unwrap();
+        fs::create_dir_all(dir.join("300234063556840/2017/09")).unwrap();
+        File::create(dir.join("300234063556840/2017/09/170901_000000.sbd"))
+            .unwrap()
+            .write_all(b"not an sbd message")
+            .unwrap();
+
+        assert!(SbdSource::new(&dir).iter().is_err());
+        let until = Utc.ymd(2017, 8, 31).and_hms(0, 0, 0);
+        let heartbeats = SbdSource::new(&dir)
+            .until(until)
+            .iter()
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(1, heartbeats.len());
+        assert_eq!(
+            Utc.ymd(2017, 8, 25).and_hms(15, 1, 6),
+            heartbeats[0].as_ref().unwrap().datetime
+        );
+        let heartbeats = SbdSource::new(&dir)
+            .imeis(&["300234063556841"])
+            .until(until)
+            .iter()
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert!(heartbeats.is_empty());
+    }
+
+    #[test]
+    fn latest_as_of() {
+        let source = SbdSource::new("data");
+        let datetime = Utc.ymd(2017, 8, 25).and_hms(15, 1, 6);
+        let latest = |as_of| {
+            source
+                .latest_heartbeat_as_of(as_of)
+                .unwrap()
+                .map(|heartbeat| heartbeat.datetime)
+        };
+        assert_eq!(Some(datetime), latest(datetime + Duration::seconds(1)));
+        assert_eq!(Some(datetime), latest(datetime));
+        assert_eq!(
+            Some(Utc.ymd(2017, 8, 1).and_hms(0, 0, 55)),
+            latest(datetime - Duration::seconds(1))
+        );
+        assert_eq!(None, latest(Utc.ymd(2017, 7, 1).and_hms(0, 0, 0)));
+    }
+
+    #[test]
+    fn rate() {
+        assert_eq!(None, heartbeat_rate(&[]));
+        let heartbeat = SbdSource::new("data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .next()
+            .unwrap();
+        let start = Utc.ymd(2017, 8, 1).and_hms(0, 0, 0);
+        let heartbeats = [0, 3, 9, 6, 15, 16]
+            .iter()
+            .map(|&hours| {
+                let mut heartbeat = heartbeat.clone();
+                heartbeat.datetime = start + Duration::hours(hours);
+                heartbeat
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(Some(Duration::hours(3)), heartbeat_rate(&heartbeats));
+        assert_eq!(None, heartbeat_rate(&heartbeats[..1]));
+        assert_eq!(
+            Some(Duration::hours(6)),
+            heartbeat_rate(&[heartbeats[1].clone(), heartbeats[2].clone()])
+        );
+    }
+
     #[test]
     fn heartbeat_parsing() {
         let read_sbd = SbdSource::new("data").iter().unwrap();
@@ -252,6 +1497,12 @@ mod tests {
         assert_eq!(Utc.ymd(2017, 8, 1).and_hms(0, 0, 55), heartbeat.datetime);
         assert_eq!(94.208, heartbeat.batteries[&1].state_of_charge);
         assert_eq!(94.947, heartbeat.batteries[&2].state_of_charge);
+        assert_eq!(
+            Some(&heartbeat.batteries[&2]),
+            heartbeat.battery_slot(1)
+        );
+        assert_eq!(None, heartbeat.battery_slot(2));
+        assert_eq!(None, heartbeat.battery_slot(1000));
         assert_eq!(
             Utc.ymd(2017, 7, 31).and_hms(18, 1, 52),
             heartbeat.scan_start
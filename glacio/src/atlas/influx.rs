@@ -0,0 +1,185 @@
+//! Export heartbeats to the InfluxDB line protocol.
+//!
+//! Each heartbeat becomes several lines, one per measurement:
+//!
+//! - `atlas_battery`, one line per battery, tagged with the battery id.
+//! - `atlas_efoy`, one line per EFOY, tagged with the EFOY id and the active cartridge. The
+//!   cartridge tag is left out if the heartbeat doesn't name one, since tag values can't be empty.
+//! - `atlas_scanner`, the scanner power on information and the last completed scan.
+//! - `atlas_system`, the heartbeat version and the state of the Riegl switch.
+//!
+//! Every line is tagged with the site and timestamped with the heartbeat's datetime, in
+//! nanoseconds.
+//!
+//! # Examples
+//!
+//! ```
+//! use glacio::atlas::{SbdSource, influx};
+//! let heartbeat = SbdSource::new("data")
+//!     .iter()
+//!     .unwrap()
+//!     .filter_map(|result| result.ok())
+//!     .next()
+//!     .unwrap();
+//! let lines = influx::to_line_protocol(&heartbeat, "helheim");
+//! assert!(lines.starts_with("atlas_battery,site=helheim,battery=1 soc=94.208 "));
+//! ```
+
+use atlas::Heartbeat;
+use chrono::{DateTime, Utc};
+
+/// Returns the heartbeat as InfluxDB line protocol, one measurement per line.
+///
+/// The returned string ends with a newline, so the output for several heartbeats can simply be
+/// concatenated.
+pub fn to_line_protocol(heartbeat: &Heartbeat, site: &str) -> String {
+    let timestamp = timestamp(&heartbeat.datetime);
+    let site = escape_tag(site);
+    let mut lines = String::new();
+    for (id, battery) in &heartbeat.batteries {
+        lines.push_str(&format!(
+            "atlas_battery,site={},battery={} soc={} {}\n",
+            site,
+            id,
+            battery.state_of_charge,
+            timestamp
+        ));
+    }
+    for (id, efoy) in &heartbeat.efoys {
+        let cartridge = if efoy.cartridge.is_empty() {
+            String::new()
+        } else {
+            format!(",cartridge={}", escape_tag(&efoy.cartridge))
+        };
+        lines.push_str(&format!(
+            "atlas_efoy,site={},efoy={}{} state={},consumed={},voltage={},current={} {}\n",
+            site,
+            id,
+            cartridge,
+            string_field(&String::from(efoy.state)),
+            efoy.consumed,
+            efoy.voltage,
+            efoy.current,
+            timestamp
+        ));
+    }
+    let power_on = &heartbeat.scanner_power_on;
+    let scan_stop = &heartbeat.scan_stop;
+    lines.push_str(&format!(
+        "atlas_scanner,site={} voltage={},temperature={},memory_external={},memory_internal={},\
+         num_points={}i,range_min={},range_max={},file_size={},roll={},pitch={} {}\n",
+        site,
+        power_on.voltage,
+        power_on.temperature,
+        power_on.memory_external,
+        power_on.memory_internal,
+        scan_stop.num_points,
+        scan_stop.range_min,
+        scan_stop.range_max,
+        scan_stop.file_size,
+        scan_stop.roll,
+        scan_stop.pitch,
+        timestamp
+    ));
+    lines.push_str(&format!(
+        "atlas_system,site={} version={}i,riegl_switch_on={} {}\n",
+        site,
+        heartbeat.version,
+        heartbeat.is_riegl_switch_on,
+        timestamp
+    ));
+    lines
+}
+
+fn timestamp(datetime: &DateTime<Utc>) -> i64 {
+    datetime.timestamp() * 1_000_000_000 + i64::from(datetime.timestamp_subsec_nanos())
+}
+
+/// Escapes commas, equals signs, and spaces in a tag key or value.
+fn escape_tag(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == ',' || c == '=' || c == ' ' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Quotes a string field value, escaping double quotes and backslashes.
+fn string_field(s: &str) -> String {
+    let mut field = String::with_capacity(s.len() + 2);
+    field.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            field.push('\\');
+        }
+        field.push(c);
+    }
+    field.push('"');
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas::SbdSource;
+    use chrono::TimeZone;
+
+    #[test]
+    fn escaping() {
+        assert_eq!("north", escape_tag("north"));
+        assert_eq!(r"north\ shore", escape_tag("north shore"));
+        assert_eq!(r"a\,b\=c", escape_tag("a,b=c"));
+        assert_eq!(r#""auto off""#, string_field("auto off"));
+        assert_eq!(r#""say \"hi\" \\o/""#, string_field(r#"say "hi" \o/"#));
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(0, timestamp(&Utc.timestamp(0, 0)));
+        assert_eq!(
+            1_501_545_655_000_000_001,
+            timestamp(&Utc.timestamp(1_501_545_655, 1))
+        );
+    }
+
+    #[test]
+    fn heartbeat() {
+        let heartbeat = SbdSource::new("data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .next()
+            .unwrap();
+        let lines = to_line_protocol(&heartbeat, "north shore");
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(6, lines.len());
+        assert_eq!(
+            r"atlas_battery,site=north\ shore,battery=1 soc=94.208 1501545655000000000",
+            lines[0]
+        );
+        assert!(lines[2].starts_with(
+            r#"atlas_efoy,site=north\ shore,efoy=1,cartridge=1.1 state="auto off","#,
+        ));
+        assert!(lines[4].contains("num_points=19512617i"));
+        assert!(lines[5].ends_with(" 1501545655000000000"));
+    }
+
+    #[test]
+    fn empty_cartridge() {
+        let mut heartbeat = SbdSource::new("data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .next()
+            .unwrap();
+        heartbeat.efoys.get_mut(&1).unwrap().cartridge = String::new();
+        let lines = to_line_protocol(&heartbeat, "helheim");
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert!(lines[2].starts_with(
+            r#"atlas_efoy,site=helheim,efoy=1 state="auto off","#,
+        ));
+    }
+}
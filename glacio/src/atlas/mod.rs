@@ -39,19 +39,28 @@
 //! undoubtedly update the heartbeat format and use the same format for both systems. This module
 //! will require an update to handle the new heartbeat version.
 
+pub mod annotate;
 pub mod battery;
 pub mod efoy;
+pub mod export;
+pub mod geojson;
+pub mod influx;
+pub mod notify;
 pub mod scanner;
+#[cfg(feature = "stream")]
+pub mod stream;
 
 mod heartbeat;
 
 pub use self::efoy::Efoy;
-pub use self::heartbeat::{Heartbeat, ReadSbd, SbdSource};
+pub use self::heartbeat::{BadHeartbeat, Format, Heartbeat, MessageStatus, MomsnGap, ReadSbd,
+                          SbdSource, TestMessage, heartbeat_rate, latest_heartbeat, session_ok};
 use chrono::ParseError;
 use sbd;
-use std::{error, result};
+use std::{error, io, result};
 use std::fmt::{self, Display, Formatter};
 use std::num::{ParseFloatError, ParseIntError};
+use std::str::Utf8Error;
 use sutron;
 
 /// A custom error enum for ATLAS issues.
@@ -69,6 +78,19 @@ pub enum Error {
     EfoyHeartbeatFormat(String),
     /// The format of the heartbeat message could not be recognized.
     HeartbeatFormat(String),
+    /// The magic number of a heartbeat format isn't four ASCII letters.
+    InvalidMagicNumber(String),
+    /// Wrapper around `std::io::Error`.
+    Io(io::Error),
+    /// The heartbeat starts with a different magic number than its format expects.
+    MagicNumber {
+        /// The magic number of the format.
+        expected: String,
+        /// The magic number at the start of the heartbeat.
+        found: String,
+    },
+    /// A notifier could not deliver its notification.
+    Notify(String),
     /// Wrapper around `std::num::ParseFloatError`.
     ParseFloat(ParseFloatError),
     /// Wrapper around `std::num::ParseIntError`.
@@ -83,6 +105,8 @@ pub enum Error {
     SutronMessage(sutron::message::Error),
     /// The efoy state, as reported, is not recognized.
     UnknownEfoyState(String),
+    /// Wrapper around `std::str::Utf8Error`, for SBD payloads that aren't text.
+    Utf8(Utf8Error),
 }
 
 /// A custom result type for ATLAS.
@@ -118,6 +142,18 @@ impl From<sutron::message::Error> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<Utf8Error> for Error {
+    fn from(err: Utf8Error) -> Error {
+        Error::Utf8(err)
+    }
+}
+
 impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
@@ -129,6 +165,10 @@ impl error::Error for Error {
             Error::EmptyCartridge(_) => "the cartridge is already empty, cannot empty it again",
             Error::EfoyHeartbeatFormat(_) => "the format of this efoy heartbeat message is invalid",
             Error::HeartbeatFormat(_) => "the format of this heartbeat message is invalid",
+            Error::InvalidMagicNumber(_) => "a magic number must be four ASCII letters",
+            Error::Io(ref err) => err.description(),
+            Error::MagicNumber { .. } => "the heartbeat has an unexpected magic number",
+            Error::Notify(_) => "the notification could not be delivered",
             Error::ParseFloat(ref err) => err.description(),
             Error::ParseInt(ref err) => err.description(),
             Error::Sbd(ref err) => err.description(),
@@ -138,16 +178,19 @@ impl error::Error for Error {
             Error::StopScanFormat(_) => "the format of the stop scan message is invalid",
             Error::SutronMessage(ref err) => err.description(),
             Error::UnknownEfoyState(_) => "the efoy state string is not recognized",
+            Error::Utf8(ref err) => err.description(),
         }
     }
 
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             Error::ChronoParse(ref err) => Some(err),
+            Error::Io(ref err) => Some(err),
             Error::ParseFloat(ref err) => Some(err),
             Error::ParseInt(ref err) => Some(err),
             Error::Sbd(ref err) => Some(err),
             Error::SutronMessage(ref err) => Some(err),
+            Error::Utf8(ref err) => Some(err),
             _ => None,
         }
     }
@@ -174,6 +217,15 @@ impl Display for Error {
             }
             Error::EfoyHeartbeatFormat(ref s) => write!(f, "invalid efoy heartbeat format: {}", s),
             Error::HeartbeatFormat(ref s) => write!(f, "invalid heartbeat format: {}", s),
+            Error::InvalidMagicNumber(ref magic) => {
+                write!(f, "invalid magic number {:?}, expected four ASCII letters", magic)
+            }
+            Error::Io(ref err) => err.fmt(f),
+            Error::MagicNumber {
+                ref expected,
+                ref found,
+            } => write!(f, "expected magic number {}, found {}", expected, found),
+            Error::Notify(ref s) => write!(f, "could not notify: {}", s),
             Error::ParseFloat(ref err) => err.fmt(f),
             Error::ParseInt(ref err) => err.fmt(f),
             Error::Sbd(ref err) => err.fmt(f),
@@ -183,6 +235,7 @@ impl Display for Error {
             Error::StopScanFormat(ref s) => write!(f, "invalid stop scan format: {}", s),
             Error::SutronMessage(ref err) => err.fmt(f),
             Error::UnknownEfoyState(ref state) => write!(f, "efoy state {} not recognized", state),
+            Error::Utf8(ref err) => err.fmt(f),
         }
     }
 }
@@ -0,0 +1,388 @@
+//! Notifications for new ATLAS heartbeats.
+//!
+//! A `Notifier` is told about every heartbeat (and every heartbeat that couldn't be parsed) as it
+//! arrives. A `Poller` checks an `SbdSource` for new messages and passes anything it hasn't seen
+//! before on to a notifier.
+//!
+//! # Examples
+//!
+//! ```
+//! use glacio::atlas::{Error, Heartbeat, Result, SbdSource};
+//! use glacio::atlas::notify::{Notifier, Poller};
+//!
+//! struct Print;
+//!
+//! impl Notifier for Print {
+//!     fn on_heartbeat(&mut self, heartbeat: &Heartbeat) -> Result<()> {
+//!         println!("New heartbeat: {}", heartbeat.datetime);
+//!         Ok(())
+//!     }
+//!
+//!     fn on_bad_heartbeat(&mut self, error: &Error) -> Result<()> {
+//!         println!("Bad heartbeat: {}", error);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut poller = Poller::new(SbdSource::new("data"));
+//! poller.poll(&mut Print).unwrap();
+//! ```
+
+use atlas::{Error, Heartbeat, Result, SbdSource};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+
+/// Something that wants to know when heartbeats arrive.
+pub trait Notifier {
+    /// Called with each new heartbeat.
+    fn on_heartbeat(&mut self, heartbeat: &Heartbeat) -> Result<()>;
+
+    /// Called with the error for each new heartbeat that could not be parsed.
+    fn on_bad_heartbeat(&mut self, error: &Error) -> Result<()>;
+
+    /// Dispatches a heartbeat result to `on_heartbeat` or `on_bad_heartbeat`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::{Error, Heartbeat, Result};
+    /// # use glacio::atlas::notify::Notifier;
+    /// struct Count(usize);
+    /// impl Notifier for Count {
+    ///     fn on_heartbeat(&mut self, _: &Heartbeat) -> Result<()> {
+    ///         Ok(())
+    ///     }
+    ///     fn on_bad_heartbeat(&mut self, _: &Error) -> Result<()> {
+    ///         self.0 += 1;
+    ///         Ok(())
+    ///     }
+    /// }
+    /// let mut count = Count(0);
+    /// count.notify(&Err(Error::HeartbeatFormat("ATHB".to_string()))).unwrap();
+    /// assert_eq!(1, count.0);
+    /// ```
+    fn notify(&mut self, result: &Result<Heartbeat>) -> Result<()> {
+        match *result {
+            Ok(ref heartbeat) => self.on_heartbeat(heartbeat),
+            Err(ref err) => self.on_bad_heartbeat(err),
+        }
+    }
+}
+
+/// Polls an `SbdSource` for heartbeats that haven't been seen yet.
+///
+/// The poller keeps a high-water mark, the time of session of the last SBD message that was
+/// turned into a heartbeat (or an error). On each poll, only heartbeats whose messages are newer
+/// than the high-water mark are passed to the notifier. Messages at the end of the source that
+/// don't yet make a complete heartbeat don't move the mark, so they'll be picked up once the rest
+/// of their packets arrive.
+///
+/// Two SBD messages can have the same time of session, so the poller also remembers the IMEI and
+/// MOMSN of each message it has passed on at the high-water mark. A heartbeat that ends at the
+/// mark is only skipped if it's one of those.
+#[derive(Debug)]
+pub struct Poller {
+    source: SbdSource,
+    high_water_mark: Option<DateTime<Utc>>,
+    at_high_water_mark: Vec<(String, u16)>,
+}
+
+impl Poller {
+    /// Creates a new poller for the source.
+    ///
+    /// The first poll will notify on every heartbeat in the source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::SbdSource;
+    /// # use glacio::atlas::notify::Poller;
+    /// let poller = Poller::new(SbdSource::new("data"));
+    /// assert_eq!(None, poller.high_water_mark());
+    /// ```
+    pub fn new(source: SbdSource) -> Poller {
+        Poller {
+            source: source,
+            high_water_mark: None,
+            at_high_water_mark: Vec::new(),
+        }
+    }
+
+    /// Sets the high-water mark, e.g. to skip heartbeats that arrived before the poller started.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::Utc;
+    /// # use glacio::atlas::SbdSource;
+    /// # use glacio::atlas::notify::Poller;
+    /// # fn main() {
+    /// let now = Utc::now();
+    /// let poller = Poller::new(SbdSource::new("data")).since(now);
+    /// assert_eq!(Some(now), poller.high_water_mark());
+    /// # }
+    /// ```
+    pub fn since(mut self, datetime: DateTime<Utc>) -> Poller {
+        self.high_water_mark = Some(datetime);
+        self.at_high_water_mark.clear();
+        self
+    }
+
+    /// Returns the time of session of the last message that this poller has notified on.
+    pub fn high_water_mark(&self) -> Option<DateTime<Utc>> {
+        self.high_water_mark
+    }
+
+    /// Reads the source and passes every new heartbeat result to the notifier.
+    ///
+    /// Returns the number of results that were passed on. Errors opening the source or returned
+    /// by the notifier stop the poll; the high-water mark includes every result that was
+    /// successfully passed on before that happened.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::{Error, Heartbeat, Result, SbdSource};
+    /// # use glacio::atlas::notify::{Notifier, Poller};
+    /// # struct Ignore;
+    /// # impl Notifier for Ignore {
+    /// #     fn on_heartbeat(&mut self, _: &Heartbeat) -> Result<()> { Ok(()) }
+    /// #     fn on_bad_heartbeat(&mut self, _: &Error) -> Result<()> { Ok(()) }
+    /// # }
+    /// let mut poller = Poller::new(SbdSource::new("data"));
+    /// assert_eq!(3, poller.poll(&mut Ignore).unwrap());
+    /// assert_eq!(0, poller.poll(&mut Ignore).unwrap());
+    /// ```
+    pub fn poll<N: Notifier + ?Sized>(&mut self, notifier: &mut N) -> Result<usize> {
+        let mut read_sbd = self.source.iter()?;
+        let mut count = 0;
+        while let Some(result) = read_sbd.next() {
+            let datetime = read_sbd.last_time_of_session();
+            let key = read_sbd.last_imei_and_momsn().map(|(imei, momsn)| {
+                (imei.to_string(), momsn)
+            });
+            let seen = match datetime.cmp(&self.high_water_mark) {
+                Ordering::Less => true,
+                Ordering::Equal => {
+                    key.as_ref().map_or(true, |key| self.at_high_water_mark.contains(key))
+                }
+                Ordering::Greater => false,
+            };
+            if seen {
+                continue;
+            }
+            notifier.notify(&result)?;
+            if datetime > self.high_water_mark {
+                self.high_water_mark = datetime;
+                self.at_high_water_mark.clear();
+            }
+            self.at_high_water_mark.extend(key);
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(feature = "http")]
+pub use self::webhook::WebhookNotifier;
+
+#[cfg(feature = "http")]
+mod webhook {
+    use super::Notifier;
+    use atlas::{Error, Heartbeat, Result};
+    use hyper::Client;
+    use hyper::header::{ContentType, Headers};
+    use serde_json;
+
+    /// The header that carries the webhook's shared secret.
+    const SECRET_HEADER: &'static str = "X-Glacio-Secret";
+
+    /// Posts heartbeats, as JSON, to a url.
+    ///
+    /// Bad heartbeats are posted as a JSON object with a single `error` field. If a secret is
+    /// configured, it is sent in the `X-Glacio-Secret` header so the receiver can check where the
+    /// post came from.
+    #[derive(Debug)]
+    pub struct WebhookNotifier {
+        url: String,
+        secret: Option<String>,
+    }
+
+    impl WebhookNotifier {
+        /// Creates a new webhook notifier for the url.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use glacio::atlas::notify::WebhookNotifier;
+        /// let notifier = WebhookNotifier::new("http://localhost:8000/heartbeats");
+        /// ```
+        pub fn new(url: &str) -> WebhookNotifier {
+            WebhookNotifier {
+                url: url.to_string(),
+                secret: None,
+            }
+        }
+
+        /// Sets the shared secret that is sent with each post.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use glacio::atlas::notify::WebhookNotifier;
+        /// let notifier = WebhookNotifier::new("http://localhost:8000/heartbeats")
+        ///     .secret("correct horse battery staple");
+        /// ```
+        pub fn secret(mut self, secret: &str) -> WebhookNotifier {
+            self.secret = Some(secret.to_string());
+            self
+        }
+
+        fn post(&self, body: &str) -> Result<()> {
+            let mut headers = Headers::new();
+            headers.set(ContentType::json());
+            if let Some(ref secret) = self.secret {
+                headers.set_raw(SECRET_HEADER, vec![secret.clone().into_bytes()]);
+            }
+            let response = Client::new()
+                .post(&self.url)
+                .headers(headers)
+                .body(body)
+                .send()
+                .map_err(|err| Error::Notify(err.to_string()))?;
+            if response.status.is_success() {
+                Ok(())
+            } else {
+                Err(Error::Notify(
+                    format!("{} responded with {}", self.url, response.status),
+                ))
+            }
+        }
+    }
+
+    impl Notifier for WebhookNotifier {
+        fn on_heartbeat(&mut self, heartbeat: &Heartbeat) -> Result<()> {
+            let body = serde_json::to_string(heartbeat).map_err(|err| {
+                Error::Notify(err.to_string())
+            })?;
+            self.post(&body)
+        }
+
+        fn on_bad_heartbeat(&mut self, error: &Error) -> Result<()> {
+            let body = json!({ "error": error.to_string() }).to_string();
+            self.post(&body)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[derive(Debug, Default)]
+    struct Record {
+        heartbeats: Vec<DateTime<Utc>>,
+        errors: usize,
+    }
+
+    impl Notifier for Record {
+        fn on_heartbeat(&mut self, heartbeat: &Heartbeat) -> Result<()> {
+            self.heartbeats.push(heartbeat.datetime);
+            Ok(())
+        }
+
+        fn on_bad_heartbeat(&mut self, _: &Error) -> Result<()> {
+            self.errors += 1;
+            Ok(())
+        }
+    }
+
+    struct Fail;
+
+    impl Notifier for Fail {
+        fn on_heartbeat(&mut self, _: &Heartbeat) -> Result<()> {
+            Err(Error::Notify("nobody is listening".to_string()))
+        }
+
+        fn on_bad_heartbeat(&mut self, _: &Error) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn dispatch() {
+        let mut record = Record::default();
+        let mut poller = Poller::new(SbdSource::new("data"));
+        assert_eq!(3, poller.poll(&mut record).unwrap());
+        assert_eq!(1, record.errors);
+        assert_eq!(
+            vec![
+                Utc.ymd(2017, 8, 1).and_hms(0, 0, 55),
+                Utc.ymd(2017, 8, 25).and_hms(15, 1, 6),
+            ],
+            record.heartbeats
+        );
+    }
+
+    #[test]
+    fn high_water_mark() {
+        let mut record = Record::default();
+        let mut poller = Poller::new(SbdSource::new("data"));
+        poller.poll(&mut record).unwrap();
+        assert_eq!(
+            Some(Utc.ymd(2017, 8, 25).and_hms(15, 1, 6)),
+            poller.high_water_mark()
+        );
+        assert_eq!(0, poller.poll(&mut record).unwrap());
+        assert_eq!(2, record.heartbeats.len());
+    }
+
+    #[test]
+    fn since() {
+        let mut record = Record::default();
+        let mut poller =
+            Poller::new(SbdSource::new("data")).since(Utc.ymd(2017, 8, 2).and_hms(0, 0, 0));
+        assert_eq!(1, poller.poll(&mut record).unwrap());
+        assert_eq!(
+            vec![Utc.ymd(2017, 8, 25).and_hms(15, 1, 6)],
+            record.heartbeats
+        );
+    }
+
+    #[test]
+    fn same_time_of_session() {
+        use test_util::{self, TempDir};
+
+        let dir = TempDir::new("poller-same-time-of-session").unwrap();
+        let subpath = "300234063556840/2017/08/170825_150106.sbd";
+        test_util::copy_sbd(&dir, subpath, |_| {}).unwrap();
+        let mut record = Record::default();
+        let mut poller = Poller::new(SbdSource::new(&dir));
+        assert_eq!(1, poller.poll(&mut record).unwrap());
+
+        // Another heartbeat arrives with the same time of session but the next MOMSN.
+        test_util::copy_sbd(&dir.join("next"), subpath, |bytes| bytes[27] += 1).unwrap();
+        assert_eq!(1, poller.poll(&mut record).unwrap());
+        assert_eq!(0, poller.poll(&mut record).unwrap());
+        assert_eq!(2, record.heartbeats.len());
+        assert_eq!(
+            Some(Utc.ymd(2017, 8, 25).and_hms(15, 1, 6)),
+            poller.high_water_mark()
+        );
+    }
+
+    #[test]
+    fn notifier_error_stops_poll() {
+        let mut poller = Poller::new(SbdSource::new("data"));
+        assert!(poller.poll(&mut Fail).is_err());
+        // The forced transmission was passed on before the failure.
+        let mark = poller.high_water_mark().unwrap();
+        let mut record = Record::default();
+        poller.poll(&mut record).unwrap();
+        assert!(record.heartbeats.iter().all(|&datetime| datetime > mark));
+        assert_eq!(2, record.heartbeats.len());
+    }
+}
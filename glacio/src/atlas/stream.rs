@@ -0,0 +1,364 @@
+//! Read heartbeats from a stream of SBD messages, as they arrive.
+//!
+//! `SbdSource` reads heartbeats out of SBD messages that are already on the filesystem. When SBD
+//! messages come from somewhere else, e.g. a DirectIP receiver that pushes each message down a
+//! channel, a `Stream` pieces together each IMEI's messages as they arrive and yields every
+//! heartbeat as soon as its last message comes in.
+//!
+//! This module is only built with the `stream` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate glacio;
+//! # extern crate sbd;
+//! # fn main() {
+//! use glacio::atlas::stream::Stream;
+//! use sbd::storage::{FilesystemStorage, Storage};
+//! use std::sync::mpsc;
+//!
+//! let (sender, receiver) = mpsc::channel();
+//! for message in FilesystemStorage::open("data").unwrap().messages().unwrap() {
+//!     sender.send(message).unwrap();
+//! }
+//! drop(sender);
+//! for (imei, result) in Stream::new(receiver) {
+//!     match result {
+//!         Ok(heartbeat) => println!("{}: {}", imei, heartbeat.datetime),
+//!         Err(err) => println!("{}: {}", imei, err),
+//!     }
+//! }
+//! # }
+//! ```
+
+use atlas::{Format, Heartbeat, Result};
+use chrono::{DateTime, Utc};
+use sbd::mo::Message;
+use std::collections::BTreeMap;
+use std::str;
+use sutron;
+
+/// An iterator that turns a stream of SBD messages into heartbeats.
+///
+/// Each IMEI gets its own partial message, so packets from two modems can be interleaved. Items
+/// are the IMEI of the modem that sent the heartbeat, along with the heartbeat or the error
+/// encountered while putting it together. Messages are assumed to arrive in order. A heartbeat
+/// that is missing its last message is never yielded, it just waits.
+///
+/// A message whose payload isn't UTF-8 is yielded as an error, and the IMEI's partial heartbeat
+/// is thrown away, since it can't be completed without that message.
+///
+/// Packets with a type byte that is reserved by Sutron can't be used, so they are yielded as
+/// errors like any other bad packet. Because a reserved type means the data logger is doing
+/// something we don't expect, the stream also remembers every reserved type byte that it has
+/// seen, see `Stream::reserved_types`.
+///
+/// If the packet that completed an IMEI's last heartbeat arrives again, the repeat is dropped
+/// rather than yielded as an error.
+#[derive(Debug)]
+pub struct Stream<I> {
+    iter: I,
+    versions: Vec<u8>,
+    format: Format,
+    messages: BTreeMap<String, (sutron::Message, DateTime<Utc>)>,
+    last_complete: BTreeMap<String, (u8, String)>,
+    reserved_types: Vec<u8>,
+}
+
+impl<I: Iterator<Item = Message>> Stream<I> {
+    /// Creates a new stream from anything that produces SBD messages, e.g. a channel receiver.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::stream::Stream;
+    /// use std::sync::mpsc;
+    /// let (_, receiver) = mpsc::channel();
+    /// let stream = Stream::new(receiver);
+    /// ```
+    pub fn new<T>(messages: T) -> Stream<I>
+    where
+        T: IntoIterator<IntoIter = I, Item = Message>,
+    {
+        Stream {
+            iter: messages.into_iter(),
+            versions: Vec::new(),
+            format: Format::default(),
+            messages: BTreeMap::new(),
+            last_complete: BTreeMap::new(),
+            reserved_types: Vec::new(),
+        }
+    }
+
+    /// Sets (or clears) the heartbeat versions to be returned.
+    ///
+    /// If the slice is empty, clears the versions filter. Just like `SbdSource::versions`, errors
+    /// are always returned, since we don't know what version they would have been.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::stream::Stream;
+    /// use std::sync::mpsc;
+    /// let (_, receiver) = mpsc::channel();
+    /// let stream = Stream::new(receiver).versions(&[3]);
+    /// ```
+    pub fn versions(mut self, versions: &[u8]) -> Stream<I> {
+        self.versions = versions.to_vec();
+        self
+    }
+
+    /// Sets the heartbeat format, just like `SbdSource::format`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::Format;
+    /// # use glacio::atlas::stream::Stream;
+    /// use std::sync::mpsc;
+    /// let (_, receiver) = mpsc::channel();
+    /// let stream = Stream::new(receiver).format(Format::new("AMHB").unwrap());
+    /// ```
+    pub fn format(mut self, format: Format) -> Stream<I> {
+        self.format = format;
+        self
+    }
+
+    /// Returns the IMEIs that have a partial heartbeat, waiting on more messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::stream::Stream;
+    /// use std::sync::mpsc;
+    /// let (_, receiver) = mpsc::channel();
+    /// let stream = Stream::new(receiver);
+    /// assert!(stream.pending().is_empty());
+    /// ```
+    pub fn pending(&self) -> Vec<&str> {
+        self.messages.keys().map(|imei| imei.as_str()).collect()
+    }
+
+    /// Returns the reserved packet type bytes that this stream has seen, in the order they were
+    /// first seen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::atlas::stream::Stream;
+    /// use std::sync::mpsc;
+    /// let (_, receiver) = mpsc::channel();
+    /// let stream = Stream::new(receiver);
+    /// assert!(stream.reserved_types().is_empty());
+    /// ```
+    pub fn reserved_types(&self) -> &[u8] {
+        &self.reserved_types
+    }
+
+    fn record_reserved_type(&mut self, err: &sutron::message::Error) {
+        use sutron::message::{Error, Type};
+
+        if let Error::UnsupportedPacketType(ref s) = *err {
+            if let Some(Type::Reserved(n)) = s.bytes().next().map(Type::from) {
+                if !self.reserved_types.contains(&n) {
+                    self.reserved_types.push(n);
+                }
+            }
+        }
+    }
+
+    fn is_duplicate_of_last_complete(&self, imei: &str, payload: &str) -> bool {
+        if let Some(&(id, ref data)) = self.last_complete.get(imei) {
+            if let Ok(packet) = payload.parse::<sutron::message::Packet>() {
+                if packet.is_duplicate_of(id, data) {
+                    debug!("dropping a repeat of the last packet of {}'s message {}", imei, id);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl<I: Iterator<Item = Message>> Iterator for Stream<I> {
+    type Item = (String, Result<Heartbeat>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(sbd_message) = self.iter.next() {
+            let imei = sbd_message.imei().to_string();
+            let (message, datetime) = self.messages.remove(&imei).unwrap_or_else(|| {
+                (sutron::Message::new(), sbd_message.time_of_session())
+            });
+            let payload = match str::from_utf8(sbd_message.payload_ref()) {
+                Ok(payload) => payload,
+                Err(err) => return Some((imei, Err(err.into()))),
+            };
+            if message.packets() == Some(0) && self.is_duplicate_of_last_complete(&imei, payload) {
+                continue;
+            }
+            let id = message.id();
+            match message.add(payload) {
+                Ok(message) => {
+                    if message.is_complete() {
+                        let data = String::from(message);
+                        if let Some(id) = id {
+                            self.last_complete.insert(imei.clone(), (id, data.clone()));
+                        } else {
+                            self.last_complete.remove(&imei);
+                        }
+                        let heartbeat = Heartbeat::new_with_format(&data, datetime, &self.format);
+                        if let Ok(ref heartbeat) = heartbeat {
+                            if !self.versions.is_empty() &&
+                                !self.versions.contains(&heartbeat.version)
+                            {
+                                continue;
+                            }
+                        }
+                        return Some((imei, heartbeat));
+                    } else {
+                        self.messages.insert(imei, (message, datetime));
+                    }
+                }
+                Err(err) => {
+                    self.record_reserved_type(&err);
+                    return Some((imei, Err(err.into())));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atlas::Error;
+    use chrono::TimeZone;
+    use sbd::storage::{FilesystemStorage, Storage};
+    use std::sync::mpsc;
+    use std::thread;
+
+    fn messages() -> Vec<Message> {
+        let mut messages = FilesystemStorage::open("data").unwrap().messages().unwrap();
+        messages.sort_by(|a, b| a.time_of_session().cmp(&b.time_of_session()));
+        messages
+    }
+
+    #[test]
+    fn channel() {
+        let (sender, receiver) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            for message in messages() {
+                sender.send(message).unwrap();
+            }
+        });
+        let results = Stream::new(receiver).collect::<Vec<_>>();
+        handle.join().unwrap();
+        assert_eq!(3, results.len());
+        assert!(results.iter().all(|&(ref imei, _)| imei == "300234063556840"));
+        assert!(results[0].1.is_err());
+        assert_eq!(
+            Utc.ymd(2017, 8, 1).and_hms(0, 0, 55),
+            results[1].1.as_ref().unwrap().datetime
+        );
+        assert_eq!(
+            Utc.ymd(2017, 8, 25).and_hms(15, 1, 6),
+            results[2].1.as_ref().unwrap().datetime
+        );
+    }
+
+    #[test]
+    fn same_as_sbd_source() {
+        use atlas::SbdSource;
+
+        let expected = SbdSource::new("data")
+            .iter()
+            .unwrap()
+            .filter_map(|result| result.ok())
+            .collect::<Vec<_>>();
+        let actual = Stream::new(messages())
+            .filter_map(|(_, result)| result.ok())
+            .collect::<Vec<_>>();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn partial_heartbeat_waits() {
+        let messages = messages();
+        let mut stream = Stream::new(messages.into_iter().take(2));
+        assert!(stream.next().unwrap().1.is_err());
+        assert!(stream.next().is_none());
+        assert_eq!(vec!["300234063556840"], stream.pending());
+    }
+
+    #[test]
+    fn duplicate_final_packet() {
+        let mut messages = messages();
+        // The second packet of the 2017-08-01 heartbeat, sent twice.
+        messages.insert(3, self::messages().remove(2));
+        let results = Stream::new(messages).collect::<Vec<_>>();
+        assert_eq!(3, results.len());
+        assert_eq!(
+            Utc.ymd(2017, 8, 1).and_hms(0, 0, 55),
+            results[1].1.as_ref().unwrap().datetime
+        );
+        assert!(results[2].1.is_ok());
+    }
+
+    #[test]
+    fn reserved_types() {
+        use test_util::{self, TempDir};
+
+        let dir = TempDir::new("stream-reserved-types").unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2016/07/160719_193136.sbd", |bytes| {
+            let n = bytes.len();
+            assert_eq!(b"8test", &bytes[n - 5..]);
+            bytes[n - 5] = b'~';
+        }).unwrap();
+
+        let messages = FilesystemStorage::open(&dir).unwrap().messages().unwrap();
+        let mut stream = Stream::new(messages);
+        assert!(stream.next().unwrap().1.is_err());
+        assert_eq!(&[b'~'], stream.reserved_types());
+    }
+
+    #[test]
+    fn non_utf8_payload() {
+        use test_util::{self, TempDir};
+
+        let dir = TempDir::new("stream-non-utf8-payload").unwrap();
+        test_util::copy_sbd(&dir, "300234063556840/2016/07/160719_193136.sbd", |bytes| {
+            let n = bytes.len();
+            bytes[n - 1] = 0xff;
+        }).unwrap();
+
+        let mut messages = FilesystemStorage::open(&dir).unwrap().messages().unwrap();
+        messages.extend(self::messages());
+        let results = Stream::new(messages).collect::<Vec<_>>();
+        match results[0].1 {
+            Err(Error::Utf8(_)) => {}
+            ref result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(
+            Utc.ymd(2017, 8, 25).and_hms(15, 1, 6),
+            results.last().unwrap().1.as_ref().unwrap().datetime
+        );
+    }
+
+    #[test]
+    fn format() {
+        let results = Stream::new(messages())
+            .format(Format::new("AMHB").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(3, results.len());
+        assert!(results.iter().all(|&(_, ref result)| result.is_err()));
+    }
+
+    #[test]
+    fn versions() {
+        let stream = Stream::new(messages()).versions(&[2]);
+        let results = stream.collect::<Vec<_>>();
+        assert_eq!(1, results.len());
+        assert!(results[0].1.is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Build camera directories full of empty, correctly-named images.
+//!
+//! Tests of pagination and gaps need hundreds of images, and committing a fixture tree that big
+//! isn't worth it. Nothing here reads the images' contents, so zero-byte files are enough.
+//!
+//! This module is only available in tests, or with the `test-support` feature. Doctests don't
+//! build the library with `cfg(test)`, so the example below isn't run; the unit tests cover it.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! # extern crate chrono;
+//! # extern crate glacio;
+//! # use chrono::{Duration, TimeZone, Utc};
+//! # use glacio::Camera;
+//! # use glacio::camera::fixture::{self, FixtureSpec};
+//! # fn main() {
+//! let dir = std::env::temp_dir().join("glacio-fixture-doctest");
+//! let spec = FixtureSpec::new(Utc.ymd(2017, 8, 6).and_hms(0, 0, 0), Duration::hours(3), 8)
+//!     .camera("ATLAS_CAM");
+//! fixture::build(&dir, &spec).unwrap();
+//! let camera = Camera::new(dir.join("ATLAS_CAM")).unwrap();
+//! assert_eq!(8, camera.images().unwrap().count());
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! # }
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+/// The cameras and images to create.
+#[derive(Clone, Debug)]
+pub struct FixtureSpec {
+    /// The cameras' directories, relative to the root, e.g. `HEL_BERGCAM3/StarDot1`.
+    ///
+    /// Images are named after the last component of the directory.
+    pub cameras: Vec<String>,
+    /// The datetime of the first image.
+    pub start: DateTime<Utc>,
+    /// The time between images.
+    pub interval: Duration,
+    /// The number of intervals, including the ones that fall in a gap.
+    pub count: usize,
+    /// Windows, from start (inclusive) to end (exclusive), where no images are created.
+    pub gaps: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl FixtureSpec {
+    /// Creates a spec for `count` images, `interval` apart, starting at `start`.
+    ///
+    /// The spec has no cameras until some are added.
+    pub fn new(start: DateTime<Utc>, interval: Duration, count: usize) -> FixtureSpec {
+        FixtureSpec {
+            cameras: Vec::new(),
+            start: start,
+            interval: interval,
+            count: count,
+            gaps: Vec::new(),
+        }
+    }
+
+    /// Adds a camera.
+    pub fn camera(mut self, camera: &str) -> FixtureSpec {
+        self.cameras.push(camera.to_string());
+        self
+    }
+
+    /// Adds a window, from `start` (inclusive) to `end` (exclusive), without any images.
+    pub fn gap(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> FixtureSpec {
+        self.gaps.push((start, end));
+        self
+    }
+
+    /// Returns the datetimes of the images that each camera gets, oldest first.
+    pub fn datetimes(&self) -> Vec<DateTime<Utc>> {
+        (0..self.count)
+            .map(|i| self.start + self.interval * i as i32)
+            .filter(|&datetime| {
+                !self.gaps.iter().any(
+                    |&(start, end)| start <= datetime && datetime < end,
+                )
+            })
+            .collect()
+    }
+}
+
+/// Creates an empty file in `dir` for each of the file names.
+///
+/// Useful for images whose names don't follow a `FixtureSpec`, e.g. images from several cameras
+/// in one directory, or files that aren't images at all.
+pub fn touch<S: AsRef<Path>>(dir: &Path, file_names: &[S]) -> io::Result<()> {
+    for file_name in file_names {
+        File::create(dir.join(file_name))?;
+    }
+    Ok(())
+}
+
+/// Creates the spec's cameras under `dir`, and an empty image for each of their datetimes.
+///
+/// Directories are created as needed, and existing files are truncated. Returns the number of
+/// images created.
+pub fn build(dir: &Path, spec: &FixtureSpec) -> io::Result<usize> {
+    let datetimes = spec.datetimes();
+    for camera in &spec.cameras {
+        let camera_dir = dir.join(camera);
+        fs::create_dir_all(&camera_dir)?;
+        let name = camera.rsplit('/').next().unwrap_or(camera);
+        for datetime in &datetimes {
+            let file_name = format!("{}_{}.jpg", name, datetime.format("%Y%m%d_%H%M%S"));
+            File::create(camera_dir.join(file_name))?;
+        }
+    }
+    Ok(datetimes.len() * spec.cameras.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camera::Camera;
+    use camera::listing::Listing;
+    use chrono::TimeZone;
+    use test_util::TempDir;
+
+    #[test]
+    fn round_trip() {
+        let dir = TempDir::new("camera-fixture-round-trip").unwrap();
+        let start = Utc.ymd(2017, 8, 6).and_hms(0, 0, 0);
+        let spec = FixtureSpec::new(start, Duration::hours(3), 240)
+            .camera("ATLAS_CAM")
+            .camera("HEL_BERGCAM3/StarDot1")
+            .gap(start + Duration::days(2), start + Duration::days(4));
+        assert_eq!(2 * 224, build(&dir, &spec).unwrap());
+
+        let camera = Camera::new(dir.join("HEL_BERGCAM3/StarDot1")).unwrap();
+        let images = camera.images_desc().unwrap();
+        assert_eq!(224, images.len());
+        assert_eq!(start + Duration::hours(3 * 239), images[0].datetime());
+        assert_eq!(start, images[223].datetime());
+        assert!(images.iter().all(|image| {
+            image.datetime() < start + Duration::days(2) ||
+                image.datetime() >= start + Duration::days(4)
+        }));
+
+        let rows = Listing::new(&dir)
+            .iter()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(2 * 224, rows.len());
+    }
+}
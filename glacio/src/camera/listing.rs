@@ -0,0 +1,323 @@
+//! Flat listings of every image under a root directory.
+//!
+//! Every directory under the root is treated as a camera, keyed by its path relative to the root,
+//! e.g. `HEL_BERGCAM3/StarDot1`. Images are read one directory at a time through
+//! `Camera::images`, so a listing of a big archive doesn't hold every image in memory.
+//!
+//! # Examples
+//!
+//! ```
+//! use glacio::camera::listing::{self, Listing};
+//! println!("{}", listing::CSV_HEADER);
+//! for result in Listing::new("data").camera("ATLAS_CAM").iter().unwrap() {
+//!     println!("{}", result.unwrap().to_csv());
+//! }
+//! ```
+
+use camera::{Camera, Image, Images, Result};
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// The header row for `Row::to_csv`.
+pub const CSV_HEADER: &'static str = "camera,subpath,datetime,size";
+
+/// Configuration for listing the images under a root directory.
+#[derive(Debug)]
+pub struct Listing {
+    root: PathBuf,
+    camera: Option<String>,
+    since: Option<DateTime<Utc>>,
+}
+
+/// An iterator over the rows of a `Listing`.
+///
+/// Problems reading a directory or an image are yielded as `Err` items, and iteration continues.
+#[derive(Debug)]
+pub struct Rows {
+    root: PathBuf,
+    camera: Option<String>,
+    since: Option<DateTime<Utc>>,
+    directories: Vec<PathBuf>,
+    current: Option<(String, Images)>,
+}
+
+/// One image in a listing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Row {
+    /// The camera's key, its directory relative to the root.
+    pub camera: String,
+    /// The image's path relative to the root.
+    pub subpath: String,
+    /// The image's datetime.
+    pub datetime: DateTime<Utc>,
+    /// The size of the image file, in bytes.
+    pub size: u64,
+}
+
+impl Listing {
+    /// Creates a new listing of every image under the root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::camera::listing::Listing;
+    /// let listing = Listing::new("data");
+    /// ```
+    pub fn new<P: AsRef<Path>>(root: P) -> Listing {
+        Listing {
+            root: root.as_ref().to_path_buf(),
+            camera: None,
+            since: None,
+        }
+    }
+
+    /// Only lists the images of the camera with this key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::camera::listing::Listing;
+    /// let listing = Listing::new("data").camera("HEL_BERGCAM3/StarDot1");
+    /// ```
+    pub fn camera(mut self, camera: &str) -> Listing {
+        self.camera = Some(camera.to_string());
+        self
+    }
+
+    /// Only lists images taken at or after this datetime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio::camera::listing::Listing;
+    /// # fn main() {
+    /// let listing = Listing::new("data").since(Utc.ymd(2017, 8, 1).and_hms(0, 0, 0));
+    /// # }
+    /// ```
+    pub fn since(mut self, since: DateTime<Utc>) -> Listing {
+        self.since = Some(since);
+        self
+    }
+
+    /// Returns an iterator over this listing's rows.
+    ///
+    /// Returns an error if the root can't be canonicalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::camera::listing::Listing;
+    /// let rows = Listing::new("data").iter().unwrap();
+    /// assert!(Listing::new("not/a/directory").iter().is_err());
+    /// ```
+    pub fn iter(&self) -> Result<Rows> {
+        let root = self.root.canonicalize()?;
+        Ok(Rows {
+            directories: vec![root.clone()],
+            root: root,
+            camera: self.camera.clone(),
+            since: self.since,
+            current: None,
+        })
+    }
+}
+
+impl Rows {
+    fn key(&self, directory: &Path) -> String {
+        match directory.strip_prefix(&self.root) {
+            Ok(relative) if relative.as_os_str().is_empty() => {
+                self.root
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            }
+            Ok(relative) => relative.to_string_lossy().into_owned(),
+            Err(_) => directory.to_string_lossy().into_owned(),
+        }
+    }
+
+    fn open(&mut self, directory: PathBuf) -> Result<()> {
+        for entry in directory.read_dir()? {
+            let entry = entry?;
+            // Symlinked directories aren't followed, since a cycle would never end.
+            if entry.file_type()?.is_dir() {
+                self.directories.push(entry.path());
+            }
+        }
+        let key = self.key(&directory);
+        if self.camera.as_ref().map_or(true, |camera| *camera == key) {
+            self.current = Some((key, Camera::new(&directory)?.images()?));
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Rows {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Result<Row>> {
+        loop {
+            if let Some((ref key, ref mut images)) = self.current {
+                while let Some(result) = images.next() {
+                    let image = match result {
+                        Ok(image) => image,
+                        Err(err) => return Some(Err(err)),
+                    };
+                    if self.since.map_or(false, |since| image.datetime() < since) {
+                        continue;
+                    }
+                    return Some(Row::new(&self.root, key, &image));
+                }
+            }
+            self.current = None;
+            match self.directories.pop() {
+                Some(directory) => {
+                    if let Err(err) = self.open(directory) {
+                        return Some(Err(err));
+                    }
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+impl Row {
+    fn new(root: &Path, camera: &str, image: &Image) -> Result<Row> {
+        Ok(Row {
+            camera: camera.to_string(),
+            subpath: image.path().strip_prefix(root)?.to_string_lossy().into_owned(),
+            datetime: image.datetime(),
+            size: image.path().metadata()?.len(),
+        })
+    }
+
+    /// Returns this row as a line of CSV, without a trailing newline.
+    ///
+    /// The columns are in the same order as `CSV_HEADER`. Fields with commas, quotes, or newlines
+    /// are quoted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio::camera::listing::Row;
+    /// # fn main() {
+    /// let row = Row {
+    ///     camera: "ATLAS_CAM".to_string(),
+    ///     subpath: "ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg".to_string(),
+    ///     datetime: Utc.ymd(2017, 8, 6).and_hms(15, 25, 0),
+    ///     size: 98265,
+    /// };
+    /// assert_eq!(
+    ///     "ATLAS_CAM,ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg,2017-08-06T15:25:00+00:00,98265",
+    ///     row.to_csv()
+    /// );
+    /// # }
+    /// ```
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            csv_field(&self.camera),
+            csv_field(&self.subpath),
+            self.datetime.to_rfc3339(),
+            self.size
+        )
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn csv() {
+        let mut row = Row {
+            camera: "CAM".to_string(),
+            subpath: "CAM/CAM_20170806_152500.jpg".to_string(),
+            datetime: Utc.ymd(2017, 8, 6).and_hms(15, 25, 0),
+            size: 0,
+        };
+        assert_eq!(
+            "CAM,CAM/CAM_20170806_152500.jpg,2017-08-06T15:25:00+00:00,0",
+            row.to_csv()
+        );
+        row.camera = "CAM, \"the good one\"".to_string();
+        assert_eq!(
+            "\"CAM, \"\"the good one\"\"\",CAM/CAM_20170806_152500.jpg,2017-08-06T15:25:00+00:00,0",
+            row.to_csv()
+        );
+        assert_eq!(4, CSV_HEADER.split(',').count());
+    }
+
+    #[test]
+    fn fixtures() {
+        let (rows, errors): (Vec<_>, Vec<_>) =
+            Listing::new("data").iter().unwrap().partition(|r| r.is_ok());
+        assert_eq!(8, rows.len());
+        assert_eq!(2, errors.len());
+        let rows = rows.into_iter().map(|r| r.unwrap()).collect::<Vec<_>>();
+        let row = rows.iter().find(|row| row.camera == "ATLAS_CAM").unwrap();
+        assert_eq!("ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", row.subpath);
+        assert_eq!(98265, row.size);
+        assert!(rows.iter().any(|row| row.camera == "HEL_BERGCAM3/StarDot1"));
+        assert!(rows.iter().any(|row| row.camera == "NESTED_CAM/2017/08/06"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycle() {
+        use std::fs;
+        use std::os::unix::fs::symlink;
+        use test_util::TempDir;
+
+        let dir = TempDir::new("listing-symlink-cycle").unwrap();
+        fs::create_dir(dir.join("CAM")).unwrap();
+        fs::File::create(dir.join("CAM/CAM_20170806_152500.jpg")).unwrap();
+        symlink(dir.path(), dir.join("CAM/loop")).unwrap();
+        let rows = Listing::new(&dir)
+            .iter()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(1, rows.len());
+        assert_eq!("CAM", rows[0].camera);
+    }
+
+    #[test]
+    fn filters() {
+        let rows = Listing::new("data")
+            .camera("HEL_BERGCAM3/StarDot1")
+            .iter()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(1, rows.len());
+        assert_eq!(
+            "HEL_BERGCAM3/StarDot1/HEL_BERGCAM3_StarDot1_20170825_120000.jpg",
+            rows[0].subpath
+        );
+        let rows = Listing::new("data")
+            .since(Utc.ymd(2017, 8, 7).and_hms(0, 0, 0))
+            .iter()
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect::<Vec<_>>();
+        assert_eq!(1, rows.len());
+        assert_eq!("HEL_BERGCAM3/StarDot1", rows[0].camera);
+    }
+}
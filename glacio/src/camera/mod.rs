@@ -0,0 +1,1197 @@
+//! Remote cameras located all over the world.
+//!
+//! These cameras are installed in remote locations, e.g. Greenland or Alaska. They take pictures
+//! at regular intervals, then send those pictures back to a home server via a satellite
+//! connection. The images are served via HTTP, right now by http://iridiumcam.lidar.io.
+
+use chrono::{self, DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+#[cfg(feature = "image")]
+use image::{self, ImageError};
+use std::{error, io, result};
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::fmt::{self, Display, Formatter};
+use std::fs::ReadDir;
+use std::path::{Path, PathBuf, StripPrefixError};
+use url::{self, Url};
+
+#[cfg(any(test, feature = "test-support"))]
+pub mod fixture;
+pub mod listing;
+pub mod repair;
+
+const DEFAULT_EXTENSIONS: &'static [&'static str] = &["jpg"];
+const DEFAULT_SERVER_BASE_URL: &'static str = "http://iridiumcam.lidar.io";
+
+/// A custom error enum for cameras.
+#[derive(Debug)]
+pub enum Error {
+    /// Wrapper around `chrono::ParseError`.
+    ChronoParse(chrono::ParseError),
+    /// The file stem is too short to parse for a datetime.
+    FileStemTooShort(String),
+    /// Wrapper around `image::ImageError`.
+    #[cfg(feature = "image")]
+    Image(ImageError),
+    /// The file stem mixes `_` and `-` as separators around its datetime.
+    MixedSeparators(String),
+    /// Wrapper around `std::io::Error`.
+    Io(io::Error),
+    /// No file stem for the provided path.
+    NoFileStem(PathBuf),
+    /// The file stem is not valid UTF-8, so we can't parse a datetime from it.
+    NonUtf8FileStem(PathBuf),
+    /// Wrapper around `std::path::StripPrefixError`.
+    StripPrefix(StripPrefixError),
+    /// Wrapper around `url::ParseError`.
+    UrlParse(url::ParseError),
+}
+
+/// Our custom result type.
+pub type Result<T> = result::Result<T, Error>;
+
+/// A path that could not be read as an image, along with the reason why.
+pub type Skipped = (PathBuf, Error);
+
+/// A remote camera, usually used to take pictures of glaciers or other cool stuff.
+#[derive(Debug)]
+pub struct Camera {
+    path: PathBuf,
+    extensions: Vec<OsString>,
+    offset: FixedOffset,
+}
+
+/// An iterator over a camera's images, wrapped in a `Result` in case something goes wrong parsing
+/// the image path.
+///
+/// # Examples
+///
+/// ```
+/// # use glacio::Camera;
+/// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+/// for result in camera.images().unwrap() {
+///     println!("{}", result.unwrap().path().display());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Images {
+    read_dir: ReadDir,
+    extensions: Vec<OsString>,
+    offset: FixedOffset,
+}
+
+/// An image taken by a remote camera and stored on the local filesystem.
+///
+/// Date and time information are assumed to be stored in the image's filename, either as
+/// `NAME_%Y%m%d_%H%M%S` or as `NAME-%Y%m%d-%H%M%S`. The offset that the image was captured in is
+/// stored alongside the datetime, so the image's local time can be recovered.
+///
+/// Images are totally ordered by datetime, and then by path. The api's pagination depends on this
+/// order being stable, so it's implemented by hand rather than derived from the field order.
+#[derive(Debug)]
+pub struct Image {
+    datetime: DateTime<Utc>,
+    offset: FixedOffset,
+    path: PathBuf,
+}
+
+/// An image server, used to translate a local image path to a url.
+#[derive(Debug)]
+pub struct Server {
+    base_url: Url,
+    document_root: PathBuf,
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<StripPrefixError> for Error {
+    fn from(err: StripPrefixError) -> Error {
+        Error::StripPrefix(err)
+    }
+}
+
+impl From<chrono::ParseError> for Error {
+    fn from(err: chrono::ParseError) -> Error {
+        Error::ChronoParse(err)
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<ImageError> for Error {
+    fn from(err: ImageError) -> Error {
+        Error::Image(err)
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(err: url::ParseError) -> Error {
+        Error::UrlParse(err)
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::ChronoParse(ref err) => err.description(),
+            Error::FileStemTooShort(_) => "file stem is too short",
+            #[cfg(feature = "image")]
+            Error::Image(ref err) => err.description(),
+            Error::MixedSeparators(_) => "file stem mixes `_` and `-` separators",
+            Error::Io(ref err) => err.description(),
+            Error::NoFileStem(_) => "no file stem for path",
+            Error::NonUtf8FileStem(_) => "file stem is not valid UTF-8",
+            Error::StripPrefix(ref err) => err.description(),
+            Error::UrlParse(ref err) => err.description(),
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::ChronoParse(ref err) => Some(err),
+            Error::FileStemTooShort(_) => None,
+            #[cfg(feature = "image")]
+            Error::Image(ref err) => Some(err),
+            Error::MixedSeparators(_) => None,
+            Error::Io(ref err) => Some(err),
+            Error::NoFileStem(_) => None,
+            Error::NonUtf8FileStem(_) => None,
+            Error::StripPrefix(ref err) => Some(err),
+            Error::UrlParse(ref err) => Some(err),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::ChronoParse(ref err) => err.fmt(f),
+            Error::FileStemTooShort(ref file_stem) => {
+                write!(
+                    f,
+                    "file stem is too short for datetime parsing: {}",
+                    file_stem
+                )
+            }
+            #[cfg(feature = "image")]
+            Error::Image(ref err) => err.fmt(f),
+            Error::Io(ref err) => err.fmt(f),
+            Error::MixedSeparators(ref file_stem) => {
+                write!(f, "file stem mixes `_` and `-` separators: {}", file_stem)
+            }
+            Error::NoFileStem(ref path) => write!(f, "no file stem for path: {}", path.display()),
+            Error::NonUtf8FileStem(ref path) => {
+                write!(f, "file stem is not valid UTF-8: {}", path.display())
+            }
+            Error::StripPrefix(ref err) => err.fmt(f),
+            Error::UrlParse(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl Camera {
+    /// Creates a new camera whose images are located under the provided path.
+    ///
+    /// The local image path is canonicalized. The path is *not* searched recursively — all images
+    /// must be located directly under the path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// assert_eq!(Path::new("data/ATLAS_CAM").canonicalize().unwrap(), camera.path());
+    /// ```
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Camera> {
+        Camera::with_offset(path, FixedOffset::east(0))
+    }
+
+    /// Creates a new camera whose image filenames are in the provided offset, rather than UTC.
+    ///
+    /// Every image from this camera is created with `Image::with_offset`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{FixedOffset, TimeZone, Utc};
+    /// # use glacio::Camera;
+    /// # fn main() {
+    /// let camera = Camera::with_offset("data/ATLAS_CAM", FixedOffset::west(3 * 3600)).unwrap();
+    /// let image = camera.latest_image().unwrap();
+    /// assert_eq!(Utc.ymd(2017, 8, 6).and_hms(18, 25, 0), image.datetime());
+    /// # }
+    /// ```
+    pub fn with_offset<P: AsRef<Path>>(path: P, offset: FixedOffset) -> Result<Camera> {
+        let path = path.as_ref();
+        Ok(Camera {
+            path: path.canonicalize().map_err(|err| with_path(err, path))?,
+            extensions: DEFAULT_EXTENSIONS.iter().map(|&s| s.into()).collect(),
+            offset: offset,
+        })
+    }
+
+    /// Returns an iterator over this camera's images.
+    ///
+    /// Only a failure to read the camera's directory is returned as an `Err` here. Problems with
+    /// individual entries, whether reading the entry itself or parsing an image from its path, are
+    /// yielded as `Err` items and iteration continues, so callers can skip them with
+    /// `filter_map(|r| r.ok())`. Use `Camera::images_with_errors` to find out which paths were
+    /// skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// let images = camera.images().unwrap().collect::<Vec<_>>();
+    /// ```
+    pub fn images(&self) -> Result<Images> {
+        Ok(Images {
+            read_dir: self.read_dir()?,
+            extensions: self.extensions.clone(),
+            offset: self.offset,
+        })
+    }
+
+    /// Returns this camera's images, along with the paths that could not be read as images.
+    ///
+    /// Unlike `Camera::images()`, a bad directory entry doesn't need to be handled by the caller —
+    /// it is skipped, and its path and error are collected into the second vector. If the entry
+    /// itself can't be read, the camera's path is used. Only a failure to read the camera's
+    /// directory is returned as an `Err`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/MISNAMED_CAM").unwrap();
+    /// let (images, skipped) = camera.images_with_errors().unwrap();
+    /// assert_eq!(1, images.len());
+    /// assert_eq!(1, skipped.len());
+    /// ```
+    pub fn images_with_errors(&self) -> Result<(Vec<Image>, Vec<Skipped>)> {
+        let mut images = Vec::new();
+        let mut skipped = Vec::new();
+        for result in self.read_dir()? {
+            let path = match result {
+                Ok(dir_entry) => dir_entry.path(),
+                Err(err) => {
+                    skipped.push((self.path.clone(), err.into()));
+                    continue;
+                }
+            };
+            if !self.has_image_extension(&path) {
+                continue;
+            }
+            match Image::with_offset(&path, self.offset) {
+                Ok(image) => images.push(image),
+                Err(err) => skipped.push((path, err)),
+            }
+        }
+        Ok((images, skipped))
+    }
+
+    /// Returns this camera's images, including images in subdirectories up to `max_depth` levels
+    /// down, in no particular order.
+    ///
+    /// Some cameras upload into dated subdirectories, e.g. `2017/08/06`, rather than one flat
+    /// directory. A `max_depth` of zero only reads the camera's directory. Images that can't be
+    /// read are skipped, just like `Camera::images_with_errors`. Only a failure to read a
+    /// directory is returned as an `Err`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/NESTED_CAM").unwrap();
+    /// assert_eq!(1, camera.images_recursive(0).unwrap().len());
+    /// assert_eq!(3, camera.images_recursive(3).unwrap().len());
+    /// ```
+    pub fn images_recursive(&self, max_depth: usize) -> Result<Vec<Image>> {
+        let mut images = Vec::new();
+        let mut directories = vec![(self.path.clone(), 0)];
+        while let Some((directory, depth)) = directories.pop() {
+            let read_dir = directory.read_dir().map_err(
+                |err| with_path(err, &directory),
+            )?;
+            for result in read_dir {
+                let (path, is_dir) = match result {
+                    Ok(dir_entry) => {
+                        // Like a listing, don't follow symlinked directories.
+                        let is_dir = dir_entry.file_type().map_or(false, |file_type| {
+                            file_type.is_dir()
+                        });
+                        (dir_entry.path(), is_dir)
+                    }
+                    Err(_) => continue,
+                };
+                if is_dir {
+                    if depth < max_depth {
+                        directories.push((path, depth + 1));
+                    }
+                } else if self.has_image_extension(&path) {
+                    if let Ok(image) = Image::with_offset(&path, self.offset) {
+                        images.push(image);
+                    }
+                }
+            }
+        }
+        Ok(images)
+    }
+
+    /// Returns this camera's images, newest first.
+    ///
+    /// Images that can't be read are skipped, just like `Camera::images_with_errors`. Images are
+    /// sorted in reverse of their `Ord`, i.e. by datetime and then by path, so images with the
+    /// same datetime are in reverse path order and the order is the same every time, on every
+    /// platform.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// let images = camera.images_desc().unwrap();
+    /// assert_eq!(camera.latest_image().as_ref(), images.first());
+    /// ```
+    pub fn images_desc(&self) -> Result<Vec<Image>> {
+        let (mut images, _) = self.images_with_errors()?;
+        images.sort_by(|a, b| b.cmp(a));
+        Ok(images)
+    }
+
+    /// Returns this camera's latest image, or None if there are no images for this camera.
+    ///
+    /// Images are ordered by their time of capture, as determined by their filename. If two images
+    /// have the same datetime, the one with the greater path is the latest.
+    ///
+    /// Any underlying errors in the image iterator are turned into `None`. If you need to see the
+    /// errors, use `Camera::images()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// let image = camera.latest_image().unwrap();
+    /// ```
+    pub fn latest_image(&self) -> Option<Image> {
+        self.latest().unwrap_or(None)
+    }
+
+    /// Returns this camera's latest image, or `None` if there are no images.
+    ///
+    /// Unlike `Camera::latest_image`, a failure to read the camera's directory is returned as an
+    /// `Err`. Images that can't be read are skipped. The images are walked once, and only the
+    /// latest so far is kept in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// let image = camera.latest().unwrap().unwrap();
+    /// assert_eq!(camera.images_desc().unwrap().first(), Some(&image));
+    /// ```
+    pub fn latest(&self) -> Result<Option<Image>> {
+        Ok(self.images()?.filter_map(|r| r.ok()).max())
+    }
+
+    /// Returns this camera's oldest image, or `None` if there are no images.
+    ///
+    /// This is the counterpart to `Camera::latest`. If two images have the same datetime, the one
+    /// with the lesser path is the oldest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// let image = camera.oldest().unwrap().unwrap();
+    /// assert_eq!(camera.images_desc().unwrap().last(), Some(&image));
+    /// ```
+    pub fn oldest(&self) -> Result<Option<Image>> {
+        Ok(self.images()?.filter_map(|r| r.ok()).min())
+    }
+
+    /// Returns the datetimes of this camera's oldest and latest images, or `None` if there are no
+    /// images.
+    ///
+    /// This is how long the camera has been deployed, as far as its images can tell. Like
+    /// `Camera::latest` and `Camera::oldest`, the images are walked once and never collected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// let (first, latest) = camera.deployment_span().unwrap().unwrap();
+    /// assert_eq!(camera.oldest().unwrap().unwrap().datetime(), first);
+    /// assert_eq!(camera.latest().unwrap().unwrap().datetime(), latest);
+    /// ```
+    pub fn deployment_span(&self) -> Result<Option<(DateTime<Utc>, DateTime<Utc>)>> {
+        let mut span: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+        for image in self.images()?.filter_map(|r| r.ok()) {
+            span = Some(match span {
+                Some((first, latest)) => (first.min(image.datetime), latest.max(image.datetime)),
+                None => (image.datetime, image.datetime),
+            });
+        }
+        Ok(span)
+    }
+
+    /// Returns the image whose datetime is closest to the target, or `None` if there are no images.
+    ///
+    /// If two images are equally close, the earlier one wins. Images that can't be read are
+    /// skipped, just like `Camera::latest_image`. The images are only walked once, and never
+    /// sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use glacio::Camera;
+    /// # fn main() {
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// let image = camera.image_nearest(Utc.ymd(2017, 1, 1).and_hms(0, 0, 0)).unwrap().unwrap();
+    /// assert_eq!(Utc.ymd(2017, 8, 6).and_hms(15, 25, 0), image.datetime());
+    /// # }
+    /// ```
+    pub fn image_nearest(&self, target: DateTime<Utc>) -> Result<Option<Image>> {
+        let mut nearest: Option<(chrono::Duration, Image)> = None;
+        for image in self.images()?.filter_map(|r| r.ok()) {
+            let distance = image.datetime.signed_duration_since(target).abs();
+            let is_nearer = match nearest {
+                Some((nearest_distance, ref nearest_image)) => {
+                    distance < nearest_distance ||
+                        (distance == nearest_distance && image.datetime < nearest_image.datetime)
+                }
+                None => true,
+            };
+            if is_nearer {
+                nearest = Some((distance, image));
+            }
+        }
+        Ok(nearest.map(|(_, image)| image))
+    }
+
+    /// Returns this camera's path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Camera;
+    /// let camera = Camera::new("data/ATLAS_CAM").unwrap();
+    /// let path = camera.path();
+    /// ```
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn has_image_extension(&self, path: &Path) -> bool {
+        has_extension(path, &self.extensions)
+    }
+
+    fn read_dir(&self) -> Result<ReadDir> {
+        self.path.read_dir().map_err(|err| with_path(err, &self.path))
+    }
+}
+
+/// Puts the path in an io error's message, so it's clear which file or directory had the problem.
+fn with_path(err: io::Error, path: &Path) -> Error {
+    Error::Io(io::Error::new(
+        err.kind(),
+        format!("{}: {}", path.display(), err),
+    ))
+}
+
+fn has_extension(path: &Path, extensions: &[OsString]) -> bool {
+    path.extension()
+        .map(|extension| extensions.iter().any(|lhs| lhs == extension))
+        .unwrap_or(false)
+}
+
+impl Iterator for Images {
+    type Item = Result<Image>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(result) = self.read_dir.next() {
+            match result {
+                Ok(dir_entry) => {
+                    let path = dir_entry.path();
+                    if has_extension(&path, &self.extensions) {
+                        return Some(Image::with_offset(path, self.offset));
+                    }
+                }
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+        None
+    }
+}
+
+impl Image {
+    /// Creates a new image from the path, which is canonicalized.
+    ///
+    /// The datetime can be separated from the camera name, and the date from the time, by either
+    /// `_` or `-`, as long as the same separator is used for both.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// # use glacio::Image;
+    /// let image = Image::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+    /// assert_eq!(
+    ///     Path::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").canonicalize().unwrap(),
+    ///     image.path()
+    /// );
+    /// let dashed = Image::new("data/DASHED_CAM/DASHED-CAM-20170806-152500.jpg").unwrap();
+    /// assert_eq!(image.datetime(), dashed.datetime());
+    /// ```
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Image> {
+        Image::with_offset(path, FixedOffset::east(0))
+    }
+
+    /// Creates a new image from the path, interpreting the filename's datetime in the provided
+    /// offset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use glacio::Image;
+    /// # use chrono::{FixedOffset, TimeZone, Utc};
+    /// # fn main() {
+    /// let offset = FixedOffset::west(3 * 3600);
+    /// let image = Image::with_offset("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", offset)
+    ///     .unwrap();
+    /// assert_eq!(Utc.ymd(2017, 8, 6).and_hms(18, 25, 0), image.datetime());
+    /// # }
+    /// ```
+    pub fn with_offset<P: AsRef<Path>>(path: P, offset: FixedOffset) -> Result<Image> {
+        let path = path.as_ref().canonicalize()?;
+        if let Some(file_stem) = path.file_stem() {
+            let file_stem = match file_stem.to_str() {
+                Some(file_stem) => file_stem,
+                None => return Err(Error::NonUtf8FileStem(path.clone())),
+            };
+            if file_stem.len() <= 15 {
+                Err(Error::FileStemTooShort(file_stem.to_string()))
+            } else {
+                let (name, s) = file_stem.split_at(file_stem.len() - 15);
+                let format = if s.as_bytes()[8] == b'-' {
+                    "%Y%m%d-%H%M%S"
+                } else {
+                    "%Y%m%d_%H%M%S"
+                };
+                match name.as_bytes()[name.len() - 1] {
+                    separator @ b'_' | separator @ b'-' if separator != s.as_bytes()[8] => {
+                        return Err(Error::MixedSeparators(file_stem.to_string()))
+                    }
+                    _ => {}
+                }
+                let naive = NaiveDateTime::parse_from_str(s, format)?;
+                Ok(Image {
+                    datetime: offset.from_local_datetime(&naive).unwrap().with_timezone(&Utc),
+                    offset: offset,
+                    path: path.clone(),
+                })
+            }
+        } else {
+            Err(Error::NoFileStem(path.clone()))
+        }
+    }
+
+    /// Returns this image's local filesystem path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Image;
+    /// let image = Image::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+    /// let path = image.path();
+    /// assert!(path.is_absolute());
+    /// assert_eq!("ATLAS_CAM_20170806_152500.jpg", path.file_name().unwrap());
+    /// ```
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns this image's datetime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use glacio::Image;
+    /// # use chrono::{Utc, TimeZone};
+    /// # fn main() {
+    /// let image = Image::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+    /// let datetime = image.datetime();
+    /// assert_eq!(Utc.ymd(2017, 8, 6).and_hms(15, 25, 0), datetime);
+    /// # }
+    /// ```
+    pub fn datetime(&self) -> DateTime<Utc> {
+        self.datetime
+    }
+
+    /// Returns this image's datetime in the offset that it was captured in.
+    ///
+    /// For images created with `Image::new`, this is the same as the UTC datetime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate chrono;
+    /// # extern crate glacio;
+    /// # use glacio::Image;
+    /// # use chrono::Utc;
+    /// # fn main() {
+    /// let image = Image::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+    /// assert_eq!(image.datetime(), image.datetime_local().with_timezone(&Utc));
+    /// # }
+    /// ```
+    pub fn datetime_local(&self) -> DateTime<FixedOffset> {
+        self.datetime.with_timezone(&self.offset)
+    }
+
+    /// Decodes this image and returns its mean luminance, from 0 (black) to 255 (white).
+    ///
+    /// This reads and decodes the whole file, so it's not cheap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Image;
+    /// let image = Image::new("data/DARK_CAM/DARK_CAM_20170806_030000.jpg").unwrap();
+    /// assert!(image.mean_luminance().unwrap() < 1.);
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn mean_luminance(&self) -> Result<f32> {
+        let luma = image::open(&self.path)?.to_luma();
+        let (width, height) = luma.dimensions();
+        let count = u64::from(width) * u64::from(height);
+        if count == 0 {
+            return Ok(0.);
+        }
+        let sum = luma.pixels().map(|pixel| u64::from(pixel.data[0])).sum::<u64>();
+        Ok(sum as f32 / count as f32)
+    }
+
+    /// Is this image's mean luminance below the threshold?
+    ///
+    /// Night frames are all (or nearly all) black, so a threshold of around 10 catches them
+    /// without catching dim daytime frames. See `Image::mean_luminance` for the scale.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::Image;
+    /// let image = Image::new("data/DARK_CAM/DARK_CAM_20170806_030000.jpg").unwrap();
+    /// assert!(image.is_dark(10.).unwrap());
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn is_dark(&self, threshold: f32) -> Result<bool> {
+        self.mean_luminance().map(|luminance| luminance < threshold)
+    }
+}
+
+impl PartialEq for Image {
+    /// Images are equal if they have the same datetime and path.
+    ///
+    /// The offset isn't compared, since an image with the same path and datetime must have been
+    /// read with the same offset.
+    fn eq(&self, other: &Image) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Image {}
+
+impl PartialOrd for Image {
+    fn partial_cmp(&self, other: &Image) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Image {
+    /// Images are ordered by datetime, and then by path.
+    ///
+    /// Two images with the same datetime and path are equal.
+    fn cmp(&self, other: &Image) -> Ordering {
+        self.datetime.cmp(&other.datetime).then_with(
+            || self.path.cmp(&other.path),
+        )
+    }
+}
+
+impl Server {
+    /// Creates a new server, defaulting to our lidar.io url as the remote base url.
+    ///
+    /// The server document root is canonicalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// # use glacio::camera::Server;
+    /// let server = Server::new("data").unwrap();
+    /// assert_eq!(Path::new("data").canonicalize().unwrap(), server.document_root());
+    /// ```
+    pub fn new<P: AsRef<Path>>(document_root: P) -> Result<Server> {
+        Ok(Server {
+            document_root: document_root.as_ref().canonicalize()?,
+            base_url: Url::parse(DEFAULT_SERVER_BASE_URL).unwrap(),
+        })
+    }
+
+    /// Creates a new server with a custom remote base url.
+    ///
+    /// The base url should end in a slash if it has a path, otherwise the last path segment will
+    /// be replaced when joining image paths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::camera::{Image, Server};
+    /// let server = Server::with_base_url("data", "http://mirror.example.com/cams/").unwrap();
+    /// let image = Image::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+    /// assert_eq!(
+    ///     "http://mirror.example.com/cams/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+    ///     server.url_for(&image).unwrap().as_str()
+    /// );
+    /// ```
+    pub fn with_base_url<P: AsRef<Path>>(document_root: P, base_url: &str) -> Result<Server> {
+        Ok(Server {
+            document_root: document_root.as_ref().canonicalize()?,
+            base_url: Url::parse(base_url)?,
+        })
+    }
+
+    /// Returns the url for the provided image.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::camera::{Image, Server};
+    /// let image = Image::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+    /// let server = Server::new("data").unwrap();
+    /// let url = server.url_for(&image).unwrap();
+    /// assert_eq!("http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+    ///            url.as_str());
+    /// ```
+    pub fn url_for(&self, image: &Image) -> Result<Url> {
+        let input = image.path().strip_prefix(&self.document_root)?;
+        self.base_url.join(&input.to_string_lossy()).map_err(
+            Error::from,
+        )
+    }
+
+    /// Returns this server's document root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use glacio::camera::Server;
+    /// let server = Server::new("data").unwrap();
+    /// let document_root = server.document_root();
+    /// assert!(document_root.is_absolute());
+    /// assert_eq!("data", document_root.file_name().unwrap());
+    /// ```
+    pub fn document_root(&self) -> &Path {
+        &self.document_root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camera::fixture::{self, FixtureSpec};
+    use chrono::Duration;
+
+    #[test]
+    fn new_camera() {
+        Camera::new("data/ATLAS_CAM").unwrap();
+    }
+
+    #[test]
+    fn camera_images() {
+        let camera = Camera::new("data/ATLAS_CAM").unwrap();
+        let images = camera.images().unwrap();
+        assert_eq!(1, images.count());
+
+        let mut images = camera.images().unwrap();
+        let image = images.next().unwrap().unwrap();
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(15, 25, 0), image.datetime);
+    }
+
+    #[test]
+    fn io_errors_include_path() {
+        use std::fs;
+        use test_util::TempDir;
+
+        let err = Camera::new("data/NOT_A_CAMERA").unwrap_err();
+        assert!(err.to_string().starts_with("data/NOT_A_CAMERA: "));
+
+        let dir = TempDir::new("camera-io-errors").unwrap();
+        let camera = Camera::new(&dir).unwrap();
+        fs::remove_dir(&dir).unwrap();
+        let prefix = format!("{}: ", camera.path().display());
+        match camera.images().unwrap_err() {
+            Error::Io(ref err) => {
+                assert_eq!(io::ErrorKind::NotFound, err.kind());
+                assert!(err.to_string().starts_with(&prefix));
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+        assert!(
+            camera
+                .images_with_errors()
+                .unwrap_err()
+                .to_string()
+                .starts_with(&prefix)
+        );
+    }
+
+    #[test]
+    fn camera_images_recursive() {
+        let camera = Camera::new("data/NESTED_CAM").unwrap();
+        let count = |max_depth| camera.images_recursive(max_depth).unwrap().len();
+        assert_eq!(1, camera.images().unwrap().count());
+        assert_eq!(1, count(0));
+        assert_eq!(1, count(2));
+        assert_eq!(3, count(3));
+        assert_eq!(3, count(10));
+        let latest = camera.images_recursive(3).unwrap().into_iter().max().unwrap();
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(15, 0, 0), latest.datetime());
+    }
+
+    #[test]
+    fn camera_with_offset() {
+        let offset = FixedOffset::east(2 * 3600);
+        let camera = Camera::with_offset("data/ATLAS_CAM", offset).unwrap();
+        let image = camera.images().unwrap().next().unwrap().unwrap();
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(13, 25, 0), image.datetime());
+        assert_eq!(&offset, image.datetime_local().offset());
+        let (images, _) = camera.images_with_errors().unwrap();
+        assert_eq!(image, images[0]);
+    }
+
+    #[test]
+    fn camera_images_with_errors() {
+        let camera = Camera::new("data/MISNAMED_CAM").unwrap();
+        let (images, skipped) = camera.images_with_errors().unwrap();
+        assert_eq!(1, images.len());
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(15, 25, 0), images[0].datetime);
+        assert_eq!(1, skipped.len());
+        assert_eq!("MISNAMED.jpg", skipped[0].0.file_name().unwrap());
+        match skipped[0].1 {
+            Error::FileStemTooShort(_) => {}
+            ref err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn camera_images_with_errors_not_a_directory() {
+        let camera = Camera::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+        assert!(camera.images_with_errors().is_err());
+    }
+
+    #[test]
+    fn image_separators() {
+        let dashed = Image::new("data/DASHED_CAM/DASHED-CAM-20170806-152500.jpg").unwrap();
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(15, 25, 0), dashed.datetime());
+        let underscored = Image::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+        assert_eq!(dashed.datetime(), underscored.datetime());
+
+        let camera = Camera::new("data/DASHED_CAM").unwrap();
+        let (images, skipped) = camera.images_with_errors().unwrap();
+        assert_eq!(1, images.len());
+        assert_eq!(1, skipped.len());
+        assert_eq!("DASHED-CAM_20170806-152500.jpg", skipped[0].0.file_name().unwrap());
+        match skipped[0].1 {
+            Error::MixedSeparators(_) => {}
+            ref err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn image_is_dark() {
+        let dark = Image::new("data/DARK_CAM/DARK_CAM_20170806_030000.jpg").unwrap();
+        assert_eq!(0., dark.mean_luminance().unwrap());
+        assert!(dark.is_dark(10.).unwrap());
+        assert!(!dark.is_dark(0.).unwrap());
+        let normal = Image::new("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").unwrap();
+        assert!(!normal.is_dark(10.).unwrap());
+        let empty = Image::new("data/MISNAMED_CAM/MISNAMED_CAM_20170806_152500.jpg").unwrap();
+        assert!(empty.mean_luminance().is_err());
+    }
+
+    #[test]
+    fn image_datetime_local() {
+        use chrono::{Datelike, Timelike};
+
+        let offset = FixedOffset::east(5 * 3600 + 30 * 60);
+        let image = Image::with_offset("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg", offset)
+            .unwrap();
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(9, 55, 0), image.datetime());
+        let local = image.datetime_local();
+        assert_eq!(&offset, local.offset());
+        assert_eq!(
+            (2017, 8, 6, 15, 25, 0),
+            (
+                local.year(),
+                local.month(),
+                local.day(),
+                local.hour(),
+                local.minute(),
+                local.second(),
+            )
+        );
+    }
+
+    #[test]
+    fn camera_images_desc() {
+        use test_util::TempDir;
+
+        let dir = TempDir::new("camera-images-desc").unwrap();
+        fixture::touch(
+            &dir,
+            &[
+                "B_20170806_152500.jpg",
+                "CAM_20170806_142500.jpg",
+                "A_20170806_152500.jpg",
+                "CAM_20170806_162500.jpg",
+            ],
+        ).unwrap();
+
+        let camera = Camera::new(&dir).unwrap();
+        let mut images = camera
+            .images()
+            .unwrap()
+            .map(|result| result.unwrap())
+            .collect::<Vec<_>>();
+        images.sort();
+        images.reverse();
+        let images_desc = camera.images_desc().unwrap();
+        assert_eq!(images, images_desc);
+        let file_names = images_desc
+            .iter()
+            .map(|image| image.path().file_name().unwrap().to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "CAM_20170806_162500.jpg",
+                "B_20170806_152500.jpg",
+                "A_20170806_152500.jpg",
+                "CAM_20170806_142500.jpg",
+            ],
+            file_names
+        );
+    }
+
+    #[test]
+    fn camera_image_nearest() {
+        use test_util::TempDir;
+
+        let dir = TempDir::new("camera-image-nearest").unwrap();
+        let spec = FixtureSpec::new(Utc.ymd(2017, 8, 6).and_hms(14, 25, 0), Duration::hours(1), 3)
+            .camera("CAM");
+        fixture::build(&dir, &spec).unwrap();
+
+        let camera = Camera::new(dir.join("CAM")).unwrap();
+        let nearest = |hour, minute| {
+            camera
+                .image_nearest(Utc.ymd(2017, 8, 6).and_hms(hour, minute, 0))
+                .unwrap()
+                .unwrap()
+                .datetime()
+        };
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(15, 25, 0), nearest(15, 40));
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(16, 25, 0), nearest(15, 56));
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(15, 25, 0), nearest(15, 55));
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(14, 25, 0), nearest(0, 0));
+        assert_eq!(Utc.ymd(2017, 8, 6).and_hms(16, 25, 0), nearest(23, 0));
+
+        let dir = TempDir::new("camera-image-nearest-empty").unwrap();
+        let camera = Camera::new(&dir).unwrap();
+        assert_eq!(None, camera.image_nearest(Utc::now()).unwrap());
+    }
+
+    #[test]
+    fn camera_latest_image_same_datetime() {
+        use test_util::TempDir;
+
+        let dir = TempDir::new("camera-latest-image-same-datetime").unwrap();
+        fixture::touch(&dir, &["B_20170806_152500.jpg", "A_20170806_152500.jpg"]).unwrap();
+
+        let camera = Camera::new(&dir).unwrap();
+        let latest_image = camera.latest_image().unwrap();
+        assert_eq!(
+            "B_20170806_152500.jpg",
+            latest_image.path().file_name().unwrap().to_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn camera_latest_and_oldest() {
+        use std::fs;
+        use test_util::TempDir;
+
+        let dir = TempDir::new("camera-latest-and-oldest").unwrap();
+        let camera_dir = dir.join("CAM");
+        fs::create_dir(&camera_dir).unwrap();
+        let camera = Camera::new(&camera_dir).unwrap();
+        assert_eq!(None, camera.latest().unwrap());
+        assert_eq!(None, camera.oldest().unwrap());
+        assert_eq!(None, camera.deployment_span().unwrap());
+
+        let spec = FixtureSpec::new(Utc.ymd(2017, 8, 5).and_hms(15, 25, 0), Duration::days(1), 3)
+            .camera("CAM");
+        fixture::build(&dir, &spec).unwrap();
+        fixture::touch(&camera_dir, &["CAM.jpg"]).unwrap();
+        let file_name = |image: Option<Image>| {
+            image.unwrap().path().file_name().unwrap().to_str().unwrap().to_string()
+        };
+        assert_eq!("CAM_20170807_152500.jpg", file_name(camera.latest().unwrap()));
+        assert_eq!("CAM_20170805_152500.jpg", file_name(camera.oldest().unwrap()));
+        assert_eq!(
+            Some((
+                Utc.ymd(2017, 8, 5).and_hms(15, 25, 0),
+                Utc.ymd(2017, 8, 7).and_hms(15, 25, 0),
+            )),
+            camera.deployment_span().unwrap()
+        );
+
+        fs::remove_dir_all(&camera_dir).unwrap();
+        assert!(camera.latest().is_err());
+        assert!(camera.oldest().is_err());
+        assert_eq!(None, camera.latest_image());
+    }
+
+    #[test]
+    fn image_ordering() {
+        use test_util::TempDir;
+
+        let dir = TempDir::new("image-ordering").unwrap();
+        let file_names = [
+            "C_20170806_152500.jpg",
+            "A_20170806_182500.jpg",
+            "B_20170806_152500.jpg",
+            "A_20170805_000000.jpg",
+            "A_20170806_152500.jpg",
+            "C_20170806_182500.jpg",
+        ];
+        fixture::touch(&dir, &file_names).unwrap();
+        let image = |file_name: &str| Image::new(dir.join(file_name)).unwrap();
+
+        // Every rotation of the input sorts to the same order.
+        let mut expected = None;
+        for rotation in 0..file_names.len() {
+            let mut images = file_names
+                .iter()
+                .cycle()
+                .skip(rotation)
+                .take(file_names.len())
+                .map(|file_name| image(file_name))
+                .collect::<Vec<_>>();
+            images.sort();
+            for window in images.windows(2) {
+                assert!(window[0].datetime() <= window[1].datetime());
+                if window[0].datetime() == window[1].datetime() {
+                    assert!(window[0].path() < window[1].path());
+                }
+            }
+            let paths = images
+                .iter()
+                .map(|image| image.path().to_path_buf())
+                .collect::<Vec<_>>();
+            if let Some(ref expected) = expected {
+                assert_eq!(expected, &paths);
+            }
+            expected = Some(paths);
+        }
+
+        let a = image("A_20170806_152500.jpg");
+        assert_eq!(Ordering::Equal, a.cmp(&image("A_20170806_152500.jpg")));
+        assert_eq!(a, image("A_20170806_152500.jpg"));
+        assert_eq!(Ordering::Less, a.cmp(&image("B_20170806_152500.jpg")));
+        assert_eq!(Ordering::Greater, a.cmp(&image("A_20170805_000000.jpg")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn camera_images_non_utf8_file_name() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use test_util::TempDir;
+
+        let dir = TempDir::new("camera-non-utf8").unwrap();
+        fixture::touch(
+            &dir,
+            &[
+                OsStr::new("CAM_20170806_152500.jpg"),
+                OsStr::from_bytes(b"CAM_\xff0170806_152500.jpg"),
+            ],
+        ).unwrap();
+
+        let camera = Camera::new(&dir).unwrap();
+        let results = camera.images().unwrap().collect::<Vec<_>>();
+        assert_eq!(2, results.len());
+        assert_eq!(1, results.iter().filter(|r| r.is_ok()).count());
+        let (images, skipped) = camera.images_with_errors().unwrap();
+        assert_eq!(1, images.len());
+        match skipped[0].1 {
+            Error::NonUtf8FileStem(_) => {}
+            ref err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn server_url() {
+        let server = Server::new("data").unwrap();
+        let camera = Camera::new("data/ATLAS_CAM").unwrap();
+        let image = camera.images().unwrap().next().unwrap().unwrap();
+        let url = server.url_for(&image).unwrap();
+        assert_eq!(
+            "http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+            url.as_str()
+        );
+    }
+
+    #[test]
+    fn server_url_subdirectory() {
+        let server = Server::new(Path::new("data").canonicalize().unwrap()).unwrap();
+        let camera = Camera::new("data/HEL_BERGCAM3/StarDot1").unwrap();
+        let image = camera.images().unwrap().next().unwrap().unwrap();
+        let url = server.url_for(&image).unwrap();
+        assert_eq!(
+            "http://iridiumcam.lidar.io/HEL_BERGCAM3/StarDot1/HEL_BERGCAM3_StarDot1_20170825_120000.jpg",
+            url.as_str()
+        );
+    }
+
+    #[test]
+    fn server_url_mixing_absolute_and_relative() {
+        let server = Server::new("data").unwrap();
+        let camera = Camera::new("data/ATLAS_CAM").unwrap();
+        let image = camera.images().unwrap().next().unwrap().unwrap();
+        let url = server.url_for(&image).unwrap();
+        assert_eq!(
+            "http://iridiumcam.lidar.io/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg",
+            url.as_str()
+        );
+    }
+}
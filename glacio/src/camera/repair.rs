@@ -0,0 +1,261 @@
+//! Find, and suggest fixes for, image files that don't follow our naming convention.
+//!
+//! Images are expected to be named `NAME_%Y%m%d_%H%M%S.jpg`, where `NAME` is the name of the
+//! camera's directory and the datetime is in UTC. Some cameras use dashes throughout instead, e.g.
+//! `NAME-%Y%m%d-%H%M%S.jpg`, which is just as good. A lot of "missing" images are actually present
+//! but misnamed, e.g. with an upper case extension, without the underscores, or with a local
+//! timestamp and a UTC offset. This module guesses the intended name for those files.
+//!
+//! Nothing in here touches the filesystem except `diagnose`, which only reads a directory.
+//! Renaming is left to the caller.
+//!
+//! # Examples
+//!
+//! ```
+//! use glacio::camera::repair;
+//! assert_eq!(
+//!     Some("ATLAS_CAM_20170806_152500.jpg".to_string()),
+//!     repair::suggest_file_name("ATLAS_CAM", "ATLAS_CAM_20170806152500.JPG")
+//! );
+//! ```
+
+use camera::{Error, Result};
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+const DATETIME_FORMAT: &'static str = "%Y%m%d_%H%M%S";
+const DASHED_DATETIME_FORMAT: &'static str = "%Y%m%d-%H%M%S";
+
+lazy_static! {
+    static ref CANONICAL: Regex = Regex::new(
+        r"^(?P<name>.+)(?P<separator>[_-])(?P<datetime>[0-9]{8}[_-][0-9]{6})\.jpg$"
+    ).unwrap();
+    static ref FUZZY: Regex = Regex::new(r"(?xi)
+        (?P<year>[0-9]{4})[-_]?(?P<month>[0-9]{2})[-_]?(?P<day>[0-9]{2})
+        [-_T\x20]?
+        (?P<hour>[0-9]{2})[-_:]?(?P<minute>[0-9]{2})[-_:]?(?P<second>[0-9]{2})
+        (?P<offset>[-+][0-9]{4})?
+        [^.]*\.jpe?g$").unwrap();
+}
+
+/// A file in a camera directory whose name isn't canonical.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnosis {
+    /// The path to the misnamed file.
+    pub path: PathBuf,
+    /// The path that the file should probably be renamed to, if we could guess one.
+    pub suggestion: Option<PathBuf>,
+}
+
+/// Returns true if the file name is canonical for the named camera.
+///
+/// Dashed file names are canonical too, as long as every separator is a dash. The camera name in
+/// a dashed file name may have its underscores replaced by dashes as well.
+///
+/// # Examples
+///
+/// ```
+/// use glacio::camera::repair;
+/// assert!(repair::is_canonical("ATLAS_CAM", "ATLAS_CAM_20170806_152500.jpg"));
+/// assert!(repair::is_canonical("ATLAS_CAM", "ATLAS-CAM-20170806-152500.jpg"));
+/// assert!(!repair::is_canonical("ATLAS_CAM", "ATLAS-CAM_20170806-152500.jpg"));
+/// assert!(!repair::is_canonical("ATLAS_CAM", "ATLAS_CAM_20170806_152500.JPG"));
+/// assert!(!repair::is_canonical("ATLAS_CAM", "OTHER_CAM_20170806_152500.jpg"));
+/// ```
+pub fn is_canonical(camera_name: &str, file_name: &str) -> bool {
+    match CANONICAL.captures(file_name) {
+        Some(captures) => {
+            let (format, name_matches) = if &captures["separator"] == "-" {
+                (
+                    DASHED_DATETIME_FORMAT,
+                    captures["name"] == *camera_name ||
+                        captures["name"] == camera_name.replace('_', "-"),
+                )
+            } else {
+                (DATETIME_FORMAT, captures["name"] == *camera_name)
+            };
+            name_matches && NaiveDateTime::parse_from_str(&captures["datetime"], format).is_ok()
+        }
+        None => false,
+    }
+}
+
+/// Guesses the canonical file name for a misnamed file.
+///
+/// Returns `None` if the file name is already canonical, or if no datetime can be found in it. If
+/// the file name includes a UTC offset after the time, e.g. `-0800`, the datetime is converted to
+/// UTC.
+///
+/// # Examples
+///
+/// ```
+/// use glacio::camera::repair;
+/// assert_eq!(
+///     Some("ATLAS_CAM_20170806_232500.jpg".to_string()),
+///     repair::suggest_file_name("ATLAS_CAM", "ATLAS_CAM_20170806_152500-0800.jpg")
+/// );
+/// assert_eq!(None, repair::suggest_file_name("ATLAS_CAM", "ATLAS_CAM_20170806_152500.jpg"));
+/// assert_eq!(None, repair::suggest_file_name("ATLAS_CAM", "notes.txt"));
+/// ```
+pub fn suggest_file_name(camera_name: &str, file_name: &str) -> Option<String> {
+    if is_canonical(camera_name, file_name) {
+        return None;
+    }
+    let captures = FUZZY.captures(file_name)?;
+    let number = |name: &str| captures[name].parse::<u32>().ok();
+    let year = captures["year"].parse::<i32>().ok()?;
+    let naive = NaiveDate::from_ymd_opt(year, number("month")?, number("day")?)?
+        .and_hms_opt(number("hour")?, number("minute")?, number("second")?)?;
+    let datetime = match captures.name("offset") {
+        Some(offset) => {
+            let offset = offset.as_str();
+            let hours = offset.get(1..3)?.parse::<i32>().ok()?;
+            let minutes = offset.get(3..5)?.parse::<i32>().ok()?;
+            let seconds = hours * 3600 + minutes * 60;
+            let offset = if offset.starts_with('-') {
+                FixedOffset::west_opt(seconds)?
+            } else {
+                FixedOffset::east_opt(seconds)?
+            };
+            offset
+                .from_local_datetime(&naive)
+                .single()?
+                .with_timezone(&Utc)
+                .naive_utc()
+        }
+        None => naive,
+    };
+    Some(format!(
+        "{}_{}.jpg",
+        camera_name,
+        datetime.format(DATETIME_FORMAT)
+    ))
+}
+
+/// Returns a diagnosis for every file in the camera directory whose name isn't canonical.
+///
+/// The camera name is the name of the directory. Subdirectories are ignored, and the diagnoses
+/// are sorted by path.
+///
+/// # Examples
+///
+/// ```
+/// use glacio::camera::repair;
+/// let diagnoses = repair::diagnose("data/MISNAMED_CAM").unwrap();
+/// assert_eq!(1, diagnoses.len());
+/// assert!(diagnoses[0].path.ends_with("MISNAMED.jpg"));
+/// assert_eq!(None, diagnoses[0].suggestion);
+/// ```
+pub fn diagnose<P: AsRef<Path>>(camera_path: P) -> Result<Vec<Diagnosis>> {
+    let camera_path = camera_path.as_ref();
+    let camera_name = match camera_path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return Err(Error::NonUtf8FileStem(camera_path.to_path_buf())),
+    };
+    let mut diagnoses = Vec::new();
+    for entry in camera_path.read_dir()? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let file_name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(file_name) => file_name.to_string(),
+            None => {
+                diagnoses.push(Diagnosis {
+                    path: path,
+                    suggestion: None,
+                });
+                continue;
+            }
+        };
+        if !is_canonical(camera_name, &file_name) {
+            let suggestion = suggest_file_name(camera_name, &file_name)
+                .map(|suggestion| path.with_file_name(suggestion));
+            diagnoses.push(Diagnosis {
+                path: path,
+                suggestion: suggestion,
+            });
+        }
+    }
+    diagnoses.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(diagnoses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical() {
+        assert!(is_canonical("ATLAS_CAM", "ATLAS_CAM_20170806_152500.jpg"));
+        assert!(is_canonical("CAM", "CAM_20170806_152500.jpg"));
+        assert!(!is_canonical("CAM", "CAM_20171306_152500.jpg"));
+        assert!(!is_canonical("CAM", "CAM_20170806_152500.jpeg"));
+        assert!(!is_canonical("CAM", "20170806_152500.jpg"));
+        assert!(!is_canonical("CAM", "CAM20170806_152500.jpg"));
+
+        assert!(is_canonical("CAM", "CAM-20170806-152500.jpg"));
+        assert!(is_canonical("DASHED_CAM", "DASHED-CAM-20170806-152500.jpg"));
+        assert!(is_canonical("DASHED_CAM", "DASHED_CAM-20170806-152500.jpg"));
+        assert!(!is_canonical("DASHED_CAM", "DASHED-CAM_20170806-152500.jpg"));
+        assert!(!is_canonical("CAM", "CAM-20170806_152500.jpg"));
+        assert!(!is_canonical("CAM", "CAM-20171306-152500.jpg"));
+        assert!(!is_canonical("CAM", "CAM_٢٠١٧٠٨٠٦_١٥٢٥٠٠.jpg"));
+    }
+
+    #[test]
+    fn suggestions() {
+        let cases = [
+            ("CAM_20170806_152500.JPG", Some("CAM_20170806_152500.jpg")),
+            ("CAM_20170806_152500.jpeg", Some("CAM_20170806_152500.jpg")),
+            ("CAM_20170806152500.jpg", Some("CAM_20170806_152500.jpg")),
+            ("CAM20170806_152500.jpg", Some("CAM_20170806_152500.jpg")),
+            ("20170806_152500.jpg", Some("CAM_20170806_152500.jpg")),
+            ("OTHER_CAM_20170806_152500.jpg", Some("CAM_20170806_152500.jpg")),
+            ("CAM_2017-08-06_15-25-00.jpg", Some("CAM_20170806_152500.jpg")),
+            ("CAM_2017-08-06T15:25:00.jpg", Some("CAM_20170806_152500.jpg")),
+            ("CAM 2017-08-06 152500.jpg", Some("CAM_20170806_152500.jpg")),
+            ("CAM_20170806_152500-0800.jpg", Some("CAM_20170806_232500.jpg")),
+            ("CAM_20170806_012500+0230.jpg", Some("CAM_20170805_225500.jpg")),
+            ("CAM_20170806_152500_small.jpg", Some("CAM_20170806_152500.jpg")),
+            ("CAM_20170806_152500.jpg", None),
+            ("CAM-20170806-152500.jpg", None),
+            ("CAM-20170806_152500.jpg", Some("CAM_20170806_152500.jpg")),
+            ("CAM_20171306_152500.jpg", None),
+            ("CAM_20170806_256000.jpg", None),
+            ("CAM.jpg", None),
+            ("CAM_20170806_152500.txt", None),
+            ("CAM_٢٠١٧٠٨٠٦_١٥٢٥٠٠.jpg", None),
+            ("CAM_２０１７０８０６_１５２５００.jpg", None),
+            ("CAM_20170806_152500+٠٨٠٠.jpg", Some("CAM_20170806_152500.jpg")),
+        ];
+        for &(file_name, expected) in &cases {
+            assert_eq!(
+                expected.map(|s| s.to_string()),
+                suggest_file_name("CAM", file_name),
+                "{}",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn diagnose_dashed() {
+        let diagnoses = diagnose("data/DASHED_CAM").unwrap();
+        assert_eq!(1, diagnoses.len());
+        assert!(diagnoses[0].path.ends_with("DASHED-CAM_20170806-152500.jpg"));
+        assert!(
+            diagnoses[0]
+                .suggestion
+                .as_ref()
+                .unwrap()
+                .ends_with("DASHED_CAM_20170806_152500.jpg")
+        );
+    }
+
+    #[test]
+    fn diagnose_not_a_directory() {
+        assert!(diagnose("data/ATLAS_CAM/ATLAS_CAM_20170806_152500.jpg").is_err());
+    }
+}
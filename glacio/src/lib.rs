@@ -17,13 +17,22 @@
         unused_qualifications)]
 
 extern crate chrono;
+#[cfg(feature = "http")]
+extern crate hyper;
+#[cfg(feature = "image")]
+extern crate image;
 #[macro_use]
 extern crate lazy_static;
+#[macro_use]
+extern crate log;
 extern crate regex;
 extern crate sbd;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[cfg(feature = "http")]
+#[macro_use]
+extern crate serde_json;
 extern crate url;
 
 #[macro_use]
@@ -32,5 +41,16 @@ mod macros;
 pub mod atlas;
 pub mod camera;
 pub mod sutron;
+#[cfg(test)]
+mod test_util;
 
 pub use camera::{Camera, Image};
+
+/// The version of this crate, as recorded in its `Cargo.toml`.
+///
+/// # Examples
+///
+/// ```
+/// assert!(!glacio::VERSION.is_empty());
+/// ```
+pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
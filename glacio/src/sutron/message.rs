@@ -3,22 +3,25 @@
 //! Contains its own error enum, because there's a variety of errors that can arise while parsing
 //! SBD messages sent by a Sutron system.
 
-use regex::Regex;
+use regex::{self, Captures, Regex};
 use std::error;
 use std::fmt::{self, Display, Formatter};
 use std::num::ParseIntError;
+use std::ops::Range;
 use std::result;
 use std::str::FromStr;
 
 lazy_static! {
-    static ref SELF_TIMED_EXTENDED_REGEX: Regex = Regex::new(r"(?sx)^
-        1,
-        (?P<id>\d+),
-        (?P<start_byte>\d+)
-        (,(?P<total_bytes>\d+))?:(?P<data>.*)
-        $").unwrap();
+    static ref SELF_TIMED_EXTENDED_REGEX: Regex =
+        self_timed_extended_regex(SUB_HEADER_TERMINATOR, SUB_HEADER_FIELD_SEPARATOR);
 }
 
+/// The byte that ends an extended packet's sub-header.
+pub const SUB_HEADER_TERMINATOR: u8 = b':';
+
+/// The byte between the fields of an extended packet's sub-header.
+pub const SUB_HEADER_FIELD_SEPARATOR: u8 = b',';
+
 /// An interleaved SBD message.
 ///
 /// In order to send a long text string over SBD, the Sutron data logger chops the message into
@@ -38,6 +41,8 @@ pub enum Message {
         ///
         /// As we add packets, we check to see if we hit/exceed the total bytes.
         total_bytes: usize,
+        /// The number of packets added so far, not counting duplicates.
+        packets: usize,
         /// The message so far.
         data: String,
     },
@@ -46,7 +51,7 @@ pub enum Message {
 }
 
 /// One SBD message's worth of information.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Packet {
     /// A self-timed message that fits in one packet.
     ///
@@ -82,6 +87,53 @@ pub enum Packet {
     ForcedTransmissionExtended(String),
 }
 
+/// Parses packets whose extended sub-headers use a particular pair of delimiters.
+///
+/// The parser compiles its regex once, so it's the way to parse many packets with non-standard
+/// delimiters.
+#[derive(Clone, Debug)]
+pub struct PacketParser {
+    self_timed_extended_regex: Regex,
+}
+
+/// Something suspicious about a packet that still parsed.
+///
+/// Warnings don't stop a packet from being added to a message, but they're worth a look when a
+/// message won't reassemble.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PacketWarning {
+    /// The first packet of an extended message claims a total that's zero, or smaller than the
+    /// packet's own data.
+    SuspiciousTotalBytes(usize),
+    /// The start byte is at or beyond the total that the same packet claims.
+    ///
+    /// Start bytes are offsets into the whole message, so later packets of a long message have
+    /// large start bytes. Only packets that carry their own total can be checked.
+    StartByteExceedsTotalBytes(usize),
+    /// The packet has no data.
+    EmptyData,
+}
+
+/// The type of a packet, as determined by the packet's first byte.
+///
+/// Each type has two type bytes, one for packets that fit in a single SBD transmission and one for
+/// extended packets. The type does not distinguish between the two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    /// A self-timed transmission, e.g. an ATLAS heartbeat.
+    SelfTimed,
+    /// The data logger is entering an alarm state.
+    EnteringAlarm,
+    /// The data logger is exiting an alarm state.
+    ExitingAlarm,
+    /// A response to a command sent to the data logger.
+    CommandResponse,
+    /// A forced transmission, usually a test message.
+    ForcedTransmission,
+    /// A type byte that is reserved by Sutron.
+    Reserved(u8),
+}
+
 /// A custom error enum for reconstruction Sutron messages.
 #[derive(Debug)]
 pub enum Error {
@@ -101,8 +153,26 @@ pub enum Error {
     },
     /// The packet is in an invalid format.
     InvalidFormat(String),
+    /// The packet type name is not recognized.
+    InvalidTypeName(String),
+    /// A numeric field in the packet's sub-header could not be parsed.
+    InvalidSubHeaderField {
+        /// The name of the sub-header field, e.g. "start_byte".
+        field: &'static str,
+        /// The value of the field, as received.
+        value: String,
+    },
     /// The message is complete, and cannot accept any more packets.
     MessageComplete,
+    /// The packets ran out before the message was complete.
+    MessageIncomplete,
+    /// The XOR checksum of the reassembled message data doesn't match the expected checksum.
+    MessageChecksumMismatch {
+        /// The checksum that was expected.
+        expected: u8,
+        /// The checksum of the reassembled data.
+        actual: u8,
+    },
     /// The initial packet is missing the total bytes field.
     MissingTotalBytes,
     /// A non-extended packet was added to an incomplete message.
@@ -134,6 +204,41 @@ impl Default for Message {
     }
 }
 
+impl Display for Message {
+    /// Formats a one-line summary of the message, without its data.
+    ///
+    /// A complete message doesn't keep its id or packet count, so it prints `None` for them.
+    /// Datetimes belong to the SBD messages that carried the packets, so they aren't included.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Message::Unstarted => write!(f, "Message(id=None, bytes=0, total=None, packets=0)"),
+            Message::Incomplete {
+                id,
+                total_bytes,
+                packets,
+                ref data,
+            } => {
+                write!(
+                    f,
+                    "Message(id={}, bytes={}, total={}, packets={})",
+                    id,
+                    data.len(),
+                    total_bytes,
+                    packets
+                )
+            }
+            Message::Complete(ref data) => {
+                write!(
+                    f,
+                    "Message(id=None, bytes={}, total={}, packets=None)",
+                    data.len(),
+                    data.len()
+                )
+            }
+        }
+    }
+}
+
 impl Message {
     /// Creates a new, unstarted message.
     ///
@@ -150,7 +255,9 @@ impl Message {
 
     /// Adds a packet, as a string, to this message.
     ///
-    /// The message is consumed, and a new message is returned from the function.
+    /// The message is consumed, and a new message is returned from the function. An extended
+    /// packet whose data is already in the message at its start byte, e.g. because the same SBD
+    /// message was read twice, is ignored.
     ///
     /// # Examples
     ///
@@ -163,10 +270,14 @@ impl Message {
     /// assert_eq!("A self timed message", String::from(message));
     /// ```
     pub fn add(self, payload: &str) -> Result<Message> {
-        match (self, payload.parse::<Packet>()?) {
-            (Message::Unstarted, Packet::SelfTimed(data)) => {
-                Ok(Message::Complete(data.to_string()))
-            }
+        let packet = payload.parse::<Packet>()?;
+        trace!("adding packet {} with {} bytes of data", packet, packet.data_len());
+        let warnings = packet.warnings();
+        if !warnings.is_empty() {
+            debug!("packet {} has warnings: {:?}", packet, warnings);
+        }
+        match (self, packet) {
+            (Message::Unstarted, Packet::SelfTimed(data)) => Ok(Message::Complete(data)),
             (Message::Unstarted,
              Packet::SelfTimedExtended {
                  id,
@@ -177,9 +288,11 @@ impl Message {
                 if start_byte != 0 {
                     Err(Error::NonzeroStartByte)
                 } else if let Some(total_bytes) = total_bytes {
+                    debug!("started message {}, expecting {} bytes", id, total_bytes);
                     Ok(Message::Incomplete {
                         id: id,
                         total_bytes: total_bytes,
+                        packets: 1,
                         data: data,
                     })
                 } else {
@@ -192,6 +305,7 @@ impl Message {
             (Message::Incomplete {
                  id,
                  total_bytes,
+                 packets,
                  data,
              },
              Packet::SelfTimedExtended {
@@ -201,34 +315,206 @@ impl Message {
                  ..
              }) => {
                 if packet_id != id {
+                    debug!("packet id {} does not match message id {}", packet_id, id);
                     Err(Error::IdMismatch {
                         packet: packet_id,
                         message: id,
                     })
+                } else if is_repeated(&data, start_byte, &packet_data) {
+                    debug!(
+                        "ignoring duplicate packet for bytes {}..{} of message {}",
+                        start_byte,
+                        start_byte + packet_data.len(),
+                        id
+                    );
+                    Ok(Message::Incomplete {
+                        id: id,
+                        total_bytes: total_bytes,
+                        packets: packets,
+                        data: data,
+                    })
                 } else if start_byte != data.len() {
+                    debug!(
+                        "message {} has {} bytes, but the packet starts at byte {}",
+                        id,
+                        data.len(),
+                        start_byte
+                    );
                     Err(Error::ByteMismatch {
                         received: data.len(),
                         start_byte: start_byte,
                     })
                 } else {
                     let data = data + &packet_data;
+                    let packets = packets + 1;
                     if data.len() == total_bytes {
+                        debug!("completed message {} with {} bytes", id, total_bytes);
                         Ok(Message::Complete(data))
                     } else {
+                        trace!(
+                            "message {} has {} of {} bytes",
+                            id,
+                            data.len(),
+                            total_bytes
+                        );
                         Ok(Message::Incomplete {
                             id: id,
                             total_bytes: total_bytes,
+                            packets: packets,
                             data: data,
                         })
                     }
                 }
             }
             (Message::Complete(_), _) => Err(Error::MessageComplete),
-            (_, Packet::ForcedTransmission(message)) => Ok(Message::Complete(message)),
+            (_, Packet::ForcedTransmission(message)) |
             (_, Packet::ForcedTransmissionExtended(message)) => Ok(Message::Complete(message)),
         }
     }
 
+    /// Returns the number of packets in this message so far, not counting duplicates.
+    ///
+    /// A complete message doesn't keep its packet count, so it returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::Message;
+    /// let message = Message::from_chunks(42, &["Hello", ", "]).unwrap();
+    /// assert_eq!(Some(2), message.packets());
+    /// assert_eq!(Some(0), Message::new().packets());
+    /// ```
+    pub fn packets(&self) -> Option<usize> {
+        match *self {
+            Message::Unstarted => Some(0),
+            Message::Incomplete { packets, .. } => Some(packets),
+            Message::Complete(_) => None,
+        }
+    }
+
+    /// Returns the id of an incomplete message.
+    ///
+    /// Unstarted messages don't have an id yet, and complete messages don't keep theirs, so they
+    /// return `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::Message;
+    /// let message = Message::new().add("1,42,0,8:abcd").unwrap();
+    /// assert_eq!(Some(42), message.id());
+    /// assert_eq!(None, message.add("1,42,4:efgh").unwrap().id());
+    /// ```
+    pub fn id(&self) -> Option<u8> {
+        match *self {
+            Message::Incomplete { id, .. } => Some(id),
+            Message::Unstarted |
+            Message::Complete(_) => None,
+        }
+    }
+
+    /// Creates a message from ordered chunks of data, without needing pre-built packets.
+    ///
+    /// Each chunk becomes a self-timed extended packet with the provided id. The first packet
+    /// carries the total byte count, and the message is assembled and validated just as if the
+    /// packets had been received over SBD. An empty slice of chunks produces an unstarted message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::Message;
+    /// let message = Message::from_chunks(42, &["Hello", ", ", "world!"]).unwrap();
+    /// assert!(message.is_complete());
+    /// assert_eq!("Hello, world!", String::from(message));
+    /// ```
+    pub fn from_chunks(id: u8, chunks: &[&str]) -> Result<Message> {
+        let total_bytes = chunks.iter().map(|chunk| chunk.len()).sum::<usize>();
+        let mut start_byte = 0;
+        let mut message = Message::new();
+        for chunk in chunks {
+            let packet = if start_byte == 0 {
+                format!("1,{},{},{}:{}", id, start_byte, total_bytes, chunk)
+            } else {
+                format!("1,{},{}:{}", id, start_byte, chunk)
+            };
+            message = message.add(&packet)?;
+            start_byte += chunk.len();
+        }
+        Ok(message)
+    }
+
+    /// Creates a complete message from data that fits in one packet of the given type.
+    ///
+    /// This is the single-packet counterpart to `from_chunks`, for tests and simulations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::Message;
+    /// use glacio::sutron::message::Type;
+    /// let message = Message::standalone("ATHB03313", Type::SelfTimed).unwrap();
+    /// assert!(message.is_complete());
+    /// assert_eq!("ATHB03313", String::from(message));
+    /// ```
+    pub fn standalone(data: &str, type_: Type) -> Result<Message> {
+        let packet = Packet::standalone(data, type_)?;
+        Message::new().add(&String::from_utf8(packet.to_bytes()).unwrap())
+    }
+
+    /// Creates a message from packets, then validates the XOR checksum of the reassembled data.
+    ///
+    /// This is for messages that carry one checksum for the whole message, rather than one per
+    /// packet. Returns `Error::MessageIncomplete` if the packets don't make a complete message, and
+    /// `Error::MessageChecksumMismatch` if the checksums don't match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::Message;
+    /// let packets = ["1,42,0,4:AB", "1,42,2:CD"];
+    /// let message = Message::new_checked(&packets, 0x04).unwrap();
+    /// assert_eq!("ABCD", String::from(message));
+    /// assert!(Message::new_checked(&packets, 0x05).is_err());
+    /// ```
+    pub fn new_checked(packets: &[&str], expected_checksum: u8) -> Result<Message> {
+        let mut message = Message::new();
+        for packet in packets {
+            message = message.add(packet)?;
+        }
+        if !message.is_complete() {
+            return Err(Error::MessageIncomplete);
+        }
+        let actual = message.checksum();
+        if actual == expected_checksum {
+            Ok(message)
+        } else {
+            Err(Error::MessageChecksumMismatch {
+                expected: expected_checksum,
+                actual: actual,
+            })
+        }
+    }
+
+    /// Returns the XOR of every byte of this message's data, so far.
+    ///
+    /// An unstarted message has a checksum of zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::Message;
+    /// let message = Message::new().add("0AB").unwrap();
+    /// assert_eq!(b'A' ^ b'B', message.checksum());
+    /// ```
+    pub fn checksum(&self) -> u8 {
+        let data = match *self {
+            Message::Unstarted => return 0,
+            Message::Incomplete { ref data, .. } |
+            Message::Complete(ref data) => data,
+        };
+        data.bytes().fold(0, |checksum, byte| checksum ^ byte)
+    }
+
     /// Is this message complete?
     ///
     /// # Examples
@@ -249,6 +535,303 @@ impl Message {
     }
 }
 
+impl Packet {
+    /// Parses a packet with the standard sub-header delimiters.
+    ///
+    /// This is the same as parsing the packet with `FromStr`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::Packet;
+    /// let packet = Packet::new("1,7,0,10:ATHB0").unwrap();
+    /// assert_eq!(Some(0..5), packet.byte_range());
+    /// ```
+    pub fn new(s: &str) -> Result<Packet> {
+        parse_packet(s, &SELF_TIMED_EXTENDED_REGEX)
+    }
+
+    /// Parses a packet whose extended sub-header uses different delimiters.
+    ///
+    /// Some Sutron firmware ends the sub-header with a semicolon instead of a colon. The delimiters
+    /// only matter for self-timed extended packets, since no other packet has a sub-header.
+    ///
+    /// Non-standard delimiters need their own regex, which is built on every call. To parse many
+    /// packets with the same delimiters, build a `PacketParser` once and reuse it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::Packet;
+    /// let packet = Packet::new_with_delimiters("1,7,0,10;ATHB0", b';', b',').unwrap();
+    /// assert_eq!(Packet::new("1,7,0,10:ATHB0").unwrap(), packet);
+    /// ```
+    pub fn new_with_delimiters(s: &str, terminator: u8, field_sep: u8) -> Result<Packet> {
+        PacketParser::new(terminator, field_sep).parse(s)
+    }
+
+    /// Creates a packet that holds all of the data on its own, without a sub-header.
+    ///
+    /// Only self-timed and forced transmission packets are supported, since those are the only
+    /// types that can be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::{Packet, Type};
+    /// let packet = Packet::standalone("test", Type::ForcedTransmission).unwrap();
+    /// assert_eq!(b"8test".to_vec(), packet.to_bytes());
+    /// assert!(Packet::standalone("test", Type::CommandResponse).is_err());
+    /// ```
+    pub fn standalone(data: &str, type_: Type) -> Result<Packet> {
+        match type_ {
+            Type::SelfTimed => Ok(Packet::SelfTimed(data.to_string())),
+            Type::ForcedTransmission => Ok(Packet::ForcedTransmission(data.to_string())),
+            _ => Err(Error::UnsupportedPacketType(type_.to_string())),
+        }
+    }
+
+    /// Returns this packet's type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::{Packet, Type};
+    /// let packet: Packet = "0ATHB03313".parse().unwrap();
+    /// assert_eq!(Type::SelfTimed, packet.type_());
+    /// ```
+    pub fn type_(&self) -> Type {
+        match *self {
+            Packet::SelfTimed(_) |
+            Packet::SelfTimedExtended { .. } => Type::SelfTimed,
+            Packet::ForcedTransmission(_) |
+            Packet::ForcedTransmissionExtended(_) => Type::ForcedTransmission,
+        }
+    }
+
+    /// Returns the raw type byte that this packet was (or would be) transmitted with.
+    ///
+    /// Unlike `type_`, this distinguishes between single and extended packets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::Packet;
+    /// let packet: Packet = "1,7,0,10:ATHB0".parse().unwrap();
+    /// assert_eq!(b'1', packet.type_byte());
+    /// ```
+    pub fn type_byte(&self) -> u8 {
+        match *self {
+            Packet::SelfTimed(_) => b'0',
+            Packet::SelfTimedExtended { .. } => b'1',
+            Packet::ForcedTransmission(_) => b'8',
+            Packet::ForcedTransmissionExtended(_) => b'9',
+        }
+    }
+
+    /// Returns this packet's on-wire form: the type byte, the sub-header (if any), and the data.
+    ///
+    /// Parsing the returned bytes produces an equal packet. Padding around sub-header fields is
+    /// not preserved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::Packet;
+    /// let packet: Packet = "1,7,0,10:ATHB0".parse().unwrap();
+    /// assert_eq!(b"1,7,0,10:ATHB0".to_vec(), packet.to_bytes());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.type_byte()];
+        match *self {
+            Packet::SelfTimed(ref data) |
+            Packet::ForcedTransmission(ref data) |
+            Packet::ForcedTransmissionExtended(ref data) => bytes.extend(data.as_bytes()),
+            Packet::SelfTimedExtended {
+                id,
+                start_byte,
+                total_bytes,
+                ref data,
+            } => {
+                let sub_header = match total_bytes {
+                    Some(total_bytes) => format!(",{},{},{}:", id, start_byte, total_bytes),
+                    None => format!(",{},{}:", id, start_byte),
+                };
+                bytes.extend(sub_header.as_bytes());
+                bytes.extend(data.as_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Do this packet's bytes overlap with the other packet's bytes?
+    ///
+    /// Only self-timed extended packets know where their bytes belong in a message, so this is
+    /// always false if either packet is some other kind of packet. Packet ids are not compared,
+    /// which makes this useful for spotting retransmits or mixed-up messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::Packet;
+    /// let first: Packet = "1,7,0,10:ATHB0".parse().unwrap();
+    /// let retransmit: Packet = "1,7,3:B03313".parse().unwrap();
+    /// let second: Packet = "1,7,5:3313".parse().unwrap();
+    /// assert!(first.overlaps_with(&retransmit));
+    /// assert!(!first.overlaps_with(&second));
+    /// ```
+    pub fn overlaps_with(&self, other: &Packet) -> bool {
+        match (self.byte_range(), other.byte_range()) {
+            (Some(a), Some(b)) => {
+                a.start < a.end && b.start < b.end && a.start < b.end && b.start < a.end
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the range of bytes that this packet covers in its reassembled message.
+    ///
+    /// Only self-timed extended packets have a sub-header with a start byte, so every other packet
+    /// type returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::Packet;
+    /// let first: Packet = "1,42,0,8:abcd".parse().unwrap();
+    /// assert_eq!(Some(0..4), first.byte_range());
+    /// let second: Packet = "1,42,4:efgh".parse().unwrap();
+    /// assert_eq!(Some(4..8), second.byte_range());
+    /// let packet: Packet = "0abcd".parse().unwrap();
+    /// assert_eq!(None, packet.byte_range());
+    /// ```
+    pub fn byte_range(&self) -> Option<Range<usize>> {
+        match *self {
+            Packet::SelfTimedExtended {
+                start_byte,
+                ref data,
+                ..
+            } => Some(start_byte..start_byte + data.len()),
+            _ => None,
+        }
+    }
+
+    /// Is this an extended packet of message `id` whose data is already in `data`?
+    ///
+    /// This is the same check that `Message::add` uses to ignore duplicate packets. Since a
+    /// complete message won't take any more packets, readers use it to drop a repeat of the packet
+    /// that completed the last message, instead of starting a new message with it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::Packet;
+    /// let packet: Packet = "1,42,4:ef".parse().unwrap();
+    /// assert!(packet.is_duplicate_of(42, "abcdef"));
+    /// assert!(!packet.is_duplicate_of(43, "abcdef"));
+    /// assert!(!packet.is_duplicate_of(42, "abcdxy"));
+    /// ```
+    pub fn is_duplicate_of(&self, id: u8, data: &str) -> bool {
+        match *self {
+            Packet::SelfTimedExtended {
+                id: packet_id,
+                start_byte,
+                data: ref packet_data,
+                ..
+            } => packet_id == id && is_repeated(data, start_byte, packet_data),
+            _ => false,
+        }
+    }
+
+    /// Returns the non-fatal problems with this packet.
+    ///
+    /// A packet with warnings parses and can be added to a message like any other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::{Packet, PacketWarning};
+    /// let packet: Packet = "1,42,0,0:".parse().unwrap();
+    /// assert_eq!(
+    ///     vec![PacketWarning::SuspiciousTotalBytes(0), PacketWarning::EmptyData],
+    ///     packet.warnings()
+    /// );
+    /// let packet: Packet = "0ATHB03313".parse().unwrap();
+    /// assert!(packet.warnings().is_empty());
+    /// ```
+    pub fn warnings(&self) -> Vec<PacketWarning> {
+        let mut warnings = Vec::new();
+        if let Packet::SelfTimedExtended {
+            start_byte,
+            total_bytes,
+            ref data,
+            ..
+        } = *self
+        {
+            if let Some(total_bytes) = total_bytes {
+                if total_bytes == 0 || total_bytes < data.len() {
+                    warnings.push(PacketWarning::SuspiciousTotalBytes(total_bytes));
+                } else if start_byte >= total_bytes {
+                    warnings.push(PacketWarning::StartByteExceedsTotalBytes(start_byte));
+                }
+            }
+        }
+        if self.data_len() == 0 {
+            warnings.push(PacketWarning::EmptyData);
+        }
+        warnings
+    }
+
+    fn data_len(&self) -> usize {
+        match *self {
+            Packet::SelfTimed(ref data) |
+            Packet::SelfTimedExtended { ref data, .. } |
+            Packet::ForcedTransmission(ref data) |
+            Packet::ForcedTransmissionExtended(ref data) => data.len(),
+        }
+    }
+}
+
+impl PacketParser {
+    /// Creates a parser for sub-headers that end with `terminator` and have fields separated by
+    /// `field_sep`.
+    ///
+    /// Any ASCII byte can be a delimiter, including regex metacharacters and whitespace. Packets
+    /// are strings, so a byte of 0x80 or above is taken to mean the character with that code
+    /// point, e.g. `0xe9` is `é`, which is two bytes in the packet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::{Packet, PacketParser};
+    /// let parser = PacketParser::new(b';', b',');
+    /// let packet = parser.parse("1,7,0,10;ATHB0").unwrap();
+    /// assert_eq!(Packet::new("1,7,0,10:ATHB0").unwrap(), packet);
+    /// ```
+    pub fn new(terminator: u8, field_sep: u8) -> PacketParser {
+        PacketParser { self_timed_extended_regex: self_timed_extended_regex(terminator, field_sep) }
+    }
+
+    /// Parses a packet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::PacketParser;
+    /// let parser = PacketParser::default();
+    /// let packet = parser.parse("1,7,0,10:ATHB0").unwrap();
+    /// ```
+    pub fn parse(&self, s: &str) -> Result<Packet> {
+        parse_packet(s, &self.self_timed_extended_regex)
+    }
+}
+
+impl Default for PacketParser {
+    fn default() -> PacketParser {
+        PacketParser { self_timed_extended_regex: SELF_TIMED_EXTENDED_REGEX.clone() }
+    }
+}
+
 impl From<Packet> for String {
     fn from(packet: Packet) -> String {
         match packet {
@@ -260,32 +843,155 @@ impl From<Packet> for String {
     }
 }
 
+impl Display for Packet {
+    /// Formats the packet's type and, for extended packets, its sub header, but not its data.
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.type_())?;
+        if let Packet::SelfTimedExtended {
+            id,
+            start_byte,
+            total_bytes,
+            ..
+        } = *self
+        {
+            write!(f, " [id={}, off={}", id, start_byte)?;
+            if let Some(total_bytes) = total_bytes {
+                write!(f, ", total={}", total_bytes)?;
+            }
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
 impl FromStr for Packet {
     type Err = Error;
     fn from_str(s: &str) -> Result<Packet> {
-        match &s[0..1] {
-            "0" => Ok(Packet::SelfTimed(s[1..].to_string())),
-            "1" => {
-                if let Some(ref captures) = SELF_TIMED_EXTENDED_REGEX.captures(s) {
-                    Ok(Packet::SelfTimedExtended {
-                        id: captures.name("id").unwrap().as_str().parse()?,
-                        start_byte: captures.name("start_byte").unwrap().as_str().parse()?,
-                        total_bytes: captures.name("total_bytes").map_or(Ok(None), |s| {
-                            s.as_str().parse().map(Some)
-                        })?,
-                        data: captures.name("data").unwrap().as_str().to_string(),
-                    })
+        Packet::new(s)
+    }
+}
+
+impl From<u8> for Type {
+    fn from(n: u8) -> Type {
+        match n {
+            b'0' | b'1' => Type::SelfTimed,
+            b'2' | b'3' => Type::EnteringAlarm,
+            b'4' | b'5' => Type::ExitingAlarm,
+            b'6' | b'7' => Type::CommandResponse,
+            b'8' | b'9' => Type::ForcedTransmission,
+            _ => Type::Reserved(n),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Type::SelfTimed => write!(f, "self-timed"),
+            Type::EnteringAlarm => write!(f, "entering-alarm"),
+            Type::ExitingAlarm => write!(f, "exiting-alarm"),
+            Type::CommandResponse => write!(f, "command-response"),
+            Type::ForcedTransmission => write!(f, "forced-transmission"),
+            Type::Reserved(n) => write!(f, "reserved(0x{:02X})", n),
+        }
+    }
+}
+
+impl FromStr for Type {
+    type Err = Error;
+
+    /// Parses a type from its display name, ignoring case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glacio::sutron::message::Type;
+    /// assert_eq!(Type::SelfTimed, "Self-Timed".parse().unwrap());
+    /// assert_eq!(Type::Reserved(0x3A), "reserved(0x3A)".parse().unwrap());
+    /// ```
+    fn from_str(s: &str) -> Result<Type> {
+        let lowercase = s.to_lowercase();
+        match lowercase.as_str() {
+            "self-timed" => Ok(Type::SelfTimed),
+            "entering-alarm" => Ok(Type::EnteringAlarm),
+            "exiting-alarm" => Ok(Type::ExitingAlarm),
+            "command-response" => Ok(Type::CommandResponse),
+            "forced-transmission" => Ok(Type::ForcedTransmission),
+            _ => {
+                if lowercase.starts_with("reserved(0x") && lowercase.ends_with(')') {
+                    u8::from_str_radix(&lowercase[11..lowercase.len() - 1], 16)
+                        .map(Type::Reserved)
+                        .map_err(|_| Error::InvalidTypeName(s.to_string()))
                 } else {
-                    Err(Error::InvalidFormat(s.to_string()))
+                    Err(Error::InvalidTypeName(s.to_string()))
                 }
             }
-            "8" => Ok(Packet::ForcedTransmission(s[1..].to_string())),
-            "9" => Ok(Packet::ForcedTransmissionExtended(s[1..].to_string())),
-            c => Err(Error::UnsupportedPacketType(c.to_string())),
         }
     }
 }
 
+/// Parses a packet, using this regex for self-timed extended packets.
+fn parse_packet(s: &str, self_timed_extended_regex: &Regex) -> Result<Packet> {
+    match &s[0..1] {
+        "0" => Ok(Packet::SelfTimed(s[1..].to_string())),
+        "1" => {
+            if let Some(ref captures) = self_timed_extended_regex.captures(s) {
+                Ok(Packet::SelfTimedExtended {
+                    id: parse_sub_header_field(captures, "id")?,
+                    start_byte: parse_sub_header_field(captures, "start_byte")?,
+                    total_bytes: if captures.name("total_bytes").is_some() {
+                        Some(parse_sub_header_field(captures, "total_bytes")?)
+                    } else {
+                        None
+                    },
+                    data: captures.name("data").unwrap().as_str().to_string(),
+                })
+            } else {
+                Err(Error::InvalidFormat(s.to_string()))
+            }
+        }
+        "8" => Ok(Packet::ForcedTransmission(s[1..].to_string())),
+        "9" => Ok(Packet::ForcedTransmissionExtended(s[1..].to_string())),
+        c => Err(Error::UnsupportedPacketType(c.to_string())),
+    }
+}
+
+/// Is `packet_data` already in `data`, starting at `start_byte`?
+fn is_repeated(data: &str, start_byte: usize, packet_data: &str) -> bool {
+    start_byte < data.len() &&
+        data.get(start_byte..).map_or(false, |rest| rest.starts_with(packet_data))
+}
+
+/// Builds the regex for a self-timed extended packet with these sub-header delimiters.
+///
+/// The pattern isn't built in verbose mode, because `regex::escape` leaves whitespace alone and a
+/// whitespace delimiter would be dropped from a verbose pattern.
+fn self_timed_extended_regex(terminator: u8, field_sep: u8) -> Regex {
+    let terminator = regex::escape(&(terminator as char).to_string());
+    let field_sep = regex::escape(&(field_sep as char).to_string());
+    Regex::new(&format!(
+        concat!(
+            r"(?s)^1{sep}",
+            r"(?P<id>[^{sep}{term}]*){sep}",
+            r"(?P<start_byte>[^{sep}{term}]*)",
+            r"({sep}(?P<total_bytes>[^{sep}{term}]*))?{term}(?P<data>.*)$"
+        ),
+        sep = field_sep,
+        term = terminator
+    )).unwrap()
+}
+
+/// Parses a sub-header field, ignoring any whitespace around the value.
+fn parse_sub_header_field<T: FromStr>(captures: &Captures, field: &'static str) -> Result<T> {
+    let value = captures.name(field).unwrap().as_str();
+    value.trim().parse().map_err(|_| {
+        Error::InvalidSubHeaderField {
+            field: field,
+            value: value.to_string(),
+        }
+    })
+}
+
 impl From<ParseIntError> for Error {
     fn from(err: ParseIntError) -> Error {
         Error::ParseInt(err)
@@ -299,10 +1005,16 @@ impl error::Error for Error {
                 "the number of bytes received does not match the start byte of the packet"
             }
             Error::IdMismatch { .. } => "the id of the packet and of the message do not match",
+            Error::InvalidSubHeaderField { .. } => "a sub-header field could not be parsed",
+            Error::InvalidTypeName(_) => "the packet type name is not recognized",
             Error::InvalidFormat(_) => {
                 "the packet has an invalid format (does not match the packet regular expression"
             }
             Error::MessageComplete => "tried adding a packet to an already-completed message",
+            Error::MessageIncomplete => "the packets did not make a complete message",
+            Error::MessageChecksumMismatch { .. } => {
+                "the checksum of the message data does not match the expected checksum"
+            }
             Error::MissingTotalBytes => {
                 "the total bytes field must be populated on an initial packet"
             }
@@ -342,7 +1054,20 @@ impl Display for Error {
                 write!(f, "packet id is {}, message id is {}", packet, message)
             }
             Error::InvalidFormat(ref s) => write!(f, "packet is an invalid format: {}", s),
+            Error::MessageChecksumMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "message checksum is {:#04x}, expected {:#04x}",
+                    actual,
+                    expected
+                )
+            }
+            Error::InvalidTypeName(ref s) => write!(f, "invalid packet type name: {}", s),
+            Error::InvalidSubHeaderField { field, ref value } => {
+                write!(f, "invalid sub-header field {}: {:?}", field, value)
+            }
             Error::MessageComplete |
+            Error::MessageIncomplete |
             Error::MissingTotalBytes |
             Error::NonExtendedContinuationPacket |
             Error::NonzeroStartByte => write!(f, "{}", self.description()),
@@ -361,6 +1086,32 @@ mod tests {
     const SELF_TIMED_EXTENDED_1: &'static str = include_str!("../../data/170801_000155.txt");
     const FORCED_TRANSMISSION: &'static str = include_str!("../../data/160719_193136.txt");
 
+    #[test]
+    fn message_new_checked() {
+        // 0x41 ^ 0x42 ^ 0x43 ^ 0x44 == 0x04
+        let packets = ["1,42,0,4:AB", "1,42,2:CD"];
+        let message = Message::new_checked(&packets, 0x04).unwrap();
+        assert!(message.is_complete());
+        assert_eq!(0x04, message.checksum());
+        match Message::new_checked(&packets, 0x05).unwrap_err() {
+            Error::MessageChecksumMismatch { expected, actual } => {
+                assert_eq!(0x05, expected);
+                assert_eq!(0x04, actual);
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+        for packets in &[&[][..], &["1,42,0,4:AB"][..]] {
+            match Message::new_checked(packets, 0x03).unwrap_err() {
+                Error::MessageIncomplete => {}
+                err => panic!("unexpected error: {}", err),
+            }
+        }
+        match Message::new_checked(&["1,42,1,4:AB"], 0).unwrap_err() {
+            Error::NonzeroStartByte => {}
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
     #[test]
     fn message_add_self_timed() {
         let mut message = Message::new();
@@ -380,6 +1131,322 @@ mod tests {
         assert!(message.add(SELF_TIMED_EXTENDED_1).is_err());
     }
 
+    #[test]
+    fn message_add_duplicate_packets() {
+        let mut message = Message::new();
+        for packet in &["1,42,0,8:abcd", "1,42,0,8:abcd", "1,42,4:ef", "1,42,4:ef"] {
+            message = message.add(packet).unwrap();
+            assert!(!message.is_complete());
+        }
+        assert_eq!("Message(id=42, bytes=6, total=8, packets=2)", message.to_string());
+        assert!(message.clone().add("1,42,4:xy").is_err());
+        message = message.add("1,42,6:gh").unwrap();
+        assert!(message.is_complete());
+        assert_eq!("abcdefgh", String::from(message));
+    }
+
+    #[test]
+    fn message_add_duplicate_final_packet() {
+        let message = Message::new().add("1,42,0,8:abcd").unwrap();
+        let id = message.id().unwrap();
+        let message = message.add("1,42,4:efgh").unwrap();
+        assert!(message.is_complete());
+        let data = String::from(message);
+
+        // A complete message takes no more packets, and a fresh message can't start with a
+        // continuation packet, so the repeat has to be recognized for what it is.
+        match Message::new().add("1,42,4:efgh").unwrap_err() {
+            Error::NonzeroStartByte => {}
+            err => panic!("unexpected error: {}", err),
+        }
+        let packet: Packet = "1,42,4:efgh".parse().unwrap();
+        assert!(packet.is_duplicate_of(id, &data));
+        let packet: Packet = "1,42,4:efgi".parse().unwrap();
+        assert!(!packet.is_duplicate_of(id, &data));
+        let packet: Packet = "1,43,4:efgh".parse().unwrap();
+        assert!(!packet.is_duplicate_of(id, &data));
+    }
+
+    #[test]
+    fn packet_warnings() {
+        let packet: Packet = SELF_TIMED_EXTENDED_0.parse().unwrap();
+        assert!(packet.warnings().is_empty());
+        let packet: Packet = SELF_TIMED_EXTENDED_1.parse().unwrap();
+        assert!(packet.warnings().is_empty());
+        let packet: Packet = "1,42,0,2:abcd".parse().unwrap();
+        assert_eq!(vec![PacketWarning::SuspiciousTotalBytes(2)], packet.warnings());
+        // The third packet of a long message starts well past the end of one SBD payload.
+        let packet: Packet = "1,42,1000:abcd".parse().unwrap();
+        assert!(packet.warnings().is_empty());
+        let packet: Packet = "1,42,8,4:abcd".parse().unwrap();
+        assert_eq!(
+            vec![PacketWarning::StartByteExceedsTotalBytes(8)],
+            packet.warnings()
+        );
+
+        let message = Message::new().add("1,42,0,0:").unwrap();
+        assert!(!message.is_complete());
+    }
+
+    #[test]
+    fn message_standalone() {
+        for &type_ in &[Type::SelfTimed, Type::ForcedTransmission] {
+            let message = Message::standalone(SELF_TIMED, type_).unwrap();
+            assert!(message.is_complete());
+            assert_eq!(SELF_TIMED, String::from(message));
+
+            let packet = Packet::standalone(SELF_TIMED, type_).unwrap();
+            assert_eq!(type_, packet.type_());
+            let bytes = packet.to_bytes();
+            let message = Message::new()
+                .add(::std::str::from_utf8(&bytes).unwrap())
+                .unwrap();
+            assert!(message.is_complete());
+            assert_eq!(SELF_TIMED, String::from(message));
+        }
+        assert!(Message::standalone(SELF_TIMED, Type::EnteringAlarm).is_err());
+    }
+
+    #[test]
+    fn message_display() {
+        let mut message = Message::new();
+        assert_eq!("Message(id=None, bytes=0, total=None, packets=0)", message.to_string());
+        message = message.add(SELF_TIMED_EXTENDED_0).unwrap();
+        assert_eq!("Message(id=15, bytes=329, total=354, packets=1)", message.to_string());
+        assert_eq!(Some(1), message.packets());
+        message = message.add(SELF_TIMED_EXTENDED_1).unwrap();
+        assert_eq!("Message(id=None, bytes=354, total=354, packets=None)", message.to_string());
+        assert_eq!(None, message.packets());
+    }
+
+    #[test]
+    fn message_from_chunks() {
+        let message = Message::from_chunks(3, &["ATHB03", "313\r\n", "data"]).unwrap();
+        assert!(message.is_complete());
+        assert_eq!("ATHB03313\r\ndata", String::from(message));
+    }
+
+    #[test]
+    fn message_many_packets_in_order() {
+        let chunks = (0..50)
+            .map(|i| format!("packet {:02} of 50\r\n", i))
+            .collect::<Vec<_>>();
+        let expected = chunks.concat();
+        let mut message = Message::new();
+        let mut start_byte = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert!(!message.is_complete());
+            let packet = if i == 0 {
+                format!("1,9,0,{}:{}", expected.len(), chunk)
+            } else {
+                format!("1,9,{}:{}", start_byte, chunk)
+            };
+            message = message.add(&packet).unwrap();
+            start_byte += chunk.len();
+        }
+        assert!(message.is_complete());
+        assert_eq!(expected, String::from(message));
+
+        let chunks = chunks.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            expected,
+            String::from(Message::from_chunks(9, &chunks).unwrap())
+        );
+    }
+
+    #[test]
+    fn packet_overlaps_with() {
+        let packet = |s: &str| s.parse::<Packet>().unwrap();
+        let first = packet("1,1,0,8:abcd");
+        assert!(first.overlaps_with(&first));
+        assert!(first.overlaps_with(&packet("1,2,3:defg")));
+        assert!(packet("1,2,3:defg").overlaps_with(&first));
+        assert!(!first.overlaps_with(&packet("1,1,4:efgh")));
+        assert!(!packet("1,1,4:efgh").overlaps_with(&first));
+        assert!(!first.overlaps_with(&packet("1,1,2:")));
+        assert!(!first.overlaps_with(&packet("0abcd")));
+        assert!(!packet("8abcd").overlaps_with(&first));
+    }
+
+    #[test]
+    fn packet_byte_range() {
+        let first = SELF_TIMED_EXTENDED_0.parse::<Packet>().unwrap();
+        let second = SELF_TIMED_EXTENDED_1.parse::<Packet>().unwrap();
+        let first_range = first.byte_range().unwrap();
+        let second_range = second.byte_range().unwrap();
+        assert_eq!(0, first_range.start);
+        assert_eq!(first_range.end, second_range.start);
+        let message = Message::new().add(SELF_TIMED_EXTENDED_0).unwrap();
+        assert_eq!(String::from(message).len(), first_range.end);
+        assert_eq!(Some(0..0), "1,255,0,0:".parse::<Packet>().unwrap().byte_range());
+        assert_eq!(None, FORCED_TRANSMISSION.parse::<Packet>().unwrap().byte_range());
+    }
+
+    #[test]
+    fn packet_round_trip() {
+        use std::str;
+
+        for payload in &[
+            SELF_TIMED,
+            SELF_TIMED_EXTENDED_0,
+            SELF_TIMED_EXTENDED_1,
+            FORCED_TRANSMISSION,
+            "9forced and extended",
+            "1,255,0,0:",
+        ]
+        {
+            let packet: Packet = payload.parse().unwrap();
+            let bytes = packet.to_bytes();
+            assert_eq!(payload.as_bytes(), bytes.as_slice());
+            assert_eq!(payload.as_bytes()[0], packet.type_byte());
+            assert_eq!(
+                packet,
+                str::from_utf8(&bytes).unwrap().parse::<Packet>().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn padded_sub_header_is_not_preserved() {
+        let packet: Packet = "1, 07 ,0,4:ATHB".parse().unwrap();
+        assert_eq!(b"1,7,0,4:ATHB".to_vec(), packet.to_bytes());
+    }
+
+    #[test]
+    fn padded_sub_header() {
+        match "1, 07 ,0,433:ATHB".parse::<Packet>().unwrap() {
+            Packet::SelfTimedExtended {
+                id,
+                start_byte,
+                total_bytes,
+                data,
+            } => {
+                assert_eq!(7, id);
+                assert_eq!(0, start_byte);
+                assert_eq!(Some(433), total_bytes);
+                assert_eq!("ATHB", data);
+            }
+            _ => panic!("Padded sub-header was not recognized as self-timed extended"),
+        }
+    }
+
+    #[test]
+    fn semicolon_terminated_sub_header() {
+        let colon = Packet::new(SELF_TIMED_EXTENDED_0).unwrap();
+        let semicolon = Packet::new_with_delimiters(
+            &SELF_TIMED_EXTENDED_0.replacen(':', ";", 1),
+            b';',
+            b',',
+        ).unwrap();
+        assert_eq!(colon, semicolon);
+        match semicolon {
+            Packet::SelfTimedExtended {
+                id,
+                start_byte,
+                total_bytes,
+                ..
+            } => {
+                assert_eq!(15, id);
+                assert_eq!(0, start_byte);
+                assert_eq!(Some(354), total_bytes);
+            }
+            _ => panic!("Semicolon-terminated sub-header was not recognized as extended"),
+        }
+    }
+
+    #[test]
+    fn regex_special_delimiters() {
+        let expected = Packet::new(SELF_TIMED_EXTENDED_0).unwrap();
+        let parser = PacketParser::new(b'|', b'.');
+        let packet = parser
+            .parse(&SELF_TIMED_EXTENDED_0.replacen(',', ".", 3).replacen(':', "|", 1))
+            .unwrap();
+        assert_eq!(expected, packet);
+        assert!(parser.parse("1x15x0x354|ATHB").is_err());
+        let parser = PacketParser::new(b']', b'^');
+        let packet = parser
+            .parse(&SELF_TIMED_EXTENDED_0.replacen(',', "^", 3).replacen(':', "]", 1))
+            .unwrap();
+        assert_eq!(expected, packet);
+    }
+
+    #[test]
+    fn non_ascii_delimiters() {
+        let expected = Packet::new(SELF_TIMED_EXTENDED_0).unwrap();
+        let parser = PacketParser::new(0xe9, b',');
+        let packet = parser.parse(&SELF_TIMED_EXTENDED_0.replacen(':', "é", 1)).unwrap();
+        assert_eq!(expected, packet);
+    }
+
+    #[test]
+    fn whitespace_delimiters() {
+        let expected = Packet::new(SELF_TIMED_EXTENDED_0).unwrap();
+        let parser = PacketParser::new(b' ', b'\t');
+        let packet = parser
+            .parse(&SELF_TIMED_EXTENDED_0.replacen(',', "\t", 3).replacen(':', " ", 1))
+            .unwrap();
+        assert_eq!(expected, packet);
+        assert!(parser.parse("1,15,0,354:ATHB").is_err());
+        let packet = Packet::new_with_delimiters(
+            &SELF_TIMED_EXTENDED_0.replacen(':', "\n", 1),
+            b'\n',
+            b',',
+        ).unwrap();
+        assert_eq!(expected, packet);
+    }
+
+    #[test]
+    fn invalid_sub_header_field() {
+        match "1,7,zero:ATHB".parse::<Packet>().unwrap_err() {
+            Error::InvalidSubHeaderField { field, value } => {
+                assert_eq!("start_byte", field);
+                assert_eq!("zero", value);
+            }
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn tilde_prefix_is_not_skipped() {
+        for payload in &["~~\u{ff}0ATHB03313", "~0ATHB03313"] {
+            match payload.parse::<Packet>().unwrap_err() {
+                Error::UnsupportedPacketType(ref s) => assert_eq!("~", s),
+                err => panic!("Unexpected error: {}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn type_display() {
+        assert_eq!("self-timed", Type::from(b'0').to_string());
+        assert_eq!("self-timed", Type::from(b'1').to_string());
+        assert_eq!("forced-transmission", Type::from(b'8').to_string());
+        assert_eq!("reserved(0x3A)", Type::from(0x3A).to_string());
+    }
+
+    #[test]
+    fn packet_display() {
+        let packet: Packet = "1,26,0,433:ATHB".parse().unwrap();
+        assert_eq!("self-timed [id=26, off=0, total=433]", packet.to_string());
+        let packet: Packet = "1,26,200:ATHB".parse().unwrap();
+        assert_eq!("self-timed [id=26, off=200]", packet.to_string());
+        let packet: Packet = "0ATHB03313".parse().unwrap();
+        assert_eq!("self-timed", packet.to_string());
+        let packet: Packet = FORCED_TRANSMISSION.parse().unwrap();
+        assert_eq!("forced-transmission", packet.to_string());
+    }
+
+    #[test]
+    fn type_round_trip() {
+        for n in 0..256 {
+            let type_ = Type::from(n as u8);
+            assert_eq!(type_, type_.to_string().parse().unwrap());
+            assert_eq!(type_, type_.to_string().to_uppercase().parse().unwrap());
+        }
+        assert!("not-a-type".parse::<Type>().is_err());
+        assert!("reserved(0xZZ)".parse::<Type>().is_err());
+    }
+
     #[test]
     fn forced_transmission() {
         match FORCED_TRANSMISSION.parse::<Packet>().unwrap() {
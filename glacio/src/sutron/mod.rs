@@ -17,3 +17,41 @@ where
 {
     Utc.datetime_from_str(s, DATETIME_FORMAT).map_err(E::from)
 }
+
+/// Returns true if the string is a well-formed IMEI.
+///
+/// A well-formed IMEI is exactly fifteen decimal digits, the last of which is a Luhn check digit
+/// over the first fourteen.
+///
+/// Iridium modems don't always follow the check digit rule, e.g. our ATLAS modems,
+/// 300234063556840 and 300234063909200, fail it. Don't use this to reject IMEIs from a
+/// configuration or from SBD messages, only to check IMEIs that are known to have a check digit.
+///
+/// # Examples
+///
+/// ```
+/// use glacio::sutron;
+/// assert!(sutron::is_valid_imei("490154203237518"));
+/// assert!(!sutron::is_valid_imei("490154203237517"));
+/// assert!(!sutron::is_valid_imei("49015420323751"));
+/// assert!(!sutron::is_valid_imei("49015420323751a"));
+/// // An Iridium IMEI without a check digit.
+/// assert!(!sutron::is_valid_imei("300234063556840"));
+/// ```
+pub fn is_valid_imei(imei: &str) -> bool {
+    if imei.len() != 15 || !imei.bytes().all(|byte| byte.is_ascii_digit()) {
+        return false;
+    }
+    let sum: u32 = imei.bytes()
+        .rev()
+        .map(|byte| u32::from(byte - b'0'))
+        .enumerate()
+        .map(|(i, digit)| if i % 2 == 1 {
+            let doubled = digit * 2;
+            if doubled > 9 { doubled - 9 } else { doubled }
+        } else {
+            digit
+        })
+        .sum();
+    sum % 10 == 0
+}
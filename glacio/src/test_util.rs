@@ -0,0 +1,95 @@
+//! Helpers shared by the unit tests of every module.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TEMP_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A fresh, empty directory under the system's temporary directory.
+///
+/// Each directory's name includes the process id and a counter, so tests running at the same time
+/// never share one. The directory and everything in it is removed when this value is dropped,
+/// even if the test panics.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a new temporary directory, with `name` in its name to make it easy to find.
+    pub fn new(name: &str) -> io::Result<TempDir> {
+        let path = env::temp_dir().join(format!(
+            "glacio-{}-{}-{}",
+            name,
+            process::id(),
+            TEMP_DIR_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path)?;
+        Ok(TempDir { path: path })
+    }
+
+    /// Returns this directory's path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Copies `data/<subpath>` to `<dir>/<subpath>`, letting `edit` change the bytes on the way.
+///
+/// Directories are created as needed.
+pub fn copy_sbd<F>(dir: &Path, subpath: &str, edit: F) -> io::Result<()>
+where
+    F: FnOnce(&mut Vec<u8>),
+{
+    let mut bytes = Vec::new();
+    File::open(Path::new("data").join(subpath))?.read_to_end(
+        &mut bytes,
+    )?;
+    edit(&mut bytes);
+    let to = dir.join(subpath);
+    fs::create_dir_all(to.parent().expect("subpath has a parent"))?;
+    File::create(to)?.write_all(&bytes)
+}
+
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_dir() {
+        let path = {
+            let a = TempDir::new("test-util-temp-dir").unwrap();
+            let b = TempDir::new("test-util-temp-dir").unwrap();
+            assert_ne!(a.path(), b.path());
+            assert!(a.is_dir());
+            File::create(a.join("file")).unwrap();
+            a.path().to_path_buf()
+        };
+        assert!(!path.exists());
+    }
+}
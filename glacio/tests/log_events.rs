@@ -0,0 +1,54 @@
+//! Checks the log events emitted while reading heartbeats.
+//!
+//! A logger is global to the whole process, so this lives in its own test binary rather than
+//! alongside the unit tests.
+
+extern crate glacio;
+extern crate log;
+
+use glacio::atlas::SbdSource;
+use log::{LogLevelFilter, LogMetadata, LogRecord};
+use std::cell::RefCell;
+
+thread_local!(static EVENTS: RefCell<Vec<String>> = RefCell::new(Vec::new()));
+
+struct Capture;
+
+impl log::Log for Capture {
+    fn enabled(&self, _: &LogMetadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &LogRecord) {
+        EVENTS.with(|events| events.borrow_mut().push(record.args().to_string()));
+    }
+}
+
+#[test]
+fn log_events() {
+    log::set_logger(|max_log_level| {
+        max_log_level.set(LogLevelFilter::Trace);
+        Box::new(Capture)
+    }).unwrap();
+    let heartbeats = SbdSource::new("data").iter().unwrap().collect::<Vec<_>>();
+    assert_eq!(3, heartbeats.len());
+    let events = EVENTS.with(|events| events.borrow().clone());
+    for expected in &[
+        "read sbd message from 300234063556840 with momsn",
+        "did not match the heartbeat format",
+        "adding packet self-timed [id=15, off=0, total=354] with 329 bytes of data",
+        "started message 15, expecting 354 bytes",
+        "adding packet self-timed [id=15, off=329] with 25 bytes of data",
+        "completed message 15 with 354 bytes",
+        "parsing a heartbeat from 354 bytes",
+        "heartbeat section soc1 is bytes",
+    ]
+    {
+        assert!(
+            events.iter().any(|event| event.contains(expected)),
+            "missing {:?} in {:#?}",
+            expected,
+            events
+        );
+    }
+}